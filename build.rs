@@ -0,0 +1,51 @@
+//! Records the git hash and the chromiumoxide version for --version-json
+
+use std::path::Path;
+use std::process::Command;
+
+fn main() {
+    let manifest_dir = std::env::var("CARGO_MANIFEST_DIR").unwrap_or_default();
+    let manifest_dir = Path::new(&manifest_dir);
+
+    if let Some(hash) = git_hash(manifest_dir) {
+        println!("cargo:rustc-env=WEB2PDF_GIT_HASH={}", hash);
+    }
+    if let Some(version) = locked_version(manifest_dir, "chromiumoxide") {
+        println!("cargo:rustc-env=WEB2PDF_CHROMIUMOXIDE_VERSION={}", version);
+    }
+
+    for file in [".git/HEAD", ".git/index", "Cargo.lock"] {
+        if manifest_dir.join(file).exists() {
+            println!("cargo:rerun-if-changed={}", file);
+        }
+    }
+    println!("cargo:rerun-if-changed=build.rs");
+}
+
+/// The commit of the checkout, or of the published crate (recorded by `cargo package`)
+fn git_hash(manifest_dir: &Path) -> Option<String> {
+    let output = Command::new("git")
+        .args(["rev-parse", "HEAD"])
+        .current_dir(manifest_dir)
+        .output()
+        .ok()
+        .filter(|output| output.status.success());
+    if let Some(output) = output {
+        let hash = String::from_utf8(output.stdout).ok()?;
+        return Some(hash.trim().to_string()).filter(|hash| !hash.is_empty());
+    }
+
+    let vcs_info = std::fs::read_to_string(manifest_dir.join(".cargo_vcs_info.json")).ok()?;
+    let vcs_info: serde_json::Value = serde_json::from_str(&vcs_info).ok()?;
+    vcs_info["git"]["sha1"].as_str().map(str::to_string)
+}
+
+/// The version of a package in Cargo.lock
+fn locked_version(manifest_dir: &Path, package: &str) -> Option<String> {
+    let lock = std::fs::read_to_string(manifest_dir.join("Cargo.lock")).ok()?;
+    let name = format!("name = \"{}\"", package);
+    let mut lines = lock.lines();
+    lines.find(|line| line.trim() == name)?;
+    let version = lines.next()?.trim().strip_prefix("version = ")?;
+    Some(version.trim_matches('"').to_string())
+}
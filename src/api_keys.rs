@@ -0,0 +1,181 @@
+use std::{
+    collections::HashSet,
+    path::Path,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use serde::Deserialize;
+
+use crate::Result;
+
+/// An entry of the --api-keys file
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ApiKeyEntry {
+    /// Identifies the key in logs and owns its jobs
+    name: String,
+    key: String,
+    requests_per_minute: Option<u32>,
+    max_jobs: Option<usize>,
+    #[serde(default)]
+    admin: bool,
+}
+
+/// A key that may use the server, see `serve --api-keys`
+pub struct ApiKey {
+    pub name: String,
+    /// May use the admin routes, e.g. `POST /admin/reload`
+    pub admin: bool,
+    key: String,
+    requests_per_minute: Option<u32>,
+    max_jobs: Option<usize>,
+    /// Tokens left for conversions and when they were last refilled
+    bucket: Mutex<(f64, Instant)>,
+    /// Conversions of the key that are queued or running
+    active: AtomicUsize,
+}
+
+pub struct ApiKeys {
+    keys: Vec<Arc<ApiKey>>,
+}
+
+impl ApiKeys {
+    /// Reads the keys of a JSON file, an array like
+    /// `[{"name": "ci", "key": "...", "requests_per_minute": 60, "max_jobs": 4}]`
+    ///
+    /// # Arguments
+    /// * `path` - The file
+    /// * `requests_per_minute` - The rate limit of keys without their own
+    /// * `max_jobs` - The limit of queued and running conversions of keys without their own
+    pub fn load(
+        path: &Path,
+        requests_per_minute: Option<u32>,
+        max_jobs: Option<usize>,
+    ) -> Result<ApiKeys> {
+        let entries: Vec<ApiKeyEntry> = serde_json::from_slice(&std::fs::read(path)?)?;
+        let mut names = HashSet::new();
+        let mut keys = HashSet::new();
+        for entry in &entries {
+            if entry.key.trim().len() < 16 {
+                return Err(
+                    format!("the key of {:?} is shorter than 16 characters", entry.name).into(),
+                );
+            }
+            if !names.insert(entry.name.as_str()) {
+                return Err(format!("the name {:?} is used twice", entry.name).into());
+            }
+            if !keys.insert(entry.key.trim()) {
+                return Err(format!("the key of {:?} is used twice", entry.name).into());
+            }
+        }
+        if entries.is_empty() {
+            return Err("the file has no keys".into());
+        }
+        let keys = entries
+            .into_iter()
+            .map(|entry| {
+                let requests_per_minute = entry.requests_per_minute.or(requests_per_minute);
+                Arc::new(ApiKey {
+                    name: entry.name,
+                    admin: entry.admin,
+                    key: entry.key.trim().to_string(),
+                    requests_per_minute,
+                    max_jobs: entry.max_jobs.or(max_jobs),
+                    bucket: Mutex::new((
+                        requests_per_minute.unwrap_or_default() as f64,
+                        Instant::now(),
+                    )),
+                    active: AtomicUsize::new(0),
+                })
+            })
+            .collect();
+        Ok(ApiKeys { keys })
+    }
+
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    /// The key matching `key`, compared in constant time so keys can't be guessed by timing
+    pub fn find(&self, key: &str) -> Option<Arc<ApiKey>> {
+        let mut found = None;
+        for candidate in &self.keys {
+            if constant_time_eq(candidate.key.as_bytes(), key.as_bytes()) {
+                found = Some(Arc::clone(candidate));
+            }
+        }
+        found
+    }
+}
+
+impl ApiKey {
+    /// Takes a request from the rate limit of the key
+    ///
+    /// # Returns
+    /// An error with the time until the next request is allowed if the limit is reached
+    pub fn check_rate(&self) -> std::result::Result<(), Duration> {
+        let Some(per_minute) = self.requests_per_minute else {
+            return Ok(());
+        };
+        let capacity = per_minute as f64;
+        let per_second = capacity / 60.0;
+        let mut bucket = self.bucket.lock().unwrap();
+        let now = Instant::now();
+        let (tokens, refilled) = &mut *bucket;
+        *tokens =
+            (*tokens + now.duration_since(*refilled).as_secs_f64() * per_second).min(capacity);
+        *refilled = now;
+        if *tokens >= 1.0 {
+            *tokens -= 1.0;
+            Ok(())
+        } else if per_second > 0.0 {
+            Err(Duration::from_secs_f64((1.0 - *tokens) / per_second))
+        } else {
+            Err(Duration::from_secs(60))
+        }
+    }
+
+    /// Counts a conversion against the job limit of the key until the returned guard is dropped
+    ///
+    /// # Returns
+    /// None if the key already has its maximum of queued and running conversions
+    pub fn start_job(self: &Arc<Self>) -> Option<KeyJob> {
+        let max_jobs = self.max_jobs.unwrap_or(usize::MAX);
+        self.active
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |active| {
+                (active < max_jobs).then_some(active + 1)
+            })
+            .ok()?;
+        Some(KeyJob {
+            key: Arc::clone(self),
+        })
+    }
+
+    pub fn max_jobs(&self) -> Option<usize> {
+        self.max_jobs
+    }
+}
+
+/// A queued or running conversion of a key, see `ApiKey::start_job`
+pub struct KeyJob {
+    key: Arc<ApiKey>,
+}
+impl Drop for KeyJob {
+    fn drop(&mut self) {
+        self.key.active.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter()
+        .zip(b)
+        .fold(0, |difference, (a, b)| difference | (a ^ b))
+        == 0
+}
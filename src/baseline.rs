@@ -0,0 +1,82 @@
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use crate::{summary::JobSummary, Result, URLPathPair};
+
+/// Content hashes of pages to compare against, see `--alert-if-changed`
+/// Stored as a JSON object of page keys (see `Baseline::key`) and hashes.
+#[derive(Debug)]
+pub struct Baseline {
+    path: PathBuf,
+    hashes: BTreeMap<String, String>,
+}
+
+impl Baseline {
+    /// Loads a baseline, a missing file is an empty baseline
+    ///
+    /// # Arguments
+    /// * `path` - The JSON file of the baseline
+    pub fn load(path: &Path) -> Result<Baseline> {
+        let hashes = match std::fs::read_to_string(path) {
+            Ok(contents) => serde_json::from_str(&contents)?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => BTreeMap::new(),
+            Err(e) => return Err(e.into()),
+        };
+        Ok(Baseline {
+            path: path.to_path_buf(),
+            hashes,
+        })
+    }
+
+    /// The key of a page, its URL with the viewport width of a breakpoint
+    pub fn key(pair: &URLPathPair) -> String {
+        match pair.viewport_width {
+            Some(width) => format!("{} @{}px", pair.url, width),
+            None => pair.url.clone(),
+        }
+    }
+
+    /// Whether a page changed since the baseline
+    ///
+    /// # Returns
+    /// None if the page is not in the baseline
+    pub fn changed(&self, key: &str, hash: &str) -> Option<bool> {
+        self.hashes.get(key).map(|baseline| baseline != hash)
+    }
+
+    /// Adds the pages missing from the baseline and saves it.
+    /// Changed pages keep their old hash, so they are reported until removed from the baseline.
+    ///
+    /// # Arguments
+    /// * `pairs` - The URL-Path pairs of the jobs
+    /// * `jobs` - The finished jobs, in the order of `pairs`
+    ///
+    /// # Returns
+    /// The number of added pages
+    pub fn save_new(&self, pairs: &[URLPathPair], jobs: &[JobSummary]) -> Result<usize> {
+        let mut hashes = self.hashes.clone();
+        let mut added = 0;
+        for (pair, job) in pairs.iter().zip(jobs) {
+            let Some(hash) = job
+                .outcome
+                .as_ref()
+                .ok()
+                .and_then(|stats| stats.content_hash.clone())
+            else {
+                continue;
+            };
+            if let std::collections::btree_map::Entry::Vacant(entry) =
+                hashes.entry(Baseline::key(pair))
+            {
+                entry.insert(hash);
+                added += 1;
+            }
+        }
+        if added > 0 {
+            std::fs::write(&self.path, serde_json::to_string_pretty(&hashes)?)?;
+        }
+        Ok(added)
+    }
+}
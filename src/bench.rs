@@ -0,0 +1,211 @@
+use std::{sync::Arc, time::Duration, time::Instant};
+
+use clap::Args;
+use futures::future::join_all;
+use tracing::{info, warn};
+
+use chromiumoxide::cdp::browser_protocol::network::ClearBrowserCacheParams;
+use web2pdf_lib::Browser;
+
+use crate::{pdf_tab, resolve_url, scheduler::StageLimits, Cli, Result, URLPathPair};
+
+#[derive(Args, Debug, Clone)]
+pub struct BenchArgs {
+    #[clap(help = "URL to benchmark")]
+    pub url: String,
+
+    #[clap(
+        long,
+        help = "Number of conversions per mode and concurrency level",
+        default_value_t = 10
+    )]
+    pub iterations: usize,
+
+    #[clap(
+        long,
+        help = "Clear the browser cache before every batch of conversions",
+        default_value_t = false
+    )]
+    pub clear_cache: bool,
+
+    #[clap(
+        long,
+        value_delimiter = ',',
+        default_value = "1",
+        help = "Concurrency levels to compare, e.g. 1,4,8"
+    )]
+    pub concurrency: Vec<usize>,
+
+    #[clap(
+        long,
+        help = "Only benchmark the mode selected by --mono instead of comparing standard and mono",
+        default_value_t = false
+    )]
+    pub single_mode: bool,
+}
+
+/// Repeatedly converts the same URL and prints the timing distribution of every phase
+/// for each mode (standard/mono) and concurrency level
+///
+/// # Arguments
+/// * `cli` - The cli, used for all conversion options
+/// * `browser` - The browser
+/// * `args` - The benchmark arguments
+pub async fn run_bench(cli: &Cli, browser: &Arc<Browser>, args: &BenchArgs) -> Result<()> {
+    let url = resolve_url(&args.url);
+    let modes = if args.single_mode {
+        vec![cli.mono_page]
+    } else {
+        vec![false, true]
+    };
+
+    println!(
+        "Benchmark of {} ({} iterations{})",
+        url,
+        args.iterations,
+        if args.clear_cache {
+            ", cache cleared"
+        } else {
+            ""
+        }
+    );
+
+    for mono_page in modes {
+        for &concurrency in &args.concurrency {
+            let concurrency = concurrency.max(1);
+            let mut job_cli = cli.clone();
+            job_cli.mono_page = mono_page;
+            job_cli.url_path_pairs = (0..concurrency)
+                .map(|slot| URLPathPair {
+                    url: url.clone(),
+                    path: std::env::temp_dir().join(format!(
+                        "web2pdf-bench-{}-{}.pdf",
+                        std::process::id(),
+                        slot
+                    )),
+                    viewport_width: None,
+                    options: serde_json::Map::new(),
+                })
+                .collect();
+            let job_cli = Arc::new(job_cli);
+            let stages = Arc::new(
+                StageLimits::new(
+                    cli.max_navigating.map(|max| max as usize),
+                    cli.max_printing.map(|max| max as usize),
+                )
+                .with_bandwidth(cli.max_bandwidth, concurrency),
+            );
+
+            info!(
+                "Benchmarking {} mode with concurrency {}",
+                mode_name(mono_page),
+                concurrency
+            );
+
+            let mut phases: Vec<(&'static str, Vec<Duration>)> = Vec::new();
+            let mut totals = Vec::new();
+            let mut failed = 0;
+            let start = Instant::now();
+            let mut remaining = args.iterations;
+            while remaining > 0 {
+                let batch = remaining.min(concurrency);
+                remaining -= batch;
+
+                if args.clear_cache {
+                    clear_browser_cache(browser).await?;
+                }
+
+                let tasks = (0..batch).map(|slot| {
+                    let cli = Arc::clone(&job_cli);
+                    let browser = Arc::clone(browser);
+                    let stages = Arc::clone(&stages);
+                    tokio::spawn(async move {
+                        let start = Instant::now();
+                        pdf_tab(&cli, &browser, &stages, None, slot)
+                            .await
+                            .map(|stats| (stats, start.elapsed()))
+                            .map_err(|e| e.to_string())
+                    })
+                });
+                for result in join_all(tasks).await {
+                    match result.expect("PDF task panicked") {
+                        Ok((stats, total)) => {
+                            totals.push(total);
+                            for (name, duration) in stats.phases {
+                                match phases.iter_mut().find(|(phase, _)| *phase == name) {
+                                    Some((_, samples)) => samples.push(duration),
+                                    None => phases.push((name, vec![duration])),
+                                }
+                            }
+                        }
+                        Err(e) => {
+                            warn!("Benchmark conversion failed: {}", e);
+                            failed += 1;
+                        }
+                    }
+                }
+            }
+            let wall = start.elapsed();
+
+            for pair in &job_cli.url_path_pairs {
+                let _ = tokio::fs::remove_file(&pair.path).await;
+            }
+
+            println!(
+                "\n{}, concurrency {}: {} ok, {} failed, {:.2}s wall, {:.2} conversions/s",
+                mode_name(mono_page),
+                concurrency,
+                totals.len(),
+                failed,
+                wall.as_secs_f64(),
+                totals.len() as f64 / wall.as_secs_f64()
+            );
+            println!(
+                "  {:<10} {:>8} {:>8} {:>8} {:>8} {:>8}",
+                "PHASE", "MIN", "MEDIAN", "MEAN", "P95", "MAX"
+            );
+            for (name, samples) in phases.iter_mut().chain([("total", totals)].iter_mut()) {
+                print_distribution(name, samples);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Clears the cache of the browser using a temporary page
+async fn clear_browser_cache(browser: &Browser) -> Result<()> {
+    let page = browser.new_page("about:blank").await?;
+    page.execute(ClearBrowserCacheParams::default()).await?;
+    page.close().await?;
+    Ok(())
+}
+
+fn mode_name(mono_page: bool) -> &'static str {
+    if mono_page {
+        "mono"
+    } else {
+        "standard"
+    }
+}
+
+/// Prints min, median, mean, 95th percentile and max of the samples in seconds
+fn print_distribution(name: &str, samples: &mut [Duration]) {
+    if samples.is_empty() {
+        return;
+    }
+    samples.sort();
+    let seconds = |duration: Duration| duration.as_secs_f64();
+    let percentile = |p: f64| samples[((samples.len() - 1) as f64 * p).round() as usize];
+    let mean = samples.iter().sum::<Duration>() / samples.len() as u32;
+
+    println!(
+        "  {:<10} {:>7.3}s {:>7.3}s {:>7.3}s {:>7.3}s {:>7.3}s",
+        name,
+        seconds(samples[0]),
+        seconds(percentile(0.5)),
+        seconds(mean),
+        seconds(percentile(0.95)),
+        seconds(samples[samples.len() - 1])
+    );
+}
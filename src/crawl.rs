@@ -0,0 +1,144 @@
+use std::{
+    collections::{HashSet, VecDeque},
+    path::PathBuf,
+};
+
+use clap::Args;
+use tracing::{debug, info, warn};
+use url::Url;
+
+use web2pdf_lib::{Browser, BrowserWeb2Pdf, PageWeb2Pdf};
+
+use crate::{output_path::sanitize_file_name, resolve_url, Cli, Result, URLPathPair};
+
+#[derive(Args, Debug, Clone)]
+pub struct CrawlArgs {
+    #[clap(help = "URL to start crawling at")]
+    pub url: String,
+
+    #[clap(help = "Directory to write the outputs to, one file per page")]
+    pub dir: PathBuf,
+
+    #[clap(
+        long,
+        help = "Follow links at most this many clicks away from the start URL",
+        default_value_t = 2
+    )]
+    pub max_depth: usize,
+
+    #[clap(
+        long,
+        help = "Convert at most this many pages, including the start URL",
+        default_value_t = 100
+    )]
+    pub max_pages: usize,
+
+    #[clap(
+        long,
+        value_name = "PREFIX",
+        help = "Only follow links starting with PREFIX, e.g. https://example.com/docs/ (default: the origin of the start URL)"
+    )]
+    pub prefix: Option<String>,
+}
+
+/// Finds the pages to convert by following the links from the start URL breadth first
+/// Only links of the same origin (or starting with `--prefix`) are followed, pages are
+/// visited once for their links and converted afterwards like any other URL.
+///
+/// # Arguments
+/// * `cli` - The cli, for the output format
+/// * `browser` - The browser, with cookies and login already applied
+/// * `args` - The crawl arguments
+///
+/// # Returns
+/// A `Result` containing a pair per page, with its output in the crawl directory
+pub async fn crawl(cli: &Cli, browser: &Browser, args: &CrawlArgs) -> Result<Vec<URLPathPair>> {
+    let start = Url::parse(&resolve_url(&args.url))?;
+    let origin = start.origin();
+    let local = start.scheme() == "file";
+    let follows = |url: &Url| match &args.prefix {
+        Some(prefix) => url.as_str().starts_with(prefix.as_str()),
+        // Local files have no origin to compare
+        None if local => url.scheme() == "file",
+        None => url.origin() == origin,
+    };
+
+    let mut seen = HashSet::from([start.clone()]);
+    let mut queue = VecDeque::from([(start, 0)]);
+    let mut pages = Vec::new();
+    while let Some((url, depth)) = queue.pop_front() {
+        pages.push(url.clone());
+        if depth >= args.max_depth || seen.len() >= args.max_pages {
+            continue;
+        }
+        let links = match collect_links(browser, &url).await {
+            Ok(links) => links,
+            Err(e) => {
+                warn!("Failed to collect the links of {} with reason: {}", url, e);
+                continue;
+            }
+        };
+        for link in links {
+            let Ok(link) = Url::parse(&link) else {
+                continue;
+            };
+            if seen.len() >= args.max_pages {
+                break;
+            }
+            if follows(&link) && seen.insert(link.clone()) {
+                queue.push_back((link, depth + 1));
+            }
+        }
+    }
+    info!("Found {} pages starting at {}", pages.len(), args.url);
+
+    let extension = cli.format.extension();
+    let mut names = HashSet::new();
+    Ok(pages
+        .into_iter()
+        .map(|url| {
+            let stem = file_stem(&url);
+            let mut name = format!("{}.{}", stem, extension);
+            for index in 2.. {
+                if names.insert(name.to_lowercase()) {
+                    break;
+                }
+                name = format!("{}-{}.{}", stem, index, extension);
+            }
+            URLPathPair {
+                url: url.to_string(),
+                path: args.dir.join(name),
+                viewport_width: None,
+                options: serde_json::Map::new(),
+            }
+        })
+        .collect())
+}
+
+/// Loads a page in a new tab and collects its links
+async fn collect_links(browser: &Browser, url: &Url) -> Result<Vec<String>> {
+    let page = browser.web2pdf_new_page(url.as_str()).await?;
+    let links = page.web2pdf_links().await;
+    page.close().await?;
+    debug!("Collected the links of {}", url);
+    links
+}
+
+/// A file name for a crawled page from its path and query, e.g. `docs_intro` for `/docs/intro.html`
+fn file_stem(url: &Url) -> String {
+    let path = url.path().trim_matches('/');
+    let path = path
+        .strip_suffix(".html")
+        .or_else(|| path.strip_suffix(".htm"))
+        .unwrap_or(path);
+    let mut stem = if path.is_empty() {
+        String::from("index")
+    } else {
+        path.replace('/', "_")
+    };
+    if let Some(query) = url.query().filter(|query| !query.is_empty()) {
+        stem.push('_');
+        stem.push_str(query);
+    }
+    sanitize_file_name(&stem)
+}
@@ -0,0 +1,58 @@
+use clap::{Args, Subcommand};
+use keyring::Entry;
+use web2pdf_lib::Credentials;
+
+use crate::{parse_credentials, Result};
+
+/// Service name of all entries in the keyring
+const SERVICE: &str = "web2pdf";
+
+#[derive(Args, Debug, Clone)]
+pub struct CredentialArgs {
+    #[clap(subcommand)]
+    pub action: CredentialAction,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum CredentialAction {
+    /// Store credentials, read as "user:password" from stdin
+    Store {
+        #[clap(help = "Name to reference the credentials with --credential")]
+        name: String,
+    },
+    /// Remove stored credentials
+    Delete {
+        #[clap(help = "Name of the credentials")]
+        name: String,
+    },
+}
+
+/// Loads credentials stored with `web2pdf credential store`
+///
+/// # Arguments
+/// * `name` - The name of the credentials
+pub fn load(name: &str) -> Result<Credentials> {
+    let secret = Entry::new(SERVICE, name)?.get_password()?;
+    Ok(parse_credentials(&secret)?)
+}
+
+/// Runs the credential subcommand
+pub fn run(args: &CredentialArgs) -> Result<()> {
+    match &args.action {
+        CredentialAction::Store { name } => {
+            eprintln!("Enter the credentials for {:?} as user:password", name);
+            let mut line = String::new();
+            std::io::stdin().read_line(&mut line)?;
+            let line = line.trim_end_matches(['\r', '\n']);
+            // Checked before storing, to not store unusable credentials
+            parse_credentials(line)?;
+            Entry::new(SERVICE, name)?.set_password(line)?;
+            eprintln!("Stored credentials {:?}", name);
+        }
+        CredentialAction::Delete { name } => {
+            Entry::new(SERVICE, name)?.delete_credential()?;
+            eprintln!("Deleted credentials {:?}", name);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,101 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use tracing::{debug, warn};
+
+use crate::{summary::JobSummary, DedupMode, Result};
+
+/// Finds the outputs of successful jobs that are byte-identical to an earlier output and
+/// replaces them with links to it (or only records them), see `--dedup`
+/// The first job of identical outputs, in the order of the URL-Path pairs, keeps its file.
+///
+/// # Arguments
+/// * `jobs` - The finished jobs, the duplicates get `duplicate_of` set
+/// * `mode` - How to handle the duplicates
+///
+/// # Returns
+/// The number of duplicates and the bytes they no longer take up
+pub fn dedup_outputs(jobs: &mut [JobSummary], mode: DedupMode) -> (usize, usize) {
+    // Candidates are only compared with outputs of the same size
+    let mut originals: HashMap<usize, Vec<PathBuf>> = HashMap::new();
+    let (mut duplicates, mut saved) = (0, 0);
+    for job in jobs.iter_mut() {
+        let Ok(stats) = &mut job.outcome else {
+            continue;
+        };
+        let same_size = originals.entry(stats.size).or_default();
+        let original = match find_identical(&job.path, same_size) {
+            Ok(original) => original,
+            Err(e) => {
+                warn!("Failed to compare {:?} for --dedup: {}", job.path, e);
+                continue;
+            }
+        };
+        let Some(original) = original else {
+            same_size.push(job.path.clone());
+            continue;
+        };
+        let linked = match mode {
+            DedupMode::Hardlink => replace_with_link(&job.path, &original, false),
+            DedupMode::Symlink => replace_with_link(&job.path, &original, true),
+            DedupMode::Report => Ok(()),
+        };
+        if let Err(e) = linked {
+            warn!(
+                "Failed to link {:?} to the identical {:?}: {}",
+                job.path, original, e
+            );
+            continue;
+        }
+        debug!("{:?} is identical to {:?}", job.path, original);
+        duplicates += 1;
+        if mode != DedupMode::Report {
+            saved += stats.size;
+        }
+        stats.duplicate_of = Some(original);
+    }
+    (duplicates, saved)
+}
+
+/// Finds an output with the same content as `path`
+fn find_identical(path: &Path, candidates: &[PathBuf]) -> Result<Option<PathBuf>> {
+    if candidates.is_empty() {
+        return Ok(None);
+    }
+    let contents = std::fs::read(path)?;
+    for candidate in candidates {
+        // Jobs writing to the same path are not duplicates of each other
+        if candidate == path {
+            continue;
+        }
+        if std::fs::read(candidate)? == contents {
+            return Ok(Some(candidate.clone()));
+        }
+    }
+    Ok(None)
+}
+
+/// Replaces `path` with a hard or symbolic link to `original`
+/// The link is created next to `path` first, so `path` is never missing.
+fn replace_with_link(path: &Path, original: &Path, symbolic: bool) -> Result<()> {
+    let mut link = path.as_os_str().to_owned();
+    link.push(".web2pdf-link");
+    let link = PathBuf::from(link);
+    if symbolic {
+        // Absolute, so the link works wherever the two files are
+        let target = std::fs::canonicalize(original)?;
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(target, &link)?;
+        #[cfg(windows)]
+        std::os::windows::fs::symlink_file(target, &link)?;
+    } else {
+        std::fs::hard_link(original, &link)?;
+    }
+    if let Err(e) = std::fs::rename(&link, path) {
+        let _ = std::fs::remove_file(&link);
+        return Err(e.into());
+    }
+    Ok(())
+}
@@ -0,0 +1,72 @@
+use std::{error::Error, fmt};
+
+/// Category of a failed job, from the least to the most severe, see --exit-code-mode class
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum FailureClass {
+    /// Skipped, as the outputs exceeded --total-size-budget
+    Skipped,
+    /// The page could not be loaded, e.g. the host is unreachable
+    Load,
+    /// The page loaded, but preparing or printing it failed
+    Render,
+    /// The output could not be processed or written, also failed merges, cookie jars and baselines
+    Output,
+    /// The browser could not open or set up a tab, e.g. after it crashed
+    Browser,
+    /// The options of the job are invalid, e.g. in a manifest
+    Config,
+}
+impl FailureClass {
+    /// The exit code of the class, starting at 3 as 1 is a fatal error and 2 an invalid command line
+    pub fn exit_code(self) -> i32 {
+        match self {
+            FailureClass::Skipped => 3,
+            FailureClass::Load => 4,
+            FailureClass::Render => 5,
+            FailureClass::Output => 6,
+            FailureClass::Browser => 7,
+            FailureClass::Config => 8,
+        }
+    }
+
+    /// The class of a conversion that failed after the given phase, see `PhaseTimer`
+    ///
+    /// # Arguments
+    /// * `phase` - The last phase that finished, None if it failed in the first one
+    pub fn after_phase(phase: Option<&str>) -> FailureClass {
+        match phase {
+            None => FailureClass::Browser,
+            Some("setup") => FailureClass::Load,
+            Some("navigate" | "prepare" | "pause") => FailureClass::Render,
+            Some(_) => FailureClass::Output,
+        }
+    }
+}
+
+/// An error of a conversion with its class, displayed like the original error
+#[derive(Debug)]
+pub struct ClassifiedError {
+    pub class: FailureClass,
+    message: String,
+}
+impl ClassifiedError {
+    pub fn new(class: FailureClass, error: impl fmt::Display) -> ClassifiedError {
+        ClassifiedError {
+            class,
+            message: error.to_string(),
+        }
+    }
+
+    /// The class of an error, errors that were not classified count as render failures
+    pub fn class_of(error: &(dyn Error + 'static)) -> FailureClass {
+        error
+            .downcast_ref::<ClassifiedError>()
+            .map_or(FailureClass::Render, |error| error.class)
+    }
+}
+impl fmt::Display for ClassifiedError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+impl Error for ClassifiedError {}
@@ -0,0 +1,58 @@
+use tokio::process::Command;
+use tracing::{debug, warn};
+
+use crate::summary::JobSummary;
+
+/// Runs a shell command for a finished job, see `--on-success` and `--on-failure`
+/// The job is described by environment variables:
+/// - `WEB2PDF_URL`: the converted URL
+/// - `WEB2PDF_PATH`: the output file
+/// - `WEB2PDF_STATUS`: "success" or "failure"
+/// - `WEB2PDF_DURATION_MS`: the duration of the job in milliseconds
+/// - `WEB2PDF_PAGES` and `WEB2PDF_SIZE`: the page count and size in bytes, only on success
+/// - `WEB2PDF_ERROR`: the error message, only on failure
+/// - `WEB2PDF_CHANGED`: "true" or "false", only for pages in the baseline of `--alert-if-changed`
+///
+/// Failing commands are logged, they don't fail the job.
+///
+/// # Arguments
+/// * `command` - The command, run by `sh -c` (`cmd /C` on Windows)
+/// * `job` - The finished job
+pub async fn run_job_hook(command: &str, job: &JobSummary) {
+    let mut process = if cfg!(windows) {
+        let mut process = Command::new("cmd");
+        process.arg("/C").arg(command);
+        process
+    } else {
+        let mut process = Command::new("sh");
+        process.arg("-c").arg(command);
+        process
+    };
+    process
+        .env("WEB2PDF_URL", &job.url)
+        .env("WEB2PDF_PATH", &job.path)
+        .env("WEB2PDF_DURATION_MS", job.duration.as_millis().to_string());
+    match &job.outcome {
+        Ok(stats) => {
+            process
+                .env("WEB2PDF_STATUS", "success")
+                .env("WEB2PDF_PAGES", stats.pages.to_string())
+                .env("WEB2PDF_SIZE", stats.size.to_string());
+            if let Some(changed) = stats.changed {
+                process.env("WEB2PDF_CHANGED", changed.to_string());
+            }
+        }
+        Err(error) => {
+            process
+                .env("WEB2PDF_STATUS", "failure")
+                .env("WEB2PDF_ERROR", error);
+        }
+    }
+
+    debug!("Running hook {:?} for {}", command, job.url);
+    match process.status().await {
+        Ok(status) if status.success() => {}
+        Ok(status) => warn!("Hook {:?} for {} exited with {}", command, job.url, status),
+        Err(e) => warn!("Could not run hook {:?} for {}: {}", command, job.url, e),
+    }
+}
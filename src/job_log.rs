@@ -0,0 +1,142 @@
+use std::{
+    fmt::Write as _,
+    fs::File,
+    io::Write as _,
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::Instant,
+};
+
+use tracing::{
+    field::{Field, Visit},
+    span, Event, Subscriber,
+};
+use tracing_subscriber::{layer::Context, registry::LookupSpan, Layer};
+
+/// Name of the field of the job span that holds the output path
+pub const OUTPUT_FIELD: &str = "output";
+/// Name of the field of the job span that holds the number of the job in the batch
+pub const JOB_FIELD: &str = "job";
+
+/// Log file of a single job, stored in the extensions of the job span
+struct JobLogFile {
+    file: Mutex<File>,
+    start: Instant,
+}
+
+/// Layer writing the events of every job span (a span with an `output` field)
+/// into its own file in a directory, named after the number of the job and the output file
+pub struct JobLogLayer {
+    dir: PathBuf,
+}
+impl JobLogLayer {
+    pub fn new(dir: PathBuf) -> JobLogLayer {
+        JobLogLayer { dir }
+    }
+
+    /// Path of the log file for a job, e.g. job 3 with "out/rust.pdf" -> "<dir>/0003-rust.pdf.log"
+    /// The number keeps outputs with the same file name in different directories apart.
+    fn log_path(&self, job: Option<u64>, output: &str) -> PathBuf {
+        let name = Path::new(output)
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| String::from("job"));
+        match job {
+            Some(job) => self.dir.join(format!("{:04}-{}.log", job, name)),
+            None => self.dir.join(format!("{}.log", name)),
+        }
+    }
+}
+
+impl<S> Layer<S> for JobLogLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attrs: &span::Attributes<'_>, id: &span::Id, ctx: Context<'_, S>) {
+        let mut visitor = FieldVisitor::default();
+        attrs.record(&mut visitor);
+        let Some(output) = visitor.output else {
+            return;
+        };
+
+        let path = self.log_path(visitor.job, &output);
+        match File::create(&path) {
+            Ok(file) => {
+                if let Some(span) = ctx.span(id) {
+                    span.extensions_mut().insert(JobLogFile {
+                        file: Mutex::new(file),
+                        start: Instant::now(),
+                    });
+                }
+            }
+            Err(e) => eprintln!("Could not create job log file {:?}: {}", path, e),
+        }
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        let Some(scope) = ctx.event_scope(event) else {
+            return;
+        };
+        for span in scope {
+            let extensions = span.extensions();
+            let Some(log) = extensions.get::<JobLogFile>() else {
+                continue;
+            };
+
+            let mut visitor = FieldVisitor::default();
+            event.record(&mut visitor);
+            let metadata = event.metadata();
+            let line = format!(
+                "[{:>9.3}s] {:>5} {}: {}{}\n",
+                log.start.elapsed().as_secs_f64(),
+                metadata.level(),
+                metadata.target(),
+                visitor.message,
+                visitor.fields
+            );
+            if let Ok(mut file) = log.file.lock() {
+                let _ = file.write_all(line.as_bytes());
+            }
+            return;
+        }
+    }
+}
+
+/// Collects the message and the remaining fields of a span or event
+#[derive(Default)]
+struct FieldVisitor {
+    message: String,
+    fields: String,
+    output: Option<String>,
+    job: Option<u64>,
+}
+impl Visit for FieldVisitor {
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        if field.name() == JOB_FIELD {
+            self.job = Some(value);
+        }
+        self.record_debug(field, &value);
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == OUTPUT_FIELD {
+            self.output = Some(value.to_string());
+        }
+        self.record_debug(field, &value);
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        match field.name() {
+            "message" => {
+                let _ = write!(self.message, "{:?}", value);
+            }
+            OUTPUT_FIELD if self.output.is_none() => {
+                self.output = Some(format!("{:?}", value));
+                let _ = write!(self.fields, " {}={:?}", field.name(), value);
+            }
+            name => {
+                let _ = write!(self.fields, " {}={:?}", name, value);
+            }
+        }
+    }
+}
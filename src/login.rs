@@ -0,0 +1,68 @@
+use std::time::Duration;
+
+use chromiumoxide::Page;
+use tracing::{info, warn};
+use web2pdf_lib::{Browser, BrowserWeb2Pdf};
+
+use crate::{Cli, Result};
+
+/// How often the page is checked for the end of the login
+const LOGIN_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Opens the login page of --interactive-login and waits until the user logged in,
+/// the cookies of the login are then used by all pages
+///
+/// # Arguments
+/// * `browser` - The (visible) browser
+/// * `cli` - The cli, for the login URL and the conditions ending the login
+pub async fn interactive_login(browser: &Browser, cli: &Cli) -> Result<()> {
+    let Some(url) = &cli.interactive_login else {
+        return Ok(());
+    };
+    let page = browser.web2pdf_new_page(url.as_str()).await?;
+    warn!(
+        "Log in to {} in the browser window, then press Enter to continue",
+        url
+    );
+
+    let enter = tokio::task::spawn_blocking(|| std::io::stdin().read_line(&mut String::new()));
+    if cli.login_done_url.is_some() || cli.login_done_selector.is_some() {
+        tokio::select! {
+            _ = enter => {}
+            _ = wait_for_login(&page, cli) => info!("Login finished"),
+        }
+    } else {
+        let _ = enter.await;
+    }
+
+    page.close().await?;
+    Ok(())
+}
+
+/// Waits until the page matches --login-done-url or --login-done-selector
+async fn wait_for_login(page: &Page, cli: &Cli) {
+    loop {
+        tokio::time::sleep(LOGIN_POLL_INTERVAL).await;
+        // The checks fail while the page navigates, they are simply repeated
+        if let Some(prefix) = &cli.login_done_url {
+            if let Ok(Some(url)) = page.url().await {
+                if url.starts_with(prefix.as_str()) {
+                    return;
+                }
+            }
+        }
+        if let Some(selector) = &cli.login_done_selector {
+            let found = page
+                .evaluate(format!(
+                    "document.querySelector({}) !== null",
+                    serde_json::to_string(selector).unwrap_or_default()
+                ))
+                .await
+                .ok()
+                .and_then(|found| found.into_value::<bool>().ok());
+            if found == Some(true) {
+                return;
+            }
+        }
+    }
+}
@@ -0,0 +1,436 @@
+use std::{collections::HashMap, path::Path, sync::Arc};
+
+use clap::{CommandFactory, Parser};
+use serde_json::{Map, Value};
+use web2pdf_lib::table;
+
+use crate::{Cli, Result, URLPathPair};
+
+/// Columns of a manifest that are not options
+const URL_COLUMN: &str = "url";
+const PATH_COLUMNS: [&str; 2] = ["path", "output"];
+
+/// Reads the URLs, output paths and option overrides of a manifest, see `--manifest`
+/// A JSON manifest is an array of objects, a CSV/TSV manifest has a header row.
+/// Besides `url` and `path` (or `output`) the fields are named like the long options, e.g.
+/// `landscape`, `mono` or `page-ranges`. Empty values keep the option of the command line.
+///
+/// # Arguments
+/// * `path` - The manifest file, JSON if it ends with `.json`, otherwise CSV (or TSV for `.tsv`)
+///
+/// # Returns
+/// A `Result` containing a pair per row or an error naming the invalid row.
+pub fn load_manifest(path: &Path) -> Result<Vec<URLPathPair>> {
+    let contents = std::fs::read_to_string(path)?;
+    let extension = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .map(str::to_ascii_lowercase);
+    let rows: Vec<Map<String, Value>> = match extension.as_deref() {
+        Some("json") => serde_json::from_str(&contents)?,
+        _ => {
+            let delimiter = match extension.as_deref() {
+                Some("tsv" | "tab") => '\t',
+                _ => table::sniff_delimiter(&contents, ','),
+            };
+            let mut rows = table::parse_delimited(&contents, delimiter).into_iter();
+            let header = rows.next().ok_or("the manifest is empty")?;
+            rows.filter(|row| row.iter().any(|field| !field.trim().is_empty()))
+                .map(|row| {
+                    header
+                        .iter()
+                        .map(|name| name.trim().to_string())
+                        .zip(row.into_iter().map(Value::String))
+                        .collect()
+                })
+                .collect()
+        }
+    };
+
+    // Rows of large manifests mostly repeat the same few overrides
+    let mut parsed = HashMap::new();
+    rows.into_iter()
+        .enumerate()
+        .map(|(index, row)| {
+            parse_row(row, &mut parsed)
+                .map_err(|e| format!("row {} of the manifest: {}", index + 1, e).into())
+        })
+        .collect()
+}
+
+/// Parses a row of the manifest into a pair with its option overrides
+///
+/// # Arguments
+/// * `row` - The fields of the row
+/// * `parsed` - Options parsed before, by name and value
+fn parse_row(
+    row: Map<String, Value>,
+    parsed: &mut HashMap<(String, String), (String, Value)>,
+) -> Result<URLPathPair> {
+    let mut url = None;
+    let mut path = None;
+    let mut options = Map::new();
+    for (name, value) in row {
+        let value = match value {
+            Value::Null => continue,
+            Value::String(text) if text.trim().is_empty() => continue,
+            value => value,
+        };
+        if name.eq_ignore_ascii_case(URL_COLUMN) {
+            url = Some(value_text(&value)?);
+        } else if PATH_COLUMNS
+            .iter()
+            .any(|column| name.eq_ignore_ascii_case(column))
+        {
+            path = Some(value_text(&value)?);
+        } else {
+            let (field, value) = match parsed.get(&(name.clone(), value.to_string())) {
+                Some(option) => option.clone(),
+                None => {
+                    let option = parse_option(&name, &value)?;
+                    parsed.insert((name, value.to_string()), option.clone());
+                    option
+                }
+            };
+            options.insert(field, value);
+        }
+    }
+    Ok(URLPathPair {
+        url: url.ok_or("no url")?,
+        path: path.ok_or("no path")?.into(),
+        viewport_width: None,
+        options,
+    })
+}
+
+/// Parses the value of an option with the parser of the command line
+///
+/// # Arguments
+/// * `name` - The long name of the option, e.g. `page-ranges`
+/// * `value` - The value, an array for options that can be repeated
+///
+/// # Returns
+/// The name of the option in the serialized cli and its value
+pub fn parse_option(name: &str, value: &Value) -> Result<(String, Value)> {
+    let long = name.trim().trim_start_matches("--").replace('_', "-");
+    let id = long.replace('-', "_");
+    let command = Cli::command();
+    let argument = command
+        .get_arguments()
+        .filter(|argument| argument.get_long().is_some())
+        .find(|argument| {
+            argument.get_long() == Some(long.as_str())
+                || argument.get_id() == id.as_str()
+                || argument
+                    .get_all_aliases()
+                    .is_some_and(|aliases| aliases.contains(&long.as_str()))
+        })
+        .ok_or_else(|| format!("unknown option {:?}", name))?;
+    let field = argument.get_id().to_string();
+    let flag = format!("--{}", argument.get_long().unwrap_or(&long));
+
+    let mut args = vec![String::from("web2pdf")];
+    if argument.get_action().takes_values() {
+        let values = match value {
+            Value::Array(values) => values.iter().map(value_text).collect::<Result<_>>()?,
+            value => vec![value_text(value)?],
+        };
+        args.extend(values.iter().map(|value| format!("{}={}", flag, value)));
+    } else {
+        let enabled = match value {
+            Value::Bool(enabled) => *enabled,
+            value => match value_text(value)?.to_ascii_lowercase().as_str() {
+                "true" | "yes" | "1" => true,
+                "false" | "no" | "0" => false,
+                _ => return Err(format!("expected true or false for {:?}", name).into()),
+            },
+        };
+        if enabled {
+            args.push(flag.clone());
+        }
+    }
+    args.extend([String::from("about:blank"), String::from("manifest.pdf")]);
+    let parsed = Cli::try_parse_from(&args).map_err(|e| {
+        // The message without the usage
+        e.render()
+            .to_string()
+            .lines()
+            .take_while(|line| !line.is_empty())
+            .map(str::trim)
+            .collect::<Vec<_>>()
+            .join(" ")
+            .trim_start_matches("error: ")
+            .to_string()
+    })?;
+    let options = serde_json::to_value(&parsed)?;
+    let value = options[&field].clone();
+
+    // Options not kept in the serialized cli (e.g. secrets) would be lost silently
+    let applied = serde_json::to_value(serde_json::from_value::<Cli>(options)?)?;
+    if value.is_null() || applied[&field] != value {
+        return Err(format!("{} can't be set in the manifest", flag).into());
+    }
+    Ok((field, value))
+}
+
+/// The text of a manifest value
+fn value_text(value: &Value) -> Result<String> {
+    match value {
+        Value::String(text) => Ok(text.trim().to_string()),
+        Value::Number(number) => Ok(number.to_string()),
+        Value::Bool(enabled) => Ok(enabled.to_string()),
+        value => Err(format!("unexpected value {}", value).into()),
+    }
+}
+
+/// The cli of a job, with the option overrides of its manifest row
+///
+/// # Arguments
+/// * `cli` - The cli of the run
+/// * `page_num` - The index of the job's URL-Path pair
+pub fn job_cli(cli: &Arc<Cli>, page_num: usize) -> Result<Arc<Cli>> {
+    let options = &cli.url_path_pairs[page_num].options;
+    if options.is_empty() {
+        return Ok(Arc::clone(cli));
+    }
+    let mut merged = serde_json::to_value(cli.as_ref())?;
+    for (field, value) in options {
+        merged[field] = value.clone();
+    }
+    let mut job: Cli = serde_json::from_value(merged)?;
+    job.restore_unrecorded(cli);
+    job.url_path_pairs = cli.url_path_pairs.clone();
+    job.comparison_paths = cli.comparison_paths.clone();
+    job.required_browser = cli.required_browser.clone();
+    // Rows changing the watermark read their own, see `render_tab`
+    if options.keys().any(|field| field.starts_with("watermark")) {
+        job.loaded_watermarks = None;
+    }
+    expand_row_shorthands(&mut job, options);
+    Ok(Arc::new(job))
+}
+
+/// Expands the shorthands (e.g. `paper` or `full-bleed`) a row sets, the ones of the command line
+/// are already expanded. The fields the row sets itself win, e.g. its `margin-top` over its
+/// `full-bleed` or the `paper-width` of the row over `--paper`.
+///
+/// # Arguments
+/// * `job` - The cli of the job, with the overrides of the row
+/// * `options` - The overrides of the row
+fn expand_row_shorthands(job: &mut Cli, options: &Map<String, Value>) {
+    let row_sets = |field: &str| options.contains_key(field);
+    let mut expanded = job.clone();
+    if !row_sets("paper") {
+        expanded.paper = None;
+    }
+    if !row_sets("paper_px") {
+        expanded.paper_px = None;
+    }
+    if !row_sets("full_bleed") {
+        expanded.full_bleed = false;
+    }
+    if !row_sets("receipt") && !row_sets("receipt_width") {
+        expanded.receipt = false;
+    }
+    expanded.expand_shorthands();
+
+    if !row_sets("paper_width") {
+        job.paper_width = expanded.paper_width;
+    }
+    if !row_sets("paper_height") {
+        job.paper_height = expanded.paper_height;
+    }
+    if !row_sets("margin_top") {
+        job.margin_top = expanded.margin_top;
+    }
+    if !row_sets("margin_bottom") {
+        job.margin_bottom = expanded.margin_bottom;
+    }
+    if !row_sets("margin_left") {
+        job.margin_left = expanded.margin_left;
+    }
+    if !row_sets("margin_right") {
+        job.margin_right = expanded.margin_right;
+    }
+    if !row_sets("display_header_footer") {
+        job.display_header_footer = expanded.display_header_footer;
+    }
+    if !row_sets("disable_prefer_css_page_size") {
+        job.disable_prefer_css_page_size = expanded.disable_prefer_css_page_size;
+    }
+    if !row_sets("mono_page") {
+        job.mono_page = expanded.mono_page;
+    }
+    if !row_sets("screen_media_type") {
+        job.screen_media_type = expanded.screen_media_type;
+    }
+    job.url_path_pairs = expanded.url_path_pairs;
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    /// The expanded cli of a command line converting about:blank
+    fn cli(args: &[&str]) -> Cli {
+        let args = ["web2pdf"]
+            .iter()
+            .chain(args)
+            .chain(&["about:blank", "out.pdf"]);
+        let mut cli = Cli::try_parse_from(args).unwrap().replace_url_path_pairs();
+        cli.expand_shorthands();
+        cli
+    }
+
+    /// The cli of the job of a manifest row, run with a command line
+    fn job(args: &[&str], row: Value) -> Arc<Cli> {
+        let Value::Object(mut row) = row else {
+            panic!("rows are objects");
+        };
+        row.insert(String::from("url"), json!("about:blank"));
+        row.insert(String::from("path"), json!("out.pdf"));
+        let mut cli = cli(args);
+        cli.url_path_pairs = vec![parse_row(row, &mut HashMap::new()).unwrap()];
+        job_cli(&Arc::new(cli), 0).unwrap()
+    }
+
+    fn row_error(row: Value) -> String {
+        let Value::Object(row) = row else {
+            panic!("rows are objects");
+        };
+        parse_row(row, &mut HashMap::new())
+            .map(|_| ())
+            .unwrap_err()
+            .to_string()
+    }
+
+    #[test]
+    fn row_options() {
+        let row = json!({
+            "URL": "https://example.com",
+            "output": "example.pdf",
+            "landscape": "yes",
+            "page-ranges": "1-2",
+            "margin_top": 1,
+            "mono": "",
+            "scale": null,
+        });
+        let Value::Object(row) = row else {
+            unreachable!()
+        };
+        let pair = parse_row(row, &mut HashMap::new()).unwrap();
+        assert_eq!(pair.url, "https://example.com");
+        assert_eq!(pair.path, Path::new("example.pdf"));
+        assert_eq!(pair.options["landscape"], json!(true));
+        assert_eq!(pair.options["page_ranges"], json!("1-2"));
+        assert_eq!(pair.options["margin_top"], json!(1.0));
+        // Empty values keep the option of the command line
+        assert!(!pair.options.contains_key("mono_page"));
+        assert!(!pair.options.contains_key("scale"));
+    }
+
+    #[test]
+    fn paper_shorthand() {
+        let expected = cli(&["--paper", "a4"]);
+        let job = job(&["--paper", "letter"], json!({"paper": "a4"}));
+        assert_eq!(job.paper_width, expected.paper_width);
+        assert_eq!(job.paper_height, expected.paper_height);
+
+        // The paper width of the row wins over its paper
+        let job = self::job(&[], json!({"paper": "a4", "paper-width": "5in"}));
+        assert_eq!(job.paper_width, Some(5.0));
+        assert_eq!(job.paper_height, expected.paper_height);
+    }
+
+    #[test]
+    fn paper_px_shorthand() {
+        let job = job(&["--paper", "a4"], json!({"paper-px": "960x480"}));
+        assert_eq!(job.paper_width, Some(10.0));
+        assert_eq!(job.paper_height, Some(5.0));
+    }
+
+    #[test]
+    fn command_line_shorthands_are_kept() {
+        let expected = cli(&["--paper", "letter"]);
+        let job = job(
+            &["--paper", "letter", "--full-bleed"],
+            json!({"landscape": true}),
+        );
+        assert_eq!(job.paper_width, expected.paper_width);
+        assert_eq!(job.paper_height, expected.paper_height);
+        assert_eq!(job.margin_top, 0.0);
+        assert!(job.landscape);
+
+        // The margin of the row wins over the --full-bleed of the command line
+        let job = self::job(&["--full-bleed"], json!({"margin-top": "1in"}));
+        assert_eq!(job.margin_top, 1.0);
+        assert_eq!(job.margin_bottom, 0.0);
+    }
+
+    #[test]
+    fn full_bleed_shorthand() {
+        let job = job(&[], json!({"full-bleed": true, "margin-left": "1in"}));
+        assert_eq!(job.margin_top, 0.0);
+        assert_eq!(job.margin_bottom, 0.0);
+        assert_eq!(job.margin_right, 0.0);
+        assert_eq!(job.margin_left, 1.0);
+        assert!(!job.display_header_footer);
+    }
+
+    #[test]
+    fn receipt_shorthand() {
+        let job = job(&[], json!({"receipt": true}));
+        assert!(job.mono_page);
+        assert!(job.screen_media_type);
+        assert_eq!(job.margin_left, 0.0);
+        assert_eq!(job.margin_right, 0.0);
+        assert_eq!(
+            job.url_path_pairs[0].viewport_width,
+            Some(job.receipt_width)
+        );
+    }
+
+    #[test]
+    fn loaded_watermarks() {
+        let job = |row: Value| {
+            let Value::Object(mut row) = row else {
+                panic!("rows are objects");
+            };
+            row.insert(String::from("url"), json!("about:blank"));
+            row.insert(String::from("path"), json!("out.pdf"));
+            let mut cli = cli(&["--watermark", "DRAFT"]);
+            cli.loaded_watermarks = Some(Arc::new(Vec::new()));
+            cli.url_path_pairs = vec![parse_row(row, &mut HashMap::new()).unwrap()];
+            job_cli(&Arc::new(cli), 0).unwrap()
+        };
+        assert!(job(json!({"landscape": true})).loaded_watermarks.is_some());
+        // Rows changing the watermark read their own
+        assert!(job(json!({"watermark": "COPY"}))
+            .loaded_watermarks
+            .is_none());
+        assert!(job(json!({"watermark-opacity": 0.5}))
+            .loaded_watermarks
+            .is_none());
+    }
+
+    #[test]
+    fn malformed_rows() {
+        assert!(row_error(json!({"path": "a.pdf"})).contains("no url"));
+        assert!(row_error(json!({"url": "about:blank"})).contains("no path"));
+        let row = |option: Value| {
+            let mut row = json!({"url": "about:blank", "path": "a.pdf"});
+            row.as_object_mut()
+                .unwrap()
+                .extend(option.as_object().unwrap().clone());
+            row_error(row)
+        };
+        assert!(row(json!({"no-such-option": "1"})).contains("unknown option"));
+        assert!(row(json!({"landscape": "maybe"})).contains("expected true or false"));
+        assert!(row(json!({"margin-top": "ten"})).contains("invalid length"));
+        assert!(row(json!({"paper": "a99"})).contains("a99"));
+        assert!(row(json!({"scale": {"value": 1}})).contains("unexpected value"));
+        assert!(row(json!({"http-auth": "user:secret"})).contains("can't be set in the manifest"));
+    }
+}
@@ -0,0 +1,182 @@
+use std::{
+    fmt::Write,
+    sync::atomic::{AtomicU64, Ordering},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+/// Upper bounds of the buckets of the conversion durations in seconds
+const DURATION_BUCKETS: [f64; 10] = [0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 20.0, 30.0, 60.0, 120.0];
+
+/// Metrics of the server in the Prometheus text format, see `GET /metrics`
+pub struct Metrics {
+    /// Seconds since the Unix epoch, a change means the server restarted (e.g. after a crash)
+    start_time: f64,
+    conversions: AtomicU64,
+    failures: AtomicU64,
+    /// Conversions per duration bucket, not cumulative, the last one counts longer conversions
+    durations: [AtomicU64; DURATION_BUCKETS.len() + 1],
+    /// Total duration of the conversions in microseconds
+    duration_sum: AtomicU64,
+    active_tabs: AtomicU64,
+    /// Conversions waiting for a tab, see `GET /readyz`
+    queued: AtomicU64,
+}
+
+impl Metrics {
+    pub fn new() -> Metrics {
+        Metrics {
+            start_time: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs_f64(),
+            conversions: AtomicU64::new(0),
+            failures: AtomicU64::new(0),
+            durations: Default::default(),
+            duration_sum: AtomicU64::new(0),
+            active_tabs: AtomicU64::new(0),
+            queued: AtomicU64::new(0),
+        }
+    }
+
+    /// Counts a tab as active until the returned guard is dropped
+    pub fn open_tab(&self) -> ActiveTab<'_> {
+        self.active_tabs.fetch_add(1, Ordering::Relaxed);
+        ActiveTab { metrics: self }
+    }
+
+    /// Counts a conversion as queued until the returned guard is dropped
+    pub fn enqueue(&self) -> Queued<'_> {
+        self.queued.fetch_add(1, Ordering::Relaxed);
+        Queued { metrics: self }
+    }
+
+    /// The conversions waiting for a tab
+    pub fn queued(&self) -> u64 {
+        self.queued.load(Ordering::Relaxed)
+    }
+
+    /// Records a finished conversion
+    ///
+    /// # Arguments
+    /// * `duration` - How long the conversion took, without waiting for a free slot
+    /// * `succeeded` - Whether the output was created
+    pub fn record(&self, duration: Duration, succeeded: bool) {
+        self.conversions.fetch_add(1, Ordering::Relaxed);
+        if !succeeded {
+            self.failures.fetch_add(1, Ordering::Relaxed);
+        }
+        let seconds = duration.as_secs_f64();
+        let bucket = DURATION_BUCKETS
+            .iter()
+            .position(|bound| seconds <= *bound)
+            .unwrap_or(DURATION_BUCKETS.len());
+        self.durations[bucket].fetch_add(1, Ordering::Relaxed);
+        self.duration_sum
+            .fetch_add(duration.as_micros() as u64, Ordering::Relaxed);
+    }
+
+    /// The metrics in the Prometheus text exposition format
+    pub fn render(&self) -> String {
+        let mut text = String::new();
+        let name = metric(
+            &mut text,
+            "process_start_time_seconds",
+            "gauge",
+            "Start time of the process since the Unix epoch in seconds",
+        );
+        let _ = writeln!(text, "{} {}", name, self.start_time);
+
+        let name = metric(
+            &mut text,
+            "web2pdf_conversions_total",
+            "counter",
+            "Conversions that finished, including failed ones",
+        );
+        let _ = writeln!(
+            text,
+            "{} {}",
+            name,
+            self.conversions.load(Ordering::Relaxed)
+        );
+
+        let name = metric(
+            &mut text,
+            "web2pdf_conversion_failures_total",
+            "counter",
+            "Conversions that failed",
+        );
+        let _ = writeln!(text, "{} {}", name, self.failures.load(Ordering::Relaxed));
+
+        let name = metric(
+            &mut text,
+            "web2pdf_conversion_duration_seconds",
+            "histogram",
+            "Duration of the conversions, from opening the tab to the output",
+        );
+        let mut count = 0;
+        for (index, bucket) in self.durations.iter().enumerate() {
+            count += bucket.load(Ordering::Relaxed);
+            let bound = DURATION_BUCKETS
+                .get(index)
+                .map_or(String::from("+Inf"), |bound| bound.to_string());
+            let _ = writeln!(text, "{}_bucket{{le=\"{}\"}} {}", name, bound, count);
+        }
+        let sum = self.duration_sum.load(Ordering::Relaxed) as f64 / 1_000_000.0;
+        let _ = writeln!(text, "{}_sum {}", name, sum);
+        let _ = writeln!(text, "{}_count {}", name, count);
+
+        let name = metric(
+            &mut text,
+            "web2pdf_active_tabs",
+            "gauge",
+            "Tabs that are converting a page right now",
+        );
+        let _ = writeln!(
+            text,
+            "{} {}",
+            name,
+            self.active_tabs.load(Ordering::Relaxed)
+        );
+
+        let name = metric(
+            &mut text,
+            "web2pdf_queued_conversions",
+            "gauge",
+            "Conversions that wait for a tab",
+        );
+        let _ = writeln!(text, "{} {}", name, self.queued());
+        text
+    }
+}
+
+/// Writes the HELP and TYPE lines of a metric
+///
+/// # Returns
+/// The name, to write the samples with
+fn metric<'a>(text: &mut String, name: &'a str, kind: &str, help: &str) -> &'a str {
+    let _ = writeln!(text, "# HELP {} {}", name, help);
+    let _ = writeln!(text, "# TYPE {} {}", name, kind);
+    name
+}
+
+/// A tab counted by `web2pdf_active_tabs`, see `Metrics::open_tab`
+pub struct ActiveTab<'a> {
+    metrics: &'a Metrics,
+}
+
+impl Drop for ActiveTab<'_> {
+    fn drop(&mut self) {
+        self.metrics.active_tabs.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// A conversion counted by `web2pdf_queued_conversions`, see `Metrics::enqueue`
+pub struct Queued<'a> {
+    metrics: &'a Metrics,
+}
+
+impl Drop for Queued<'_> {
+    fn drop(&mut self) {
+        self.metrics.queued.fetch_sub(1, Ordering::Relaxed);
+    }
+}
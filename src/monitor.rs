@@ -0,0 +1,134 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use serde::Serialize;
+use sysinfo::{Pid, ProcessRefreshKind, ProcessesToUpdate, System};
+
+/// A single measurement of the browser process tree
+#[derive(Debug, Clone, Copy)]
+struct Sample {
+    time: Instant,
+    /// CPU usage in percent of a single core, summed over all browser processes
+    cpu: f32,
+    /// Resident memory in bytes, summed over all browser processes
+    rss: u64,
+}
+
+/// Peak and average resource usage of the browser over a period of time
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct ResourceStats {
+    pub cpu_peak: f32,
+    pub cpu_avg: f32,
+    pub rss_peak: u64,
+    pub rss_avg: u64,
+}
+
+/// Periodically samples CPU and memory usage of the browser and all of its child processes
+pub struct ResourceMonitor {
+    samples: Arc<Mutex<Vec<Sample>>>,
+    stop: Arc<AtomicBool>,
+}
+impl ResourceMonitor {
+    /// Starts sampling the process tree of `pid` in a background thread
+    ///
+    /// # Arguments
+    /// * `pid` - The process id of the browser
+    /// * `interval` - The time between two samples
+    pub fn start(pid: u32, interval: Duration) -> ResourceMonitor {
+        let samples = Arc::new(Mutex::new(Vec::new()));
+        let stop = Arc::new(AtomicBool::new(false));
+
+        let thread_samples = Arc::clone(&samples);
+        let thread_stop = Arc::clone(&stop);
+        thread::spawn(move || {
+            let root = Pid::from_u32(pid);
+            let mut system = System::new();
+            while !thread_stop.load(Ordering::Relaxed) {
+                system.refresh_processes_specifics(
+                    ProcessesToUpdate::All,
+                    true,
+                    ProcessRefreshKind::nothing().with_cpu().with_memory(),
+                );
+
+                let mut sample = Sample {
+                    time: Instant::now(),
+                    cpu: 0.0,
+                    rss: 0,
+                };
+                for (pid, process) in system.processes() {
+                    if is_in_tree(&system, *pid, root) {
+                        sample.cpu += process.cpu_usage();
+                        sample.rss += process.memory();
+                    }
+                }
+                if let Ok(mut samples) = thread_samples.lock() {
+                    samples.push(sample);
+                }
+
+                thread::sleep(interval);
+            }
+        });
+
+        ResourceMonitor { samples, stop }
+    }
+
+    /// Peak and average usage of all samples taken between `start` and `end`
+    /// Returns None if no sample was taken in that period
+    pub fn stats_between(&self, start: Instant, end: Instant) -> Option<ResourceStats> {
+        let samples = self.samples.lock().ok()?;
+        let window: Vec<&Sample> = samples
+            .iter()
+            .filter(|sample| sample.time >= start && sample.time <= end)
+            .collect();
+        if window.is_empty() {
+            return None;
+        }
+
+        Some(ResourceStats {
+            cpu_peak: window.iter().map(|s| s.cpu).fold(0.0, f32::max),
+            cpu_avg: window.iter().map(|s| s.cpu).sum::<f32>() / window.len() as f32,
+            rss_peak: window.iter().map(|s| s.rss).max().unwrap_or_default(),
+            rss_avg: window.iter().map(|s| s.rss).sum::<u64>() / window.len() as u64,
+        })
+    }
+
+    /// Memory usage of the most recent sample
+    pub fn latest_rss(&self) -> Option<u64> {
+        Some(self.samples.lock().ok()?.last()?.rss)
+    }
+
+    /// Peak and average usage over the whole run
+    pub fn stats(&self) -> Option<ResourceStats> {
+        let (first, last) = {
+            let samples = self.samples.lock().ok()?;
+            (samples.first()?.time, samples.last()?.time)
+        };
+        self.stats_between(first, last)
+    }
+}
+impl Drop for ResourceMonitor {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Checks whether `pid` is `root` or one of its descendants
+fn is_in_tree(system: &System, mut pid: Pid, root: Pid) -> bool {
+    // Chromium spawns its processes (zygote, renderers, gpu) at most a few levels deep
+    for _ in 0..8 {
+        if pid == root {
+            return true;
+        }
+        match system.process(pid).and_then(|process| process.parent()) {
+            Some(parent) => pid = parent,
+            None => return false,
+        }
+    }
+    false
+}
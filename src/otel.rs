@@ -0,0 +1,63 @@
+use std::{
+    sync::OnceLock,
+    time::{Duration, SystemTime},
+};
+
+use opentelemetry::trace::{Span, Tracer, TracerProvider};
+use opentelemetry_otlp::SpanExporter;
+use opentelemetry_sdk::{
+    trace::{SdkTracer, SdkTracerProvider},
+    Resource,
+};
+use tracing::Subscriber;
+use tracing_opentelemetry::{OpenTelemetryLayer, OpenTelemetrySpanExt};
+use tracing_subscriber::registry::LookupSpan;
+
+use crate::Result;
+
+static PROVIDER: OnceLock<SdkTracerProvider> = OnceLock::new();
+
+/// Creates a tracing layer exporting all spans via OTLP
+/// The collector is configured through the standard `OTEL_EXPORTER_OTLP_*` environment variables.
+pub fn layer<S>() -> Result<OpenTelemetryLayer<S, SdkTracer>>
+where
+    S: Subscriber + for<'span> LookupSpan<'span>,
+{
+    let exporter = SpanExporter::builder().with_http().build()?;
+    let provider = SdkTracerProvider::builder()
+        .with_batch_exporter(exporter)
+        .with_resource(Resource::builder().with_service_name("web2pdf").build())
+        .build();
+    let tracer = provider.tracer("web2pdf");
+    let _ = PROVIDER.set(provider);
+
+    Ok(tracing_opentelemetry::layer().with_tracer(tracer))
+}
+
+/// Records a finished phase of a conversion as child span of the current span
+///
+/// # Arguments
+/// * `name` - The name of the phase
+/// * `duration` - The duration of the phase, which ended now
+pub fn record_phase(name: &'static str, duration: Duration) {
+    let Some(provider) = PROVIDER.get() else {
+        return;
+    };
+    let end = SystemTime::now();
+    let tracer = provider.tracer("web2pdf");
+    let parent = tracing::Span::current().context();
+    let mut span = tracer
+        .span_builder(name)
+        .with_start_time(end - duration)
+        .start_with_context(&tracer, &parent);
+    span.end_with_timestamp(end);
+}
+
+/// Exports all remaining spans, call before exiting
+pub fn shutdown() {
+    if let Some(provider) = PROVIDER.get() {
+        if let Err(e) = provider.shutdown() {
+            eprintln!("Failed to export traces: {}", e);
+        }
+    }
+}
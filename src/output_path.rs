@@ -0,0 +1,145 @@
+use std::path::{Component, Path, PathBuf};
+
+use unicode_normalization::UnicodeNormalization;
+
+/// Names of devices that can't be used as file names on Windows, with any extension
+const RESERVED_NAMES: [&str; 22] = [
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+    "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+/// Longest path the Windows APIs accept without the `\\?\` prefix
+const MAX_PATH: usize = 260;
+
+/// Makes a file name valid on all platforms, e.g. for names derived from page titles
+/// The name is normalized to NFC (so visually identical names are equal), characters not allowed
+/// on Windows are replaced with `_`, trailing dots and spaces are removed and reserved device
+/// names (e.g. `CON` or `nul.pdf`) are prefixed with `_`.
+///
+/// # Arguments
+/// * `name` - The file name, without directories
+pub fn sanitize_file_name(name: &str) -> String {
+    let mut sanitized: String = name
+        .nfc()
+        .map(|c| match c {
+            '<' | '>' | ':' | '"' | '/' | '\\' | '|' | '?' | '*' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect();
+    sanitized.truncate(sanitized.trim_end_matches(['.', ' ']).len());
+    let stem = sanitized.split('.').next().unwrap_or_default().trim_end();
+    if sanitized.is_empty()
+        || RESERVED_NAMES
+            .iter()
+            .any(|reserved| stem.eq_ignore_ascii_case(reserved))
+    {
+        sanitized.insert(0, '_');
+    }
+    sanitized
+}
+
+/// Prepares an output path given on the command line
+/// The file name is normalized to NFC, on Windows it is also sanitized (see `sanitize_file_name`),
+/// as e.g. `con.pdf` would otherwise write to the console instead of a file.
+///
+/// # Arguments
+/// * `path` - The output path
+pub fn prepare_output_path(path: &Path) -> PathBuf {
+    let Some(name) = path.file_name() else {
+        return path.to_path_buf();
+    };
+    let Some(name) = name.to_str() else {
+        // Not valid Unicode, so there is nothing to normalize
+        return path.to_path_buf();
+    };
+    if cfg!(windows) {
+        path.with_file_name(sanitize_file_name(name))
+    } else {
+        path.with_file_name(name.nfc().collect::<String>())
+    }
+}
+
+/// The path to open a file with, long paths get the `\\?\` prefix on Windows
+/// Without it Windows fails to open paths longer than 260 characters.
+/// The path is returned unchanged on other platforms.
+///
+/// # Arguments
+/// * `path` - The path of the file
+pub fn long_path(path: &Path) -> PathBuf {
+    if !cfg!(windows) || path.as_os_str().len() < MAX_PATH {
+        return path.to_path_buf();
+    }
+    let Ok(absolute) = std::path::absolute(path) else {
+        return path.to_path_buf();
+    };
+    let mut components = absolute.components();
+    // Paths with the prefix are not normalized by Windows, so `.` and `..` are resolved here
+    let prefixed = match components.next() {
+        Some(Component::Prefix(prefix)) => match prefix.kind() {
+            std::path::Prefix::Disk(drive) => format!(r"\\?\{}:", drive as char),
+            std::path::Prefix::UNC(server, share) => format!(
+                r"\\?\UNC\{}\{}",
+                server.to_string_lossy(),
+                share.to_string_lossy()
+            ),
+            // Already a verbatim or device path
+            _ => return absolute,
+        },
+        _ => return absolute,
+    };
+    let mut result = PathBuf::from(prefixed);
+    for component in components {
+        match component {
+            Component::RootDir => result.push(r"\"),
+            Component::CurDir => {}
+            Component::ParentDir => {
+                result.pop();
+            }
+            component => result.push(component),
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sanitize_invalid_characters() {
+        assert_eq!(sanitize_file_name("a<b>c:d\"e"), "a_b_c_d_e");
+        assert_eq!(sanitize_file_name("a/b\\c|d?e*f"), "a_b_c_d_e_f");
+        assert_eq!(sanitize_file_name("tab\there\n"), "tab_here_");
+        assert_eq!(sanitize_file_name("page.pdf"), "page.pdf");
+    }
+
+    #[test]
+    fn sanitize_trailing_dots_and_spaces() {
+        assert_eq!(sanitize_file_name("name. . "), "name");
+        assert_eq!(sanitize_file_name("name.pdf..."), "name.pdf");
+        assert_eq!(sanitize_file_name(" name"), " name");
+        assert_eq!(sanitize_file_name("..."), "_");
+        assert_eq!(sanitize_file_name(""), "_");
+    }
+
+    #[test]
+    fn sanitize_reserved_names() {
+        assert_eq!(sanitize_file_name("CON"), "_CON");
+        assert_eq!(sanitize_file_name("nul.pdf"), "_nul.pdf");
+        assert_eq!(sanitize_file_name("Com1.tar.gz"), "_Com1.tar.gz");
+        assert_eq!(sanitize_file_name("lpt9 .pdf"), "_lpt9 .pdf");
+        assert_eq!(sanitize_file_name("aux."), "_aux");
+        assert_eq!(sanitize_file_name("console.pdf"), "console.pdf");
+        assert_eq!(sanitize_file_name("COM10"), "COM10");
+    }
+
+    #[test]
+    fn sanitize_normalizes_to_nfc() {
+        // "e" followed by a combining acute accent
+        assert_eq!(sanitize_file_name("cafe\u{301}.pdf"), "caf\u{e9}.pdf");
+        assert_eq!(
+            sanitize_file_name("caf\u{e9}.pdf"),
+            sanitize_file_name("cafe\u{301}.pdf")
+        );
+    }
+}
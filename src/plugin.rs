@@ -0,0 +1,152 @@
+//! Sandboxed WebAssembly plugins, see `--plugin`
+//!
+//! A plugin is a WebAssembly module without imports that exports its `memory` and
+//! `alloc(len: i32) -> i32`, which returns the address of `len` free bytes. Every hook is optional,
+//! takes the address and length of its input and returns the address of its output in the upper
+//! and its length in the lower 32 bits of an `i64`, or 0 to return nothing:
+//! * `before_navigate(url)` - JavaScript that runs in the page before the scripts of the page
+//! * `before_print(url)` - JavaScript that runs once the page is prepared, right before printing
+//! * `after_pdf(pdf)` - The changed PDF file, before it is encrypted
+
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+};
+
+use tracing::debug;
+use wasmtime::{Config, Engine, Instance, Module, Store, StoreLimits, StoreLimitsBuilder};
+
+use crate::Result;
+
+/// Instructions a hook may run, so a runaway plugin can't hang the conversion
+const FUEL: u64 = 20_000_000_000;
+/// Memory a hook may use
+const MAX_MEMORY: usize = 1 << 30;
+
+/// A compiled plugin, every hook runs in a new instance
+pub struct Plugin {
+    path: PathBuf,
+    module: Module,
+}
+
+impl Plugin {
+    /// Compiles a plugin, fails if it imports anything or doesn't export `memory` and `alloc`
+    ///
+    /// # Arguments
+    /// * `path` - The WebAssembly module
+    pub fn from_file(path: &Path) -> Result<Plugin> {
+        let mut config = Config::new();
+        config.consume_fuel(true);
+        let engine = Engine::new(&config)?;
+        let module = Module::from_file(&engine, path)?;
+        if let Some(import) = module.imports().next() {
+            return Err(format!(
+                "plugins run sandboxed, but it imports {}::{}",
+                import.module(),
+                import.name()
+            )
+            .into());
+        }
+        for export in ["memory", "alloc"] {
+            if module.get_export(export).is_none() {
+                return Err(format!("the plugin doesn't export {}", export).into());
+            }
+        }
+        Ok(Plugin {
+            path: path.to_path_buf(),
+            module,
+        })
+    }
+
+    /// JavaScript to add to the page before navigating
+    ///
+    /// # Arguments
+    /// * `url` - The URL of the job
+    pub async fn before_navigate(&self, url: &str) -> Result<Option<String>> {
+        self.script("before_navigate", url).await
+    }
+
+    /// JavaScript to run in the page before printing
+    ///
+    /// # Arguments
+    /// * `url` - The URL of the job
+    pub async fn before_print(&self, url: &str) -> Result<Option<String>> {
+        self.script("before_print", url).await
+    }
+
+    /// Lets the plugin change the PDF file
+    ///
+    /// # Arguments
+    /// * `pdf` - The PDF file
+    ///
+    /// # Returns
+    /// The changed PDF file, or the original if the plugin returned nothing
+    pub async fn after_pdf(&self, pdf: Vec<u8>) -> Result<Vec<u8>> {
+        if self.module.get_export("after_pdf").is_none() {
+            return Ok(pdf);
+        }
+        let changed = self.call("after_pdf", pdf.clone()).await?;
+        if !changed
+            .as_ref()
+            .is_none_or(|changed| changed.starts_with(b"%PDF-"))
+        {
+            return Err(format!("after_pdf of {:?} returned no PDF file", self.path).into());
+        }
+        Ok(changed.unwrap_or(pdf))
+    }
+
+    async fn script(&self, hook: &'static str, url: &str) -> Result<Option<String>> {
+        if self.module.get_export(hook).is_none() {
+            return Ok(None);
+        }
+        let Some(script) = self.call(hook, url.as_bytes().to_vec()).await? else {
+            return Ok(None);
+        };
+        let script = String::from_utf8(script)
+            .map_err(|_| format!("{} of {:?} returned invalid UTF-8", hook, self.path))?;
+        debug!("{} of {:?} returned a script for {}", hook, self.path, url);
+        Ok(Some(script))
+    }
+
+    /// Runs a hook on the blocking threads
+    async fn call(&self, hook: &'static str, input: Vec<u8>) -> Result<Option<Vec<u8>>> {
+        let module = self.module.clone();
+        tokio::task::spawn_blocking(move || call(&module, hook, &input).map_err(|e| e.to_string()))
+            .await?
+            .map_err(|e| format!("{} of {:?} failed: {}", hook, self.path, e).into())
+    }
+}
+
+impl fmt::Debug for Plugin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Plugin").field(&self.path).finish()
+    }
+}
+
+/// Copies the input into a new instance of the module, calls the hook and copies its output
+fn call(module: &Module, hook: &str, input: &[u8]) -> wasmtime::Result<Option<Vec<u8>>> {
+    let limits = StoreLimitsBuilder::new().memory_size(MAX_MEMORY).build();
+    let mut store: Store<StoreLimits> = Store::new(module.engine(), limits);
+    store.limiter(|limits| limits);
+    store.set_fuel(FUEL)?;
+    let instance = Instance::new(&mut store, module, &[])?;
+    let memory = instance
+        .get_memory(&mut store, "memory")
+        .ok_or_else(|| wasmtime::Error::msg("memory is not a memory"))?;
+    let alloc = instance.get_typed_func::<i32, i32>(&mut store, "alloc")?;
+    let function = instance.get_typed_func::<(i32, i32), i64>(&mut store, hook)?;
+
+    let len = i32::try_from(input.len())?;
+    let address = alloc.call(&mut store, len)?;
+    memory.write(&mut store, address as u32 as usize, input)?;
+    let output = function.call(&mut store, (address, len))? as u64;
+    if output == 0 {
+        return Ok(None);
+    }
+    let (address, len) = ((output >> 32) as usize, (output as u32) as usize);
+    memory
+        .data(&store)
+        .get(address..address + len)
+        .map(|output| Some(output.to_vec()))
+        .ok_or_else(|| wasmtime::Error::msg("the output is outside of the memory"))
+}
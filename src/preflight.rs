@@ -0,0 +1,113 @@
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use sysinfo::Disks;
+
+use crate::{Cli, OutputFormat, Result};
+
+/// Rough size of a single output when there is no earlier report to estimate from
+fn typical_size(format: OutputFormat) -> u64 {
+    const MIB: u64 = 1024 * 1024;
+    match format {
+        OutputFormat::Pdf => MIB,
+        OutputFormat::Png => 4 * MIB,
+        OutputFormat::Jpeg => MIB,
+        OutputFormat::Mhtml => 2 * MIB,
+        OutputFormat::Html => 3 * MIB,
+    }
+}
+
+/// Checks that the filesystems of the outputs have space for the whole batch, see `--skip-disk-check`
+/// The size of an output is the average of the successful jobs of an earlier report at the
+/// `--report` path, or a rough per-format size. A quarter of the estimate is required on top.
+///
+/// # Arguments
+/// * `cli` - The cli, for the outputs, the report and the size budget
+///
+/// # Returns
+/// A `Result` containing an empty `()` value or an error explaining the missing space.
+pub fn check_disk_space(cli: &Cli) -> Result<()> {
+    let per_output = cli
+        .report
+        .as_deref()
+        .and_then(average_reported_size)
+        .unwrap_or_else(|| typical_size(cli.format));
+
+    let mut needed: BTreeMap<PathBuf, u64> = BTreeMap::new();
+    for pair in &cli.url_path_pairs {
+        *needed.entry(existing_dir(&pair.path)).or_default() += per_output;
+    }
+    if let Some(merge) = &cli.merge {
+        // The merged PDF is written while the single PDFs still exist
+        *needed.entry(existing_dir(merge)).or_default() +=
+            per_output * cli.url_path_pairs.len() as u64;
+    }
+
+    let disks = Disks::new_with_refreshed_list();
+    let mut per_disk: BTreeMap<&Path, (u64, u64)> = BTreeMap::new();
+    for (dir, bytes) in needed {
+        // The disk mounted at the longest prefix of the directory holds it
+        let Some(disk) = disks
+            .list()
+            .iter()
+            .filter(|disk| dir.starts_with(disk.mount_point()))
+            .max_by_key(|disk| disk.mount_point().as_os_str().len())
+        else {
+            continue;
+        };
+        let entry = per_disk
+            .entry(disk.mount_point())
+            .or_insert((0, disk.available_space()));
+        entry.0 += bytes;
+    }
+
+    for (mount_point, (bytes, available)) in per_disk {
+        let bytes = cli
+            .total_size_budget
+            .map_or(bytes, |budget| bytes.min(budget));
+        let required = bytes + bytes / 4;
+        tracing::debug!(
+            "Outputs on {:?} need about {} bytes, {} available",
+            mount_point,
+            bytes,
+            available
+        );
+        if required > available {
+            return Err(format!(
+                "the outputs need about {} MiB on {}, but only {} MiB are free (skip this check with --skip-disk-check)",
+                required / (1024 * 1024),
+                mount_point.display(),
+                available / (1024 * 1024)
+            )
+            .into());
+        }
+    }
+    Ok(())
+}
+
+/// The average size of the successful jobs of a JSON report, see `--report`
+fn average_reported_size(report: &Path) -> Option<u64> {
+    let report: serde_json::Value = serde_json::from_slice(&std::fs::read(report).ok()?).ok()?;
+    let sizes: Vec<u64> = report["jobs"]
+        .as_array()?
+        .iter()
+        .filter(|job| job["status"] == "ok")
+        .filter_map(|job| job["size"].as_u64())
+        .collect();
+    if sizes.is_empty() {
+        return None;
+    }
+    Some(sizes.iter().sum::<u64>() / sizes.len() as u64)
+}
+
+/// The closest existing directory an output will be written in, as an absolute path
+fn existing_dir(output: &Path) -> PathBuf {
+    let absolute = std::path::absolute(output).unwrap_or_else(|_| output.to_path_buf());
+    absolute
+        .ancestors()
+        .skip(1)
+        .find_map(|dir| std::fs::canonicalize(dir).ok())
+        .unwrap_or(absolute)
+}
@@ -0,0 +1,135 @@
+use std::{
+    fs::File,
+    io::Write,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex, OnceLock,
+    },
+    time::{Duration, Instant},
+};
+
+use serde_json::{json, Value};
+
+use crate::{summary::JobSummary, Result};
+
+/// Phases of a conversion in order, see `PhaseTimer::mark`
+const PHASES: [&str; 5] = ["setup", "navigate", "prepare", "print", "finish"];
+
+static PROGRESS: OnceLock<Progress> = OnceLock::new();
+
+/// Newline-delimited JSON progress events, see `--progress-json`
+struct Progress {
+    output: Mutex<Box<dyn Write + Send>>,
+    start: Instant,
+    total: usize,
+    done: AtomicUsize,
+}
+impl Progress {
+    fn emit(&self, mut event: Value) {
+        event["elapsed_ms"] = (self.start.elapsed().as_millis() as u64).into();
+        let mut output = self.output.lock().expect("progress output poisoned");
+        // Progress is best effort, a closed reader must not fail the run
+        let _ = writeln!(output, "{}", event).and_then(|_| output.flush());
+    }
+}
+
+/// Starts emitting progress events to `destination` and emits the start event
+///
+/// # Arguments
+/// * `destination` - "-" for stdout, "fd:N" for an inherited file descriptor (Unix) or a file path
+/// * `total` - The number of jobs
+pub fn init(destination: &str, total: usize) -> Result<()> {
+    let output: Box<dyn Write + Send> = if destination == "-" {
+        Box::new(std::io::stdout())
+    } else if let Some(fd) = destination.strip_prefix("fd:") {
+        let fd: u32 = fd
+            .parse()
+            .map_err(|_| format!("invalid file descriptor {:?}", fd))?;
+        if !cfg!(unix) {
+            return Err("file descriptors are only supported on Unix".into());
+        }
+        Box::new(
+            File::options()
+                .append(true)
+                .open(format!("/dev/fd/{}", fd))?,
+        )
+    } else {
+        Box::new(File::create(destination)?)
+    };
+    let progress = Progress {
+        output: Mutex::new(output),
+        start: Instant::now(),
+        total,
+        done: AtomicUsize::new(0),
+    };
+    progress.emit(json!({ "event": "start", "jobs": total }));
+    let _ = PROGRESS.set(progress);
+    Ok(())
+}
+
+/// Emits that a job started converting
+pub fn job_started(job: usize, url: &str) {
+    if let Some(progress) = PROGRESS.get() {
+        progress.emit(json!({ "event": "job_started", "job": job, "url": url }));
+    }
+}
+
+/// Emits a finished phase of a job, with the share of the job's phases done
+pub fn phase(job: usize, name: &'static str, duration: Duration) {
+    let Some(progress) = PROGRESS.get() else {
+        return;
+    };
+    let mut event = json!({
+        "event": "phase",
+        "job": job,
+        "phase": name,
+        "duration_ms": duration.as_millis() as u64,
+    });
+    // Optional phases (e.g. waiting with --pause-before-print) don't advance the job
+    if let Some(index) = PHASES.iter().position(|phase| *phase == name) {
+        event["percent"] = ((index + 1) * 100 / PHASES.len()).into();
+    }
+    progress.emit(event);
+}
+
+/// Emits that a job finished, with the share of all jobs done
+pub fn job_finished(job: usize, summary: &JobSummary) {
+    let Some(progress) = PROGRESS.get() else {
+        return;
+    };
+    let done = progress.done.fetch_add(1, Ordering::Relaxed) + 1;
+    let mut event = json!({
+        "event": "job_finished",
+        "job": job,
+        "url": summary.url,
+        "path": summary.path,
+        "duration_ms": summary.duration.as_millis() as u64,
+        "done": done,
+        "total": progress.total,
+        "percent": done * 100 / progress.total.max(1),
+    });
+    match &summary.outcome {
+        Ok(stats) => {
+            event["status"] = "ok".into();
+            event["pages"] = stats.pages.into();
+            event["size"] = stats.size.into();
+        }
+        Err(error) => {
+            event["status"] = "failed".into();
+            event["error"] = error.as_str().into();
+        }
+    }
+    progress.emit(event);
+}
+
+/// Emits the end of the run
+pub fn finish(jobs: &[JobSummary]) {
+    if let Some(progress) = PROGRESS.get() {
+        let failed = jobs.iter().filter(|job| job.outcome.is_err()).count();
+        progress.emit(json!({
+            "event": "finish",
+            "succeeded": jobs.len() - failed,
+            "failed": failed,
+        }));
+    }
+}
@@ -0,0 +1,179 @@
+use std::{path::Path, sync::Mutex};
+
+use rusqlite::{params, Connection, OptionalExtension};
+
+use crate::{
+    serve::{JobError, JobStatus},
+    OutputFormat, Result,
+};
+
+/// Async jobs of the server persisted in a SQLite database, see `serve --queue`
+/// Jobs that were queued or running when the server stopped are queued again on start.
+pub struct Queue {
+    connection: Mutex<Connection>,
+}
+
+impl Queue {
+    /// Opens the database, it is created if it doesn't exist
+    ///
+    /// # Arguments
+    /// * `path` - The database file
+    pub fn open(path: &Path) -> Result<Queue> {
+        let connection = Connection::open(path)?;
+        connection.execute_batch(
+            "PRAGMA journal_mode = WAL;
+             CREATE TABLE IF NOT EXISTS jobs (
+                 id INTEGER PRIMARY KEY AUTOINCREMENT,
+                 request TEXT NOT NULL,
+                 status TEXT NOT NULL,
+                 error TEXT,
+                 output BLOB,
+                 format TEXT
+             );
+             UPDATE jobs SET status = 'queued' WHERE status = 'running';",
+        )?;
+        // Databases of earlier versions have no owners, their jobs belong to no API key
+        let has_owner = connection.prepare("SELECT owner FROM jobs LIMIT 0").is_ok();
+        if !has_owner {
+            connection.execute_batch("ALTER TABLE jobs ADD COLUMN owner TEXT")?;
+        }
+        Ok(Queue {
+            connection: Mutex::new(connection),
+        })
+    }
+
+    /// Adds a queued job
+    ///
+    /// # Arguments
+    /// * `request` - The request of the job as JSON, to run it again after a restart
+    /// * `owner` - The name of the API key that started the job
+    ///
+    /// # Returns
+    /// A `Result` containing the id of the job or an error.
+    pub fn insert(&self, request: &str, owner: Option<&str>) -> Result<u64> {
+        let connection = self.connection.lock().unwrap();
+        connection.execute(
+            "INSERT INTO jobs (request, status, owner) VALUES (?1, 'queued', ?2)",
+            params![request, owner],
+        )?;
+        Ok(connection.last_insert_rowid() as u64)
+    }
+
+    /// The ids and requests of the queued jobs, oldest first
+    pub fn queued(&self) -> Result<Vec<(u64, String)>> {
+        let connection = self.connection.lock().unwrap();
+        let mut statement = connection
+            .prepare("SELECT id, request FROM jobs WHERE status = 'queued' ORDER BY id")?;
+        let jobs = statement
+            .query_map([], |row| Ok((row.get::<_, i64>(0)? as u64, row.get(1)?)))?
+            .collect::<rusqlite::Result<_>>()?;
+        Ok(jobs)
+    }
+
+    /// Marks a queued job as running
+    ///
+    /// # Returns
+    /// A `Result` containing whether the job was still queued (and not cancelled) or an error.
+    pub fn start(&self, id: u64) -> Result<bool> {
+        let changed = self.connection.lock().unwrap().execute(
+            "UPDATE jobs SET status = 'running' WHERE id = ?1 AND status = 'queued'",
+            params![id as i64],
+        )?;
+        Ok(changed == 1)
+    }
+
+    /// Records the output or error of a job, unless it was cancelled meanwhile
+    pub fn finish(
+        &self,
+        id: u64,
+        outcome: &std::result::Result<(&[u8], OutputFormat), JobError>,
+    ) -> Result<()> {
+        let connection = self.connection.lock().unwrap();
+        match outcome {
+            Ok((output, format)) => connection.execute(
+                "UPDATE jobs SET status = 'done', output = ?2, format = ?3
+                 WHERE id = ?1 AND status IN ('queued', 'running')",
+                params![id as i64, output, format_name(*format)?],
+            )?,
+            Err(error) => {
+                let (status, error) = match error {
+                    JobError::Failed(error) => ("failed", error),
+                    JobError::TimedOut(error) => ("timed_out", error),
+                };
+                connection.execute(
+                    "UPDATE jobs SET status = ?2, error = ?3
+                     WHERE id = ?1 AND status IN ('queued', 'running')",
+                    params![id as i64, status, error],
+                )?
+            }
+        };
+        Ok(())
+    }
+
+    /// The status of a job, None if there is no such job of the owner
+    pub fn status(&self, id: u64, owner: Option<&str>) -> Result<Option<JobStatus>> {
+        status(&self.connection.lock().unwrap(), id, owner)
+    }
+
+    /// The output of a finished job, None if there is no such job of the owner or it is not done
+    pub fn output(&self, id: u64, owner: Option<&str>) -> Result<Option<(Vec<u8>, OutputFormat)>> {
+        let connection = self.connection.lock().unwrap();
+        let row: Option<(Vec<u8>, String)> = connection
+            .query_row(
+                "SELECT output, format FROM jobs WHERE id = ?1 AND owner IS ?2 AND status = 'done'",
+                params![id as i64, owner],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()?;
+        row.map(|(output, format)| {
+            let format = serde_json::from_value(serde_json::Value::String(format))?;
+            Ok((output, format))
+        })
+        .transpose()
+    }
+
+    /// Cancels a queued or running job, or deletes a finished job with its output
+    ///
+    /// # Returns
+    /// A `Result` containing the status before, None if there is no such job of the owner, or an error.
+    pub fn cancel(&self, id: u64, owner: Option<&str>) -> Result<Option<JobStatus>> {
+        let connection = self.connection.lock().unwrap();
+        let status = status(&connection, id, owner)?;
+        match status {
+            Some(JobStatus::Queued | JobStatus::Running) => connection.execute(
+                "UPDATE jobs SET status = 'cancelled' WHERE id = ?1",
+                params![id as i64],
+            )?,
+            Some(_) => connection.execute("DELETE FROM jobs WHERE id = ?1", params![id as i64])?,
+            None => 0,
+        };
+        Ok(status)
+    }
+}
+
+/// The status of a job, None if there is no such job of the owner
+fn status(connection: &Connection, id: u64, owner: Option<&str>) -> Result<Option<JobStatus>> {
+    let row: Option<(String, Option<String>)> = connection
+        .query_row(
+            "SELECT status, error FROM jobs WHERE id = ?1 AND owner IS ?2",
+            params![id as i64, owner],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .optional()?;
+    Ok(row.map(|(status, error)| match status.as_str() {
+        "queued" => JobStatus::Queued,
+        "running" => JobStatus::Running,
+        "done" => JobStatus::Done,
+        "failed" => JobStatus::Failed(error.unwrap_or_default()),
+        "timed_out" => JobStatus::TimedOut(error.unwrap_or_default()),
+        _ => JobStatus::Cancelled,
+    }))
+}
+
+/// The name of a format as in --format, e.g. `pdf`
+fn format_name(format: OutputFormat) -> Result<String> {
+    match serde_json::to_value(format)? {
+        serde_json::Value::String(name) => Ok(name),
+        value => Err(format!("unexpected format {}", value).into()),
+    }
+}
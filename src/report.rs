@@ -0,0 +1,104 @@
+use std::{collections::BTreeMap, path::Path};
+
+use serde::Serialize;
+
+use crate::{monitor::ResourceStats, summary::JobSummary, Cli, Result};
+
+/// JSON report of a whole run
+#[derive(Debug, Serialize)]
+struct Report<'a> {
+    /// The browser product, e.g. "HeadlessChrome/131.0.6778.85"
+    browser: Option<String>,
+    succeeded: usize,
+    failed: usize,
+    /// Resource usage of the browser over the whole run
+    resources: Option<ResourceStats>,
+    jobs: Vec<JobReport<'a>>,
+}
+
+/// JSON report of a single URL-Path pair
+#[derive(Debug, Serialize)]
+struct JobReport<'a> {
+    url: &'a str,
+    path: &'a Path,
+    status: &'static str,
+    error: Option<&'a str>,
+    pages: Option<usize>,
+    size: Option<usize>,
+    /// Duration in seconds
+    duration: f64,
+    /// Duration of every phase in seconds
+    phases: BTreeMap<&'static str, f64>,
+    resources: Option<ResourceStats>,
+    /// Hash of the text content, with --alert-if-changed
+    content_hash: Option<&'a str>,
+    /// Whether the content changed since the baseline, None if the page is not in it
+    changed: Option<bool>,
+    /// The earlier output this one is identical to, with --dedup
+    duplicate_of: Option<&'a Path>,
+    /// The effective options of the job, after presets and per-job overrides
+    options: serde_json::Value,
+}
+
+/// Writes a JSON report of all jobs
+///
+/// # Arguments
+/// * `path` - The path to write the report to
+/// * `jobs` - The finished jobs
+/// * `resources` - Resource usage of the browser over the whole run
+/// * `browser` - The browser product the jobs were run with
+/// * `cli` - The cli the jobs were run with, in the order of its URL-Path pairs
+pub fn write_report(
+    path: &Path,
+    jobs: &[JobSummary],
+    resources: Option<ResourceStats>,
+    browser: Option<String>,
+    cli: &Cli,
+) -> Result<()> {
+    let options = serde_json::to_value(cli)?;
+    let failed = jobs.iter().filter(|job| job.outcome.is_err()).count();
+    let report = Report {
+        browser,
+        succeeded: jobs.len() - failed,
+        failed,
+        resources,
+        jobs: jobs
+            .iter()
+            .zip(&cli.url_path_pairs)
+            .map(|(job, pair)| {
+                let stats = job.outcome.as_ref().ok();
+                let mut options = options.clone();
+                options["viewport_width"] = pair.viewport_width.into();
+                for (field, value) in &pair.options {
+                    options[field] = value.clone();
+                }
+                JobReport {
+                    url: &job.url,
+                    path: &job.path,
+                    status: if stats.is_some() { "ok" } else { "failed" },
+                    error: job.outcome.as_ref().err().map(String::as_str),
+                    pages: stats.map(|stats| stats.pages),
+                    size: stats.map(|stats| stats.size),
+                    duration: job.duration.as_secs_f64(),
+                    phases: stats
+                        .map(|stats| {
+                            stats
+                                .phases
+                                .iter()
+                                .map(|(name, duration)| (*name, duration.as_secs_f64()))
+                                .collect()
+                        })
+                        .unwrap_or_default(),
+                    resources: job.resources,
+                    content_hash: stats.and_then(|stats| stats.content_hash.as_deref()),
+                    changed: stats.and_then(|stats| stats.changed),
+                    duplicate_of: stats.and_then(|stats| stats.duplicate_of.as_deref()),
+                    options,
+                }
+            })
+            .collect(),
+    };
+
+    std::fs::write(path, serde_json::to_string_pretty(&report)?)?;
+    Ok(())
+}
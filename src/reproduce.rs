@@ -0,0 +1,120 @@
+use std::path::PathBuf;
+
+use clap::{Args, Parser};
+
+use crate::{Cli, Result, URLPathPair};
+
+#[derive(Args, Debug, Clone)]
+pub struct ReproduceArgs {
+    #[clap(help = "JSON report written with --report")]
+    pub report: PathBuf,
+
+    #[clap(
+        long,
+        value_name = "N",
+        help = "Index of the job in the jobs of the report, starting at 0"
+    )]
+    pub job: usize,
+
+    #[clap(
+        long,
+        value_name = "PATH",
+        help = "Write the output to PATH instead of the recorded path"
+    )]
+    pub output: Option<PathBuf>,
+
+    #[clap(
+        long,
+        help = "Fail if the browser is not the one recorded in the report",
+        default_value_t = false
+    )]
+    pub require_browser_version: bool,
+}
+
+/// Builds the cli of a job recorded in a report, to re-run it with the same options.
+/// Options that are not recorded (passwords, credentials, header values, --adblock and --site-fixes)
+/// are taken from `cli`, jobs of --merge and --compare-breakpoints are reproduced as standalone files.
+///
+/// # Arguments
+/// * `cli` - The cli of this run, for the options that are not recorded
+/// * `args` - The arguments of the subcommand
+///
+/// # Returns
+/// The cli with the single URL-Path pair of the job
+pub fn reproduce_cli(cli: &Cli, args: &ReproduceArgs) -> Result<Cli> {
+    let report: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&args.report)?)?;
+    let jobs = report["jobs"].as_array().ok_or("the report has no jobs")?;
+    let job = jobs.get(args.job).ok_or_else(|| {
+        format!(
+            "the report has {} jobs, there is no job {}",
+            jobs.len(),
+            args.job
+        )
+    })?;
+    let url = job["url"].as_str().ok_or("the job has no url")?;
+    let options = job["options"]
+        .as_object()
+        .ok_or("the job has no recorded options, the report was written by an older version")?;
+
+    // Options added after the report was written keep their default
+    let mut merged = serde_json::to_value(Cli::try_parse_from(["web2pdf", url])?)?;
+    for (name, value) in options {
+        merged[name] = value.clone();
+    }
+    let mut reproduced: Cli = serde_json::from_value(merged)?;
+
+    let path = match &args.output {
+        Some(output) => output.clone(),
+        None => PathBuf::from(job["path"].as_str().ok_or("the job has no path")?),
+    };
+    reproduced.url_path_pairs = vec![URLPathPair {
+        url: url.to_string(),
+        path,
+        viewport_width: options
+            .get("viewport_width")
+            .and_then(|width| width.as_u64())
+            .map(|width| width as u32),
+        options: serde_json::Map::new(),
+    }];
+    if args.require_browser_version {
+        let browser = report["browser"]
+            .as_str()
+            .ok_or("the report has no browser version")?;
+        reproduced.required_browser = Some(browser.to_string());
+    }
+
+    // Not recorded in the report
+    reproduced.restore_unrecorded(cli);
+    let recorded_headers = options
+        .get("headers")
+        .and_then(|headers| headers.as_array())
+        .into_iter()
+        .flatten()
+        .filter_map(|name| name.as_str());
+    for name in recorded_headers {
+        if !cli
+            .headers
+            .iter()
+            .any(|(header, _)| header.eq_ignore_ascii_case(name))
+        {
+            eprintln!(
+                "warning: the job was run with the header {:?}, give it again with --header before the subcommand",
+                name
+            );
+        }
+    }
+
+    // A single job, without the steps run over all jobs
+    reproduced.merge = None;
+    reproduced.manifest = None;
+    reproduced.merge_toc = false;
+    reproduced.breakpoints = Vec::new();
+    reproduced.compare_breakpoints = false;
+    reproduced.report = None;
+    reproduced.alert_if_changed = None;
+    reproduced.dedup = None;
+    reproduced.total_size_budget = None;
+    reproduced.on_success = None;
+    reproduced.on_failure = None;
+    Ok(reproduced)
+}
@@ -0,0 +1,381 @@
+use std::{
+    str::FromStr,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use tokio::sync::{Notify, Semaphore, SemaphorePermit};
+use tracing::debug;
+
+use crate::monitor::ResourceMonitor;
+
+/// Maximum number of conversions running at the same time
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MaxParallel {
+    Limit(usize),
+    /// Adjust the limit based on throughput, failure rate and browser memory
+    Auto,
+}
+impl Serialize for MaxParallel {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        match self {
+            MaxParallel::Limit(limit) => serializer.serialize_u64(*limit as u64),
+            MaxParallel::Auto => serializer.serialize_str("auto"),
+        }
+    }
+}
+impl<'de> Deserialize<'de> for MaxParallel {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        match serde_json::Value::deserialize(deserializer)? {
+            serde_json::Value::Number(limit) => limit.to_string().parse(),
+            serde_json::Value::String(value) => value.parse(),
+            value => Err(format!("invalid value {}", value)),
+        }
+        .map_err(serde::de::Error::custom)
+    }
+}
+impl FromStr for MaxParallel {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        if s.eq_ignore_ascii_case("auto") {
+            return Ok(MaxParallel::Auto);
+        }
+        match s.parse::<usize>() {
+            Ok(limit) if limit > 0 => Ok(MaxParallel::Limit(limit)),
+            _ => Err(format!(
+                "invalid value '{}', expected a positive number or 'auto'",
+                s
+            )),
+        }
+    }
+}
+
+/// Limits how many jobs run at the same time
+pub struct Scheduler {
+    state: Mutex<State>,
+    notify: Notify,
+}
+
+struct State {
+    limit: usize,
+    running: usize,
+    tuner: Option<AutoTuner>,
+    ramp_up: Option<RampUp>,
+}
+
+/// Staggers the start of the first jobs, see `--ramp-up`
+struct RampUp {
+    duration: Duration,
+    /// Number of jobs spread over the duration
+    jobs: usize,
+    started: usize,
+    /// Start of the first job
+    start: Option<Instant>,
+}
+
+impl RampUp {
+    /// How long the next job has to wait for its start
+    fn next_delay(&mut self) -> Duration {
+        let start = *self.start.get_or_insert_with(Instant::now);
+        let index = self.started;
+        self.started += 1;
+        if index >= self.jobs {
+            return Duration::ZERO;
+        }
+        (start + self.duration.mul_f64(index as f64 / self.jobs as f64))
+            .saturating_duration_since(Instant::now())
+    }
+}
+
+impl Scheduler {
+    /// Creates a new scheduler
+    ///
+    /// # Arguments
+    /// * `max_parallel` - The limit, None for no limit
+    /// * `monitor` - Resource monitor of the browser, used to back off when memory runs low
+    /// * `ramp_up` - Time over which the start of the first parallel jobs is spread
+    /// * `jobs` - The total number of jobs
+    pub fn new(
+        max_parallel: Option<MaxParallel>,
+        monitor: Option<Arc<ResourceMonitor>>,
+        ramp_up: Option<Duration>,
+        jobs: usize,
+    ) -> Scheduler {
+        let (limit, tuner) = match max_parallel {
+            None => (usize::MAX, None),
+            Some(MaxParallel::Limit(limit)) => (limit, None),
+            Some(MaxParallel::Auto) => (AutoTuner::START, Some(AutoTuner::new(monitor))),
+        };
+        let ramp_up = ramp_up.map(|duration| RampUp {
+            duration,
+            jobs: limit.min(jobs),
+            started: 0,
+            start: None,
+        });
+        Scheduler {
+            state: Mutex::new(State {
+                limit,
+                running: 0,
+                tuner,
+                ramp_up,
+            }),
+            notify: Notify::new(),
+        }
+    }
+
+    /// The most jobs that may run at the same time, None without a limit
+    /// With `--max-parallel auto` this is the upper bound of the tuned limit.
+    pub fn max_running(&self) -> Option<usize> {
+        let state = self.state.lock().unwrap();
+        match &state.tuner {
+            Some(tuner) => Some(tuner.max),
+            None => (state.limit != usize::MAX).then_some(state.limit),
+        }
+    }
+
+    /// Waits until a job may start
+    pub async fn acquire(self: &Arc<Self>) -> Permit {
+        loop {
+            let notified = self.notify.notified();
+            let delay = {
+                let mut state = self.state.lock().unwrap();
+                if state.running < state.limit {
+                    state.running += 1;
+                    Some(
+                        state
+                            .ramp_up
+                            .as_mut()
+                            .map_or(Duration::ZERO, RampUp::next_delay),
+                    )
+                } else {
+                    None
+                }
+            };
+            if let Some(delay) = delay {
+                let mut permit = Permit {
+                    scheduler: Arc::clone(self),
+                    start: Instant::now(),
+                    success: false,
+                };
+                if !delay.is_zero() {
+                    debug!("Ramp up: delaying job start by {:?}", delay);
+                    tokio::time::sleep(delay).await;
+                    permit.start = Instant::now();
+                }
+                return permit;
+            }
+            notified.await;
+        }
+    }
+
+    fn release(&self, duration: Duration, success: bool) {
+        {
+            let mut state = self.state.lock().unwrap();
+            state.running -= 1;
+            let limit = state.limit;
+            if let Some(tuner) = state.tuner.as_mut() {
+                state.limit = tuner.on_complete(limit, duration, success);
+            }
+        }
+        self.notify.notify_waiters();
+    }
+}
+
+/// Allows a job to run, the slot is released when the permit is dropped
+pub struct Permit {
+    scheduler: Arc<Scheduler>,
+    start: Instant,
+    success: bool,
+}
+impl Permit {
+    /// Marks the job as successful, otherwise it counts as failed for auto tuning
+    pub fn succeeded(&mut self) {
+        self.success = true;
+    }
+}
+impl Drop for Permit {
+    fn drop(&mut self) {
+        self.scheduler.release(self.start.elapsed(), self.success);
+    }
+}
+
+/// Separate limits for the network bound navigation and the CPU bound printing of pages,
+/// within the limit of the `Scheduler`, and the bandwidth of every tab
+pub struct StageLimits {
+    navigating: Option<Semaphore>,
+    printing: Option<Semaphore>,
+    /// Share of --max-bandwidth of every tab in bytes per second
+    tab_bandwidth: Option<f64>,
+}
+impl StageLimits {
+    /// Creates the limits, None for no limit
+    pub fn new(max_navigating: Option<usize>, max_printing: Option<usize>) -> StageLimits {
+        StageLimits {
+            navigating: max_navigating.map(Semaphore::new),
+            printing: max_printing.map(Semaphore::new),
+            tab_bandwidth: None,
+        }
+    }
+
+    /// Splits the bandwidth of the browser evenly among the tabs, as the browser can only
+    /// throttle single tabs
+    ///
+    /// # Arguments
+    /// * `max_bandwidth` - The bandwidth of the browser in bytes per second, None for no limit
+    /// * `tabs` - The most tabs converting at the same time
+    pub fn with_bandwidth(mut self, max_bandwidth: Option<f64>, tabs: usize) -> StageLimits {
+        self.tab_bandwidth = max_bandwidth.map(|rate| rate / tabs.max(1) as f64);
+        self
+    }
+
+    /// The bandwidth of a tab in bytes per second, None for no limit
+    pub fn tab_bandwidth(&self) -> Option<f64> {
+        self.tab_bandwidth
+    }
+
+    /// Waits until a page may navigate, the slot is released when the permit is dropped
+    pub async fn navigating(&self) -> Option<SemaphorePermit<'_>> {
+        Self::acquire(&self.navigating).await
+    }
+
+    /// Waits until a page may be printed, the slot is released when the permit is dropped
+    pub async fn printing(&self) -> Option<SemaphorePermit<'_>> {
+        Self::acquire(&self.printing).await
+    }
+
+    async fn acquire(semaphore: &Option<Semaphore>) -> Option<SemaphorePermit<'_>> {
+        match semaphore {
+            // The semaphores are never closed
+            Some(semaphore) => semaphore.acquire().await.ok(),
+            None => None,
+        }
+    }
+}
+
+/// Total size of the outputs of a batch, new jobs are skipped once it is exceeded, see `--total-size-budget`
+pub struct SizeBudget {
+    limit: u64,
+    used: AtomicU64,
+}
+impl SizeBudget {
+    /// Creates a budget of `limit` bytes
+    pub fn new(limit: u64) -> SizeBudget {
+        SizeBudget {
+            limit,
+            used: AtomicU64::new(0),
+        }
+    }
+
+    /// Records the size of a created output
+    pub fn add(&self, size: usize) {
+        self.used.fetch_add(size as u64, Ordering::Relaxed);
+    }
+
+    /// Whether the outputs so far exceed the budget, jobs running already are still finished
+    pub fn exceeded(&self) -> bool {
+        self.used.load(Ordering::Relaxed) > self.limit
+    }
+
+    pub fn limit(&self) -> u64 {
+        self.limit
+    }
+}
+
+/// Hill climbing controller for the number of parallel jobs
+///
+/// After every window of completed jobs the throughput is compared to the previous window:
+/// if it improved the limit keeps moving in the same direction, otherwise the direction is reversed.
+/// Failures, rising latency or high browser memory usage cut the limit multiplicatively.
+struct AutoTuner {
+    max: usize,
+    monitor: Option<Arc<ResourceMonitor>>,
+    memory_limit: u64,
+    window_start: Instant,
+    completed: usize,
+    failed: usize,
+    latency: Duration,
+    best_latency: Option<Duration>,
+    last_throughput: f64,
+    direction: isize,
+}
+impl AutoTuner {
+    const START: usize = 2;
+
+    fn new(monitor: Option<Arc<ResourceMonitor>>) -> AutoTuner {
+        let cpus = std::thread::available_parallelism()
+            .map(|cpus| cpus.get())
+            .unwrap_or(4);
+        let mut system = sysinfo::System::new();
+        system.refresh_memory();
+
+        AutoTuner {
+            max: (cpus * 4).max(Self::START),
+            monitor,
+            // Leave some headroom for the rest of the system
+            memory_limit: system.total_memory() / 4 * 3,
+            window_start: Instant::now(),
+            completed: 0,
+            failed: 0,
+            latency: Duration::ZERO,
+            best_latency: None,
+            last_throughput: 0.0,
+            direction: 1,
+        }
+    }
+
+    /// Records a finished job and returns the new limit
+    fn on_complete(&mut self, limit: usize, duration: Duration, success: bool) -> usize {
+        self.completed += 1;
+        self.latency += duration;
+        if !success {
+            self.failed += 1;
+        }
+        if self.completed < limit.max(Self::START) {
+            return limit;
+        }
+
+        let throughput = self.completed as f64 / self.window_start.elapsed().as_secs_f64();
+        let latency = self.latency / self.completed as u32;
+        let best_latency = *self.best_latency.get_or_insert(latency);
+        let failure_rate = self.failed as f64 / self.completed as f64;
+        let memory_high = self
+            .monitor
+            .as_ref()
+            .and_then(|monitor| monitor.latest_rss())
+            .is_some_and(|rss| self.memory_limit > 0 && rss > self.memory_limit);
+
+        let new_limit = if failure_rate > 0.2 || memory_high || latency > best_latency * 4 {
+            self.direction = 1;
+            (limit * 3 / 4).max(1)
+        } else {
+            if throughput < self.last_throughput * 1.05 {
+                self.direction = -self.direction;
+            }
+            limit
+                .saturating_add_signed(self.direction)
+                .clamp(1, self.max)
+        };
+        debug!(
+            "Auto tuning: {:.2} jobs/s, {:.1}s latency, {:.0}% failed, memory high: {} -> parallel jobs {} -> {}",
+            throughput,
+            latency.as_secs_f64(),
+            failure_rate * 100.0,
+            memory_high,
+            limit,
+            new_limit
+        );
+
+        self.best_latency = Some(best_latency.min(latency));
+        self.last_throughput = throughput;
+        self.window_start = Instant::now();
+        self.completed = 0;
+        self.failed = 0;
+        self.latency = Duration::ZERO;
+        new_limit
+    }
+}
@@ -0,0 +1,167 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::OnceLock,
+};
+
+use rhai::{Dynamic, Engine, Map, Scope, AST};
+use serde_json::Value;
+use tracing::{info, warn};
+
+use crate::{manifest::parse_option, summary::JobSummary, Result, URLPathPair};
+
+static SCRIPT: OnceLock<Script> = OnceLock::new();
+
+/// A Rhai script that inspects and changes the jobs, see `--script`
+struct Script {
+    engine: Engine,
+    ast: AST,
+}
+impl Script {
+    fn has(&self, function: &str) -> bool {
+        self.ast.iter_functions().any(|f| f.name == function)
+    }
+
+    fn call(&self, function: &str, job: Map) -> Result<Dynamic> {
+        Ok(self
+            .engine
+            .call_fn::<Dynamic>(&mut Scope::new(), &self.ast, function, (job,))
+            .map_err(|e| format!("{} failed: {}", function, e))?)
+    }
+}
+
+/// Compiles the script, its functions are called by `before_jobs` and `after_job`
+///
+/// # Arguments
+/// * `path` - The script file
+pub fn init(path: &Path) -> Result<()> {
+    let mut engine = Engine::new();
+    // Scripts only see the jobs, a runaway loop must not hang the run
+    engine.set_max_operations(10_000_000);
+    let ast = engine
+        .compile_file(path.to_path_buf())
+        .map_err(|e| e.to_string())?;
+    let script = Script { engine, ast };
+    if !script.has("before_job") && !script.has("after_job") {
+        warn!(
+            "The script {:?} defines neither before_job nor after_job",
+            path
+        );
+    }
+    let _ = SCRIPT.set(script);
+    Ok(())
+}
+
+/// Calls `before_job(job)` of the script for every job
+/// `job` is a map with the `index`, `url`, `path` and `options` (the overrides of the job, named
+/// like the long options as in a --manifest). The function returns the changed job, `()` to
+/// keep it or `false` to skip it.
+///
+/// # Arguments
+/// * `pairs` - The URL-Path pairs, skipped jobs are removed
+pub fn before_jobs(pairs: &mut Vec<URLPathPair>) -> Result<()> {
+    let Some(script) = SCRIPT.get().filter(|script| script.has("before_job")) else {
+        return Ok(());
+    };
+    let mut kept = Vec::with_capacity(pairs.len());
+    for (index, mut pair) in pairs.drain(..).enumerate() {
+        let mut job = Map::new();
+        job.insert("index".into(), (index as i64).into());
+        job.insert("url".into(), pair.url.clone().into());
+        job.insert(
+            "path".into(),
+            pair.path.to_string_lossy().to_string().into(),
+        );
+        job.insert("options".into(), rhai::serde::to_dynamic(&pair.options)?);
+
+        let returned = script.call("before_job", job)?;
+        if returned.is_unit() {
+            kept.push(pair);
+            continue;
+        }
+        if returned.as_bool() == Ok(false) {
+            info!("The script skipped {}", pair.url);
+            continue;
+        }
+        let job: serde_json::Map<String, Value> = rhai::serde::from_dynamic(&returned)
+            .map_err(|e| format!("before_job returned neither a job nor false: {}", e))?;
+        if let Some(url) = job.get("url").and_then(Value::as_str) {
+            pair.url = crate::resolve_url(url);
+        }
+        if let Some(path) = job.get("path").and_then(Value::as_str) {
+            pair.path = crate::prepare_output_path(Path::new(path));
+        }
+        if let Some(Value::Object(options)) = job.get("options") {
+            let mut changed = serde_json::Map::new();
+            for (name, value) in options {
+                // Unchanged options are kept as they are, they are already parsed
+                if let Some(value) = pair.options.get(name).filter(|old| *old == value) {
+                    changed.insert(name.clone(), value.clone());
+                    continue;
+                }
+                let (field, value) = parse_option(name, value)
+                    .map_err(|e| format!("option {:?} of {}: {}", name, pair.url, e))?;
+                changed.insert(field, value);
+            }
+            pair.options = changed;
+        }
+        kept.push(pair);
+    }
+    *pairs = kept;
+    Ok(())
+}
+
+/// Calls `after_job(job)` of the script for a finished job
+/// `job` is a map with the `index`, `url`, `path`, `status` ("success" or "failure"),
+/// `duration_ms` and `pages` and `size` on success or `error` on failure. The function returns
+/// a new path to move the output to, or `()` to keep it.
+///
+/// # Arguments
+/// * `index` - The index of the job
+/// * `job` - The finished job, its path is updated if the output was moved
+pub async fn after_job(index: usize, job: &mut JobSummary) -> Result<()> {
+    let Some(script) = SCRIPT.get().filter(|script| script.has("after_job")) else {
+        return Ok(());
+    };
+    let mut map = Map::new();
+    map.insert("index".into(), (index as i64).into());
+    map.insert("url".into(), job.url.clone().into());
+    map.insert("path".into(), job.path.to_string_lossy().to_string().into());
+    map.insert(
+        "duration_ms".into(),
+        (job.duration.as_millis() as i64).into(),
+    );
+    match &job.outcome {
+        Ok(stats) => {
+            map.insert("status".into(), "success".into());
+            map.insert("pages".into(), (stats.pages as i64).into());
+            map.insert("size".into(), (stats.size as i64).into());
+        }
+        Err(error) => {
+            map.insert("status".into(), "failure".into());
+            map.insert("error".into(), error.clone().into());
+        }
+    }
+
+    let returned = script.call("after_job", map)?;
+    if returned.is_unit() {
+        return Ok(());
+    }
+    let path = PathBuf::from(
+        returned
+            .into_string()
+            .map_err(|kind| format!("after_job returned a {} instead of a path", kind))?,
+    );
+    if job.outcome.is_err() {
+        return Err(format!("after_job moved the output of the failed job {}", job.url).into());
+    }
+    if let Some(parent) = path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+    {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    tokio::fs::rename(&job.path, &path).await?;
+    info!("The script moved {:?} to {:?}", job.path, path);
+    job.path = path;
+    Ok(())
+}
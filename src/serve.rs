@@ -0,0 +1,1308 @@
+use std::{
+    collections::HashMap,
+    net::SocketAddr,
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use axum::{
+    body::Bytes,
+    extract::{Path, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use clap::Args;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use tokio::{
+    sync::{OwnedRwLockReadGuard, RwLock, Semaphore},
+    task::JoinSet,
+};
+use tracing::{debug, error, info, warn};
+
+use web2pdf_lib::{private_network, Browser};
+
+#[cfg(feature = "queue")]
+use crate::queue::Queue;
+use crate::{
+    api_keys::{ApiKey, ApiKeys, KeyJob},
+    manifest::{job_cli, parse_option},
+    metrics::Metrics,
+    pdf_tab,
+    scheduler::{Scheduler, StageLimits},
+    Cli, OutputFormat, Result, URLPathPair,
+};
+
+#[derive(Args, Debug, Clone)]
+pub struct ServeArgs {
+    #[clap(
+        long,
+        value_name = "ADDR",
+        default_value = "127.0.0.1:8080",
+        help = "Address to listen on, e.g. 0.0.0.0:8080 for all interfaces"
+    )]
+    pub listen: SocketAddr,
+
+    #[clap(
+        long,
+        help = "Allow converting file:// URLs, which exposes the files of the server to its clients",
+        default_value_t = false
+    )]
+    pub allow_file_urls: bool,
+
+    #[cfg(feature = "queue")]
+    #[clap(
+        long,
+        value_name = "FILE",
+        help = "Persist the async jobs in a SQLite database, so they survive restarts",
+        long_help = "Persist the async jobs in a SQLite database (created if missing), so they survive restarts.\nJobs that were queued or running when the server stopped are run again on start.\nOutputs are kept in the database until the job is deleted with DELETE /jobs/{id},\nwithout --queue they are kept in memory until they are fetched."
+    )]
+    pub queue: Option<PathBuf>,
+
+    #[clap(
+        long,
+        value_name = "FILE",
+        help = "Require an API key from a JSON file for every request",
+        long_help = "Require an API key for every request, sent as `Authorization: Bearer KEY` or `X-Api-Key: KEY`.\nThe JSON file is an array like [{\"name\": \"ci\", \"key\": \"...\", \"requests_per_minute\": 60, \"max_jobs\": 4}],\nkeys have at least 16 characters, the limits are optional (see --rate-limit and --max-jobs-per-key).\nJobs can only be seen and cancelled with the key that started them.\nOnly keys with \"admin\": true may use POST /admin/reload."
+    )]
+    pub api_keys: Option<PathBuf>,
+
+    #[clap(
+        long,
+        value_name = "N",
+        requires = "api_keys",
+        help = "Limit every API key to N conversions per minute, unless the key has its own limit"
+    )]
+    pub rate_limit: Option<u32>,
+
+    #[clap(
+        long,
+        value_name = "N",
+        requires = "api_keys",
+        help = "Limit every API key to N queued or running conversions, unless the key has its own limit"
+    )]
+    pub max_jobs_per_key: Option<usize>,
+
+    #[clap(
+        long,
+        value_name = "N",
+        help = "Reserve N of the --max-parallel tabs for interactive conversions",
+        long_help = "Reserve N of the --max-parallel tabs for interactive conversions, so bulk conversions (e.g. a queued crawl)\ncan't starve them. Requests are interactive unless they are async, \"priority\": \"bulk\" or \"interactive\"\nin the body overrides that. Bulk conversions only use the other tabs."
+    )]
+    pub reserve_interactive: Option<usize>,
+
+    #[clap(
+        long,
+        value_name = "FILE",
+        help = "Read the defaults of the conversions from a JSON file, read again on POST /admin/reload and SIGHUP",
+        long_help = "Read the defaults of the conversions from a JSON file, an object of options named like the long options,\ne.g. {\"paper\": \"a4\", \"dismiss-cookie-banners\": true}. Only the options a request may set are allowed,\nthe options of a request override them.\nPOST /admin/reload and SIGHUP read the file again and replace the browser (unless --user-data-dir,\n--client-cert or --interactive-login is used), running conversions finish with the previous browser."
+    )]
+    pub config: Option<PathBuf>,
+
+    #[clap(
+        long,
+        value_name = "N",
+        help = "Report the server as not ready on GET /readyz while N or more conversions wait for a tab",
+        long_help = "Report the server as not ready on GET /readyz while N or more conversions wait for a tab,\nso a load balancer sends new requests to other instances. Without it the server is always ready."
+    )]
+    pub ready_max_queued: Option<u64>,
+
+    #[clap(
+        long = "allow-origin",
+        value_name = "ORIGIN",
+        value_parser = parse_origin,
+        help = "Only convert URLs of ORIGIN, e.g. https://example.com, can be repeated",
+        long_help = "Only convert URLs of ORIGIN, e.g. https://example.com or http://intranet:8080, can be repeated.\nOther URLs are refused with 403. Only the converted URL is checked, not the resources or redirects of the page\n(see --deny-private-networks)."
+    )]
+    pub allowed_origins: Vec<String>,
+
+    #[clap(
+        long,
+        value_name = "SIZE",
+        value_parser = crate::parse_size,
+        help = "Fail conversions whose output is larger than SIZE, e.g. 50MB",
+        long_help = "Fail conversions whose output is larger than SIZE, e.g. 50MB or 20M\n(K, M, G, T are powers of 1024, KB, MB, GB, TB powers of 1000), instead of sending it."
+    )]
+    pub max_output_size: Option<u64>,
+
+    #[clap(
+        long,
+        value_name = "DURATION",
+        value_parser = crate::parse_duration,
+        help = "Cancel conversions that take longer than DURATION, e.g. 60s",
+        long_help = "Cancel conversions that take longer than DURATION, e.g. 60s or 2m, counted once the conversion has a tab.\nThey respond like a passed deadline of the request, with 504 and \"code\": \"timeout\"."
+    )]
+    pub max_render_time: Option<Duration>,
+}
+
+/// The options a request may set, only options of the rendering and the output
+/// Options naming files or hooks on the server, interactive options and options of the browser
+/// (e.g. --watermark-pdf, --on-success, --pause-before-print, --proxy) stay with the command line.
+const REQUEST_OPTIONS: &[&str] = &[
+    "mono",
+    "mono-min-height",
+    "mono-snap-a4",
+    "mono-passes",
+    "mono-trim",
+    "slides",
+    "receipt",
+    "receipt-width",
+    "format",
+    "attach-source",
+    "quality",
+    "screen",
+    "color-scheme",
+    "landscape",
+    "disable-backgrounds",
+    "paper-width",
+    "paper-height",
+    "paper-px",
+    "paper",
+    "full-bleed",
+    "margin-top",
+    "margin-bottom",
+    "margin-left",
+    "margin-right",
+    "page-ranges",
+    "display-header-footer",
+    "header-template",
+    "footer-template",
+    "disable-prefer-css-page-size",
+    "generate-tagged-pdf",
+    "disable-lang-tagging",
+    "disable-outline",
+    "pdf-title",
+    "pdf-author",
+    "pdf-subject",
+    "pdf-keywords",
+    "pdfa",
+    "watermark",
+    "watermark-position",
+    "watermark-rotation",
+    "watermark-opacity",
+    "watermark-font-size",
+    "dismiss-cookie-banners",
+    "remove-selector",
+    "rtl-check",
+    "force-dir",
+    "scale",
+    "user-agent",
+    "lang-profile",
+    "language",
+    "block-url",
+    "block-resources",
+    "no-site-fixes",
+    "code-view",
+    "auto-landscape",
+    "pretty-data",
+    "code-theme",
+];
+
+/// The scheduling lane of a conversion, see --reserve-interactive
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+enum Priority {
+    /// Someone waits for the output, may use the reserved tabs
+    Interactive,
+    /// Only uses the tabs that are not reserved
+    Bulk,
+}
+
+/// Body of `POST /convert`
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ConvertRequest {
+    url: String,
+    /// Overrides of the command line options, named like the long options, see `REQUEST_OPTIONS`
+    #[serde(default)]
+    options: Map<String, Value>,
+    /// Return a job id right away instead of waiting for the output
+    #[serde(default, rename = "async")]
+    run_async: bool,
+    /// The lane of the conversion, by default bulk for async requests and interactive otherwise
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    priority: Option<Priority>,
+    /// How long the caller waits from now on, e.g. "30s", see `resolve_deadline`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    timeout: Option<String>,
+    /// When the caller stops waiting, in seconds since the Unix epoch
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    deadline: Option<f64>,
+}
+impl ConvertRequest {
+    /// Turns the timeout into a deadline, the earlier one counts if both are given
+    /// Persisted jobs keep the deadline, so they don't wait longer after a restart.
+    /// Deadlines before the epoch or beyond the range of a `Duration` are refused.
+    fn resolve_deadline(&mut self) -> std::result::Result<(), String> {
+        if let Some(timeout) = self.timeout.take() {
+            let timeout =
+                crate::parse_duration(&timeout).map_err(|e| format!("invalid timeout: {}", e))?;
+            let deadline = unix_time() + timeout.as_secs_f64();
+            self.deadline = Some(self.deadline.map_or(deadline, |other| other.min(deadline)));
+        }
+        if let Some(deadline) = self.deadline {
+            if Duration::try_from_secs_f64(deadline).is_err() {
+                return Err(format!(
+                    "invalid deadline {}, expected seconds since the Unix epoch",
+                    deadline
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    fn priority(&self) -> Priority {
+        self.priority.unwrap_or(if self.run_async {
+            Priority::Bulk
+        } else {
+            Priority::Interactive
+        })
+    }
+}
+
+/// The status of a conversion started with `"async": true`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JobStatus {
+    /// Waiting for a slot of --max-parallel
+    Queued,
+    Running,
+    Done,
+    Failed(String),
+    /// The deadline of the request passed before the output was created
+    TimedOut(String),
+    Cancelled,
+}
+impl JobStatus {
+    fn to_json(&self, id: u64) -> Value {
+        let (status, error) = match self {
+            JobStatus::Queued => ("queued", None),
+            JobStatus::Running => ("running", None),
+            JobStatus::Done => ("done", None),
+            JobStatus::Failed(error) => ("failed", Some(error)),
+            JobStatus::TimedOut(error) => ("timed_out", Some(error)),
+            JobStatus::Cancelled => ("cancelled", None),
+        };
+        let mut json = json!({ "id": id, "status": status });
+        if let Some(error) = error {
+            json["error"] = error.as_str().into();
+        }
+        json
+    }
+}
+
+/// Why a conversion created no output
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JobError {
+    Failed(String),
+    /// The deadline of the request passed, see `ConvertRequest::resolve_deadline`
+    TimedOut(String),
+}
+impl From<JobError> for JobStatus {
+    fn from(error: JobError) -> JobStatus {
+        match error {
+            JobError::Failed(error) => JobStatus::Failed(error),
+            JobError::TimedOut(error) => JobStatus::TimedOut(error),
+        }
+    }
+}
+
+/// An async job kept in memory
+struct MemoryJob {
+    /// The name of the API key that started the job
+    owner: Option<String>,
+    status: JobStatus,
+    output: Option<(Bytes, OutputFormat)>,
+}
+
+/// The async jobs, in memory or persisted with --queue
+enum JobStore {
+    Memory {
+        jobs: Mutex<HashMap<u64, MemoryJob>>,
+        next_id: AtomicU64,
+    },
+    #[cfg(feature = "queue")]
+    Queue(Queue),
+}
+
+impl JobStore {
+    fn memory() -> JobStore {
+        JobStore::Memory {
+            jobs: Mutex::new(HashMap::new()),
+            next_id: AtomicU64::new(1),
+        }
+    }
+
+    /// Adds a queued job, the request is persisted to run it again after a restart
+    ///
+    /// # Arguments
+    /// * `request` - The request of the job
+    /// * `owner` - The name of the API key of the request, only it can see the job
+    #[cfg_attr(not(feature = "queue"), allow(unused_variables))]
+    fn insert(
+        &self,
+        request: &ConvertRequest,
+        owner: Option<&str>,
+    ) -> std::result::Result<u64, String> {
+        match self {
+            JobStore::Memory { jobs, next_id } => {
+                let id = next_id.fetch_add(1, Ordering::Relaxed);
+                let job = MemoryJob {
+                    owner: owner.map(str::to_string),
+                    status: JobStatus::Queued,
+                    output: None,
+                };
+                jobs.lock().unwrap().insert(id, job);
+                Ok(id)
+            }
+            #[cfg(feature = "queue")]
+            JobStore::Queue(queue) => serde_json::to_string(request)
+                .map_err(|e| e.to_string())
+                .and_then(|request| queue.insert(&request, owner).map_err(|e| e.to_string())),
+        }
+    }
+
+    /// Marks a job as running, false if it was cancelled before it started
+    fn start(&self, id: u64) -> std::result::Result<bool, String> {
+        match self {
+            JobStore::Memory { jobs, .. } => match jobs.lock().unwrap().get_mut(&id) {
+                Some(job) if job.status == JobStatus::Queued => {
+                    job.status = JobStatus::Running;
+                    Ok(true)
+                }
+                _ => Ok(false),
+            },
+            #[cfg(feature = "queue")]
+            JobStore::Queue(queue) => queue.start(id).map_err(|e| e.to_string()),
+        }
+    }
+
+    /// Records the outcome of a job, unless it was cancelled meanwhile
+    fn finish(
+        &self,
+        id: u64,
+        outcome: std::result::Result<(Bytes, OutputFormat), JobError>,
+    ) -> std::result::Result<(), String> {
+        match self {
+            JobStore::Memory { jobs, .. } => {
+                if let Some(job) = jobs.lock().unwrap().get_mut(&id) {
+                    if matches!(job.status, JobStatus::Queued | JobStatus::Running) {
+                        match outcome {
+                            Ok(output) => {
+                                job.status = JobStatus::Done;
+                                job.output = Some(output);
+                            }
+                            Err(e) => job.status = e.into(),
+                        }
+                    }
+                }
+                Ok(())
+            }
+            #[cfg(feature = "queue")]
+            JobStore::Queue(queue) => {
+                let outcome = outcome
+                    .as_ref()
+                    .map(|(output, format)| (output.as_ref(), *format))
+                    .map_err(Clone::clone);
+                queue.finish(id, &outcome).map_err(|e| e.to_string())
+            }
+        }
+    }
+
+    /// The status of a job, None if there is no such job of the owner
+    fn status(
+        &self,
+        id: u64,
+        owner: Option<&str>,
+    ) -> std::result::Result<Option<JobStatus>, String> {
+        match self {
+            JobStore::Memory { jobs, .. } => Ok(jobs
+                .lock()
+                .unwrap()
+                .get(&id)
+                .filter(|job| job.owner.as_deref() == owner)
+                .map(|job| job.status.clone())),
+            #[cfg(feature = "queue")]
+            JobStore::Queue(queue) => queue.status(id, owner).map_err(|e| e.to_string()),
+        }
+    }
+
+    /// The output of a done job of the owner, jobs in memory are removed with it
+    fn output(
+        &self,
+        id: u64,
+        owner: Option<&str>,
+    ) -> std::result::Result<Option<(Bytes, OutputFormat)>, String> {
+        match self {
+            JobStore::Memory { jobs, .. } => {
+                let mut jobs = jobs.lock().unwrap();
+                match jobs.get(&id) {
+                    Some(job) if job.owner.as_deref() == owner && job.status == JobStatus::Done => {
+                        Ok(jobs.remove(&id).and_then(|job| job.output))
+                    }
+                    _ => Ok(None),
+                }
+            }
+            #[cfg(feature = "queue")]
+            JobStore::Queue(queue) => queue
+                .output(id, owner)
+                .map(|output| output.map(|(output, format)| (Bytes::from(output), format)))
+                .map_err(|e| e.to_string()),
+        }
+    }
+
+    /// Cancels a queued or running job, or deletes a finished job
+    ///
+    /// # Returns
+    /// The status before, None if there is no such job of the owner
+    fn cancel(
+        &self,
+        id: u64,
+        owner: Option<&str>,
+    ) -> std::result::Result<Option<JobStatus>, String> {
+        match self {
+            JobStore::Memory { jobs, .. } => {
+                let mut jobs = jobs.lock().unwrap();
+                let Some(job) = jobs
+                    .get_mut(&id)
+                    .filter(|job| job.owner.as_deref() == owner)
+                else {
+                    return Ok(None);
+                };
+                let status = job.status.clone();
+                if matches!(status, JobStatus::Queued | JobStatus::Running) {
+                    job.status = JobStatus::Cancelled;
+                } else {
+                    jobs.remove(&id);
+                }
+                Ok(Some(status))
+            }
+            #[cfg(feature = "queue")]
+            JobStore::Queue(queue) => queue.cancel(id, owner).map_err(|e| e.to_string()),
+        }
+    }
+}
+
+/// How long `GET /healthz` waits for the browser to respond
+const HEALTH_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The defaults and the browser of the conversions, replaced on a reload
+struct Generation {
+    /// The options of --config, the options of a request override them
+    defaults: Map<String, Value>,
+    /// Read by the running conversions, written to close it once they are done
+    browser: Arc<RwLock<Browser>>,
+}
+
+struct Server {
+    /// The options of the command line, the defaults of every conversion
+    cli: Arc<Cli>,
+    /// The file of --config
+    config: Option<PathBuf>,
+    generation: Mutex<Arc<Generation>>,
+    /// Runs one reload at a time
+    reloading: tokio::sync::Mutex<()>,
+    /// See --ready-max-queued
+    ready_max_queued: Option<u64>,
+    /// The origins of --allow-origin, any origin if empty
+    allowed_origins: Vec<String>,
+    max_output_size: Option<u64>,
+    max_render_time: Option<Duration>,
+    scheduler: Arc<Scheduler>,
+    stages: Arc<StageLimits>,
+    /// Slots of the bulk conversions, the other tabs are reserved, see --reserve-interactive
+    bulk_slots: Option<Semaphore>,
+    allow_file_urls: bool,
+    api_keys: Option<ApiKeys>,
+    jobs: JobStore,
+    metrics: Metrics,
+    /// The conversions of async jobs, awaited before shutting down
+    tasks: Mutex<JoinSet<()>>,
+    /// Numbers the temporary outputs
+    next_output: AtomicU64,
+}
+
+/// Serves conversions over HTTP until Ctrl-C is pressed
+/// `POST /convert` converts the URL of a JSON body like `{"url": "https://example.com",
+/// "options": {"landscape": true}}` and responds with the output. With `"async": true` it
+/// responds with a job id instead, whose status is at `GET /jobs/{id}` and output at
+/// `GET /jobs/{id}/result`. `DELETE /jobs/{id}` cancels a job or deletes its output.
+/// `GET /metrics` exposes metrics of the conversions for Prometheus.
+/// `GET /healthz` checks that the browser responds and `GET /readyz` that the queue is below
+/// --ready-max-queued, both without an API key, for the probes of e.g. Kubernetes.
+/// Async requests are bulk conversions, which can't use the tabs of --reserve-interactive.
+/// A `"timeout"` like `"30s"` or a `"deadline"` in seconds since the Unix epoch in the body
+/// limits how long the conversion may take, including the wait for a tab, a conversion that
+/// doesn't finish in time is cancelled and responds with 504 and `"code": "timeout"`.
+/// --allow-origin, --deny-private-networks, --max-output-size and --max-render-time guard a
+/// server that is open to the public.
+/// `POST /admin/reload` and SIGHUP read --config again and replace the browser, queued jobs
+/// wait for the new browser and running ones finish with the previous one.
+///
+/// # Arguments
+/// * `cli` - The cli, its options are the defaults of every conversion
+/// * `browser` - The browser, with cookies and login already applied, closed before returning
+/// * `scheduler` - Limits the conversions running at the same time, see --max-parallel
+/// * `args` - The arguments of the subcommand
+pub async fn serve(
+    cli: Arc<Cli>,
+    browser: Browser,
+    scheduler: Arc<Scheduler>,
+    args: &ServeArgs,
+) -> Result<()> {
+    let defaults = match &args.config {
+        Some(path) => load_config(path)?,
+        None => Map::new(),
+    };
+    let stages = Arc::new(
+        StageLimits::new(
+            cli.max_navigating.map(|max| max as usize),
+            cli.max_printing.map(|max| max as usize),
+        )
+        .with_bandwidth(cli.max_bandwidth, scheduler.max_running().unwrap_or(1)),
+    );
+    #[cfg(feature = "queue")]
+    let jobs = match &args.queue {
+        Some(path) => JobStore::Queue(Queue::open(path)?),
+        None => JobStore::memory(),
+    };
+    #[cfg(not(feature = "queue"))]
+    let jobs = JobStore::memory();
+    let api_keys = match &args.api_keys {
+        Some(path) => {
+            let keys = ApiKeys::load(path, args.rate_limit, args.max_jobs_per_key)
+                .map_err(|e| format!("failed to load the API keys {:?}: {}", path, e))?;
+            info!("Loaded {} API keys", keys.len());
+            Some(keys)
+        }
+        None if !args.listen.ip().is_loopback() => {
+            warn!(
+                "Listening on {} without --api-keys, anyone who can reach it can convert pages",
+                args.listen
+            );
+            None
+        }
+        None => None,
+    };
+    if !args.listen.ip().is_loopback() && !cli.deny_private_networks {
+        warn!(
+            "Listening on {} without --deny-private-networks, clients can convert pages of the networks of the server",
+            args.listen
+        );
+    }
+    // Checked against --max-parallel when parsing the command line
+    let bulk_slots = args.reserve_interactive.map(|reserved| {
+        let limit = scheduler.max_running().unwrap_or(reserved + 1);
+        info!(
+            "Reserving {} of {} tabs for interactive conversions",
+            reserved, limit
+        );
+        Semaphore::new(limit - reserved)
+    });
+    let server = Arc::new(Server {
+        cli,
+        config: args.config.clone(),
+        generation: Mutex::new(Arc::new(Generation {
+            defaults,
+            browser: Arc::new(RwLock::new(browser)),
+        })),
+        reloading: tokio::sync::Mutex::new(()),
+        ready_max_queued: args.ready_max_queued,
+        allowed_origins: args.allowed_origins.clone(),
+        max_output_size: args.max_output_size,
+        max_render_time: args.max_render_time,
+        scheduler,
+        stages,
+        bulk_slots,
+        allow_file_urls: args.allow_file_urls,
+        api_keys,
+        jobs,
+        metrics: Metrics::new(),
+        tasks: Mutex::new(JoinSet::new()),
+        next_output: AtomicU64::new(1),
+    });
+    #[cfg(feature = "queue")]
+    if let JobStore::Queue(queue) = &server.jobs {
+        let queued = queue.queued()?;
+        if !queued.is_empty() {
+            info!("Resuming {} queued jobs", queued.len());
+        }
+        for (id, request) in queued {
+            let job = serde_json::from_str::<ConvertRequest>(&request)
+                .map_err(|e| e.to_string())
+                .and_then(|request| {
+                    Ok((server.job(&request)?, request.priority(), request.deadline))
+                });
+            match job {
+                // Resumed jobs don't count against the job limit of their key
+                Ok((job, priority, deadline)) => server.spawn(id, job, priority, deadline, None),
+                Err(e) => server.jobs.finish(id, Err(JobError::Failed(e)))?,
+            }
+        }
+    }
+    let app = Router::new()
+        .route("/convert", post(convert))
+        .route("/jobs/{id}", get(job_status).delete(cancel_job))
+        .route("/jobs/{id}/result", get(job_result))
+        .route("/metrics", get(metrics))
+        .route("/admin/reload", post(reload))
+        .route("/healthz", get(healthz))
+        .route("/readyz", get(readyz))
+        .with_state(Arc::clone(&server));
+
+    #[cfg(unix)]
+    let hangups = {
+        let mut hangup = tokio::signal::unix::signal(tokio::signal::unix::SignalKind::hangup())?;
+        let server = Arc::clone(&server);
+        tokio::spawn(async move {
+            while hangup.recv().await.is_some() {
+                info!("Reloading on SIGHUP");
+                if let Err(e) = server.reload().await {
+                    error!("Reload failed with reason: {}", e);
+                }
+            }
+        })
+    };
+
+    let listener = tokio::net::TcpListener::bind(args.listen).await?;
+    info!("Listening on http://{}", listener.local_addr()?);
+    axum::serve(listener, app)
+        .with_graceful_shutdown(async {
+            let _ = tokio::signal::ctrl_c().await;
+            info!("Shutting down");
+        })
+        .await?;
+    #[cfg(unix)]
+    hangups.abort();
+
+    // The browser can only be closed once no conversion uses it
+    let mut tasks = std::mem::take(&mut *server.tasks.lock().unwrap());
+    if !tasks.is_empty() {
+        info!("Waiting for {} running jobs", tasks.len());
+    }
+    while tasks.join_next().await.is_some() {}
+    let browser = Arc::clone(&server.generation.lock().unwrap().browser);
+    close_browser(browser, server.cli.connect.is_some()).await;
+    Ok(())
+}
+
+async fn convert(State(server): State<Arc<Server>>, headers: HeaderMap, body: Bytes) -> Response {
+    let key = match server.authenticate(&headers) {
+        Ok(key) => key,
+        Err(response) => return *response,
+    };
+    let mut quota = None;
+    if let Some(key) = &key {
+        if let Err(retry_after) = key.check_rate() {
+            return (
+                StatusCode::TOO_MANY_REQUESTS,
+                [(
+                    header::RETRY_AFTER,
+                    retry_after.as_secs().max(1).to_string(),
+                )],
+                Json(json!({ "error": "rate limit exceeded" })),
+            )
+                .into_response();
+        }
+        match key.start_job() {
+            Some(job) => quota = Some(job),
+            None => {
+                return error_response(
+                    StatusCode::TOO_MANY_REQUESTS,
+                    format!(
+                        "the key already has {} queued or running jobs",
+                        key.max_jobs().unwrap_or_default()
+                    ),
+                )
+            }
+        }
+    }
+    let mut request: ConvertRequest = match serde_json::from_slice(&body) {
+        Ok(request) => request,
+        Err(e) => return error_response(StatusCode::BAD_REQUEST, format!("invalid body: {}", e)),
+    };
+    if let Err(e) = request.resolve_deadline() {
+        return error_response(StatusCode::BAD_REQUEST, e);
+    }
+    let job = match server.job(&request) {
+        Ok(job) => job,
+        Err(e) => return error_response(StatusCode::BAD_REQUEST, e),
+    };
+    if let Err(e) = server.check_url(&request.url).await {
+        return error_response(StatusCode::FORBIDDEN, e);
+    }
+
+    if !request.run_async {
+        let converted = server
+            .run(job, None, request.priority(), request.deadline)
+            .await;
+        drop(quota);
+        return match converted {
+            Ok(Some((output, format))) => output_response(output, format),
+            Ok(None) => unreachable!("only async jobs can be cancelled"),
+            Err(JobError::Failed(e)) => error_response(StatusCode::UNPROCESSABLE_ENTITY, e),
+            Err(JobError::TimedOut(e)) => timeout_response(e),
+        };
+    }
+    let owner = key.as_ref().map(|key| key.name.as_str());
+    let id = match server.jobs.insert(&request, owner) {
+        Ok(id) => id,
+        Err(e) => return error_response(StatusCode::INTERNAL_SERVER_ERROR, e),
+    };
+    server.spawn(id, job, request.priority(), request.deadline, quota);
+    (
+        StatusCode::ACCEPTED,
+        [(header::LOCATION, format!("/jobs/{}", id))],
+        Json(JobStatus::Queued.to_json(id)),
+    )
+        .into_response()
+}
+
+async fn job_status(
+    State(server): State<Arc<Server>>,
+    Path(id): Path<u64>,
+    headers: HeaderMap,
+) -> Response {
+    let key = match server.authenticate(&headers) {
+        Ok(key) => key,
+        Err(response) => return *response,
+    };
+    match server
+        .jobs
+        .status(id, key.as_ref().map(|key| key.name.as_str()))
+    {
+        Ok(Some(status)) => Json(status.to_json(id)).into_response(),
+        Ok(None) => error_response(StatusCode::NOT_FOUND, format!("no job {}", id)),
+        Err(e) => error_response(StatusCode::INTERNAL_SERVER_ERROR, e),
+    }
+}
+
+async fn job_result(
+    State(server): State<Arc<Server>>,
+    Path(id): Path<u64>,
+    headers: HeaderMap,
+) -> Response {
+    let key = match server.authenticate(&headers) {
+        Ok(key) => key,
+        Err(response) => return *response,
+    };
+    let owner = key.as_ref().map(|key| key.name.as_str());
+    let status = match server.jobs.status(id, owner) {
+        Ok(Some(status)) => status,
+        Ok(None) => return error_response(StatusCode::NOT_FOUND, format!("no job {}", id)),
+        Err(e) => return error_response(StatusCode::INTERNAL_SERVER_ERROR, e),
+    };
+    match status {
+        JobStatus::Queued | JobStatus::Running => {
+            error_response(StatusCode::CONFLICT, format!("job {} is not done", id))
+        }
+        JobStatus::Failed(e) => error_response(StatusCode::UNPROCESSABLE_ENTITY, e),
+        JobStatus::TimedOut(e) => timeout_response(e),
+        JobStatus::Cancelled => {
+            error_response(StatusCode::GONE, format!("job {} was cancelled", id))
+        }
+        JobStatus::Done => match server.jobs.output(id, owner) {
+            Ok(Some((output, format))) => output_response(output, format),
+            // Fetched or deleted meanwhile
+            Ok(None) => error_response(StatusCode::NOT_FOUND, format!("no job {}", id)),
+            Err(e) => error_response(StatusCode::INTERNAL_SERVER_ERROR, e),
+        },
+    }
+}
+
+async fn cancel_job(
+    State(server): State<Arc<Server>>,
+    Path(id): Path<u64>,
+    headers: HeaderMap,
+) -> Response {
+    let key = match server.authenticate(&headers) {
+        Ok(key) => key,
+        Err(response) => return *response,
+    };
+    match server
+        .jobs
+        .cancel(id, key.as_ref().map(|key| key.name.as_str()))
+    {
+        // A running conversion is finished, but its output is discarded
+        Ok(Some(JobStatus::Queued | JobStatus::Running)) => {
+            Json(JobStatus::Cancelled.to_json(id)).into_response()
+        }
+        Ok(Some(_)) => Json(json!({ "id": id, "status": "deleted" })).into_response(),
+        Ok(None) => error_response(StatusCode::NOT_FOUND, format!("no job {}", id)),
+        Err(e) => error_response(StatusCode::INTERNAL_SERVER_ERROR, e),
+    }
+}
+
+/// Requires an API key like the other routes, see `authorization` in the scrape config of Prometheus
+async fn metrics(State(server): State<Arc<Server>>, headers: HeaderMap) -> Response {
+    if let Err(response) = server.authenticate(&headers) {
+        return *response;
+    }
+    (
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        server.metrics.render(),
+    )
+        .into_response()
+}
+
+/// Liveness, the browser is running and responds to the DevTools protocol
+async fn healthz(State(server): State<Arc<Server>>) -> Response {
+    let browser = server.browser();
+    match tokio::time::timeout(HEALTH_TIMEOUT, browser.version()).await {
+        Ok(Ok(version)) => {
+            Json(json!({ "status": "ok", "browser": version.product })).into_response()
+        }
+        Ok(Err(e)) => error_response(
+            StatusCode::SERVICE_UNAVAILABLE,
+            format!("the browser doesn't respond: {}", e),
+        ),
+        Err(_) => error_response(
+            StatusCode::SERVICE_UNAVAILABLE,
+            format!(
+                "the browser didn't respond within {} seconds",
+                HEALTH_TIMEOUT.as_secs()
+            ),
+        ),
+    }
+}
+
+/// Readiness, fewer conversions than --ready-max-queued wait for a tab
+async fn readyz(State(server): State<Arc<Server>>) -> Response {
+    let queued = server.metrics.queued();
+    match server.ready_max_queued {
+        Some(max) if queued >= max => error_response(
+            StatusCode::SERVICE_UNAVAILABLE,
+            format!("{} conversions wait for a tab", queued),
+        ),
+        _ => Json(json!({ "status": "ready", "queued": queued })).into_response(),
+    }
+}
+
+/// With --api-keys only keys with `"admin": true` may reload
+async fn reload(State(server): State<Arc<Server>>, headers: HeaderMap) -> Response {
+    match server.authenticate(&headers) {
+        Ok(Some(key)) if !key.admin => {
+            return error_response(
+                StatusCode::FORBIDDEN,
+                format!("the key {:?} is no admin key", key.name),
+            )
+        }
+        Ok(_) => {}
+        Err(response) => return *response,
+    }
+    match server.reload().await {
+        Ok(replaced) => {
+            Json(json!({ "status": "reloaded", "browser_replaced": replaced })).into_response()
+        }
+        Err(e) => error_response(StatusCode::INTERNAL_SERVER_ERROR, e),
+    }
+}
+
+impl Server {
+    /// The API key of a request, None without --api-keys
+    ///
+    /// # Returns
+    /// The key or a 401 response if the key is missing or unknown
+    fn authenticate(
+        &self,
+        headers: &HeaderMap,
+    ) -> std::result::Result<Option<Arc<ApiKey>>, Box<Response>> {
+        let Some(keys) = &self.api_keys else {
+            return Ok(None);
+        };
+        let header = |name| headers.get(name).and_then(|value| value.to_str().ok());
+        let key = header(header::AUTHORIZATION)
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .or_else(|| header(header::HeaderName::from_static("x-api-key")));
+        match key.and_then(|key| keys.find(key.trim())) {
+            Some(key) => Ok(Some(key)),
+            None => Err(Box::new(
+                (
+                    StatusCode::UNAUTHORIZED,
+                    [(header::WWW_AUTHENTICATE, "Bearer")],
+                    Json(json!({ "error": "missing or invalid API key" })),
+                )
+                    .into_response(),
+            )),
+        }
+    }
+
+    /// The cli of a conversion, with the options of the request and a temporary output
+    fn job(&self, request: &ConvertRequest) -> std::result::Result<Arc<Cli>, String> {
+        let scheme = request.url.split(':').next().unwrap_or_default();
+        match scheme.to_ascii_lowercase().as_str() {
+            "http" | "https" => {}
+            "file" if self.allow_file_urls => {}
+            _ => return Err(format!("unsupported URL {:?}", request.url)),
+        }
+        let mut options = self.generation.lock().unwrap().defaults.clone();
+        options.extend(request_options(&request.options)?);
+
+        let mut cli = Cli::clone(&self.cli);
+        cli.url_path_pairs = vec![URLPathPair {
+            url: request.url.clone(),
+            path: std::env::temp_dir().join(format!(
+                "web2pdf-serve-{}-{}.{}",
+                std::process::id(),
+                self.next_output.fetch_add(1, Ordering::Relaxed),
+                self.cli.format.extension()
+            )),
+            viewport_width: None,
+            options,
+        }];
+        job_cli(&Arc::new(cli), 0).map_err(|e| e.to_string())
+    }
+
+    /// The browser of the current generation, it stays open until the guard is dropped
+    fn browser(&self) -> OwnedRwLockReadGuard<Browser> {
+        let generation = self.generation.lock().unwrap();
+        // Only the browsers of replaced generations are written, after they were replaced
+        Arc::clone(&generation.browser)
+            .try_read_owned()
+            .expect("the browser of the current generation is open")
+    }
+
+    /// Reads --config again and replaces the browser, conversions that already run finish with
+    /// the previous browser, which is closed once they are done
+    ///
+    /// # Returns
+    /// Whether the browser was replaced, a browser whose profile can't be shared is kept
+    async fn reload(&self) -> std::result::Result<bool, String> {
+        let _reloading = self.reloading.lock().await;
+        let defaults = match &self.config {
+            Some(path) => load_config(path)?,
+            None => Map::new(),
+        };
+        // A second browser can't open the profile while the previous one still converts
+        if self.cli.user_data_dir.is_some()
+            || self.cli.client_cert.is_some()
+            || self.cli.interactive_login.is_some()
+        {
+            warn!("Keeping the browser, it is not replaced with --user-data-dir, --client-cert or --interactive-login");
+            let mut generation = self.generation.lock().unwrap();
+            let browser = Arc::clone(&generation.browser);
+            *generation = Arc::new(Generation { defaults, browser });
+            info!("Reloaded the defaults");
+            return Ok(false);
+        }
+
+        let browser = crate::open_browser(&self.cli)
+            .await
+            .map_err(|e| e.to_string())?;
+        let browser = Arc::new(RwLock::new(browser));
+        let prepared = crate::prepare_browser(&*browser.read().await, &self.cli)
+            .await
+            .map_err(|e| e.to_string());
+        if let Err(e) = prepared {
+            close_browser(browser, self.cli.connect.is_some()).await;
+            return Err(e);
+        }
+        let previous = std::mem::replace(
+            &mut *self.generation.lock().unwrap(),
+            Arc::new(Generation { defaults, browser }),
+        );
+        info!("Reloaded, running conversions finish with the previous browser");
+        let connected = self.cli.connect.is_some();
+        let mut tasks = self.tasks.lock().unwrap();
+        while tasks.try_join_next().is_some() {}
+        tasks.spawn(close_browser(Arc::clone(&previous.browser), connected));
+        Ok(true)
+    }
+
+    /// Refuses URLs outside of --allow-origin and, with --deny-private-networks, URLs into
+    /// private networks. Redirects and the resources of the page are blocked while loading it.
+    async fn check_url(&self, url: &str) -> std::result::Result<(), String> {
+        if !self.allowed_origins.is_empty() {
+            let origin = url::Url::parse(url)
+                .map(|url| url.origin().ascii_serialization())
+                .unwrap_or_default();
+            if !self.allowed_origins.contains(&origin) {
+                return Err(format!("{} is not of an origin of --allow-origin", url));
+            }
+        }
+        if self.cli.deny_private_networks {
+            // Hosts that don't resolve are refused, like their requests are blocked while loading
+            match private_network::is_private_url(url).await {
+                Ok(false) => {}
+                Ok(true) => return Err(format!("{} is in a private network", url)),
+                Err(e) => return Err(format!("failed to resolve the host of {}: {}", url, e)),
+            }
+        }
+        Ok(())
+    }
+
+    /// Reads the output of a conversion, unless it exceeds --max-output-size
+    async fn read_output(&self, path: &std::path::Path) -> std::result::Result<Vec<u8>, JobError> {
+        let failed = |e: std::io::Error| JobError::Failed(e.to_string());
+        if let Some(max) = self.max_output_size {
+            let size = tokio::fs::metadata(path).await.map_err(failed)?.len();
+            if size > max {
+                return Err(JobError::Failed(format!(
+                    "the output of {} bytes exceeds the --max-output-size of {} bytes",
+                    size, max
+                )));
+            }
+        }
+        tokio::fs::read(path).await.map_err(failed)
+    }
+
+    /// Runs an async job in the background
+    ///
+    /// # Arguments
+    /// * `id` - The id of the job
+    /// * `cli` - The cli of the job
+    /// * `priority` - The lane of the job
+    /// * `deadline` - When the job times out, in seconds since the Unix epoch
+    /// * `quota` - Counts the job against the job limit of its API key until it is done
+    fn spawn(
+        self: &Arc<Self>,
+        id: u64,
+        cli: Arc<Cli>,
+        priority: Priority,
+        deadline: Option<f64>,
+        quota: Option<KeyJob>,
+    ) {
+        let mut tasks = self.tasks.lock().unwrap();
+        while tasks.try_join_next().is_some() {}
+        tasks.spawn(Arc::clone(self).run_async(id, cli, priority, deadline, quota));
+    }
+
+    /// Converts the URL of an async job and records its output
+    async fn run_async(
+        self: Arc<Self>,
+        id: u64,
+        cli: Arc<Cli>,
+        priority: Priority,
+        deadline: Option<f64>,
+        _quota: Option<KeyJob>,
+    ) {
+        let outcome = match self.run(cli, Some(id), priority, deadline).await {
+            // Cancelled before it started
+            Ok(None) => return,
+            Ok(Some(output)) => Ok(output),
+            Err(e) => Err(e),
+        };
+        if let Err(e) = self.jobs.finish(id, outcome) {
+            error!(
+                "Failed to record the outcome of job {} with reason: {}",
+                id, e
+            );
+        }
+    }
+
+    /// Converts the URL of a job and returns its output
+    ///
+    /// # Arguments
+    /// * `cli` - The cli of the job
+    /// * `id` - The id of an async job, which is skipped if it was cancelled while queued
+    /// * `priority` - The lane of the job
+    /// * `deadline` - When the job times out, in seconds since the Unix epoch, it is cancelled
+    ///   wherever it is, e.g. while queued, navigating, waiting for the page or printing
+    async fn run(
+        &self,
+        cli: Arc<Cli>,
+        id: Option<u64>,
+        priority: Priority,
+        deadline: Option<f64>,
+    ) -> std::result::Result<Option<(Bytes, OutputFormat)>, JobError> {
+        let pair = &cli.url_path_pairs[0];
+        // A passed deadline times out right away, one too far ahead for the clock counts as none
+        let deadline = deadline
+            .filter(|deadline| deadline.is_finite())
+            .and_then(|deadline| {
+                let left = Duration::try_from_secs_f64((deadline - unix_time()).max(0.0)).ok()?;
+                tokio::time::Instant::now().checked_add(left)
+            });
+        let timed_out = |error: String| {
+            warn!("{}", error);
+            JobError::TimedOut(error)
+        };
+
+        let queued = self.metrics.enqueue();
+        let acquired = within(deadline, async {
+            // Bulk jobs wait for a slot of their lane first, so they never take a reserved tab
+            let lane = match (&self.bulk_slots, priority) {
+                // The semaphore is never closed
+                (Some(slots), Priority::Bulk) => slots.acquire().await.ok(),
+                _ => None,
+            };
+            (lane, self.scheduler.acquire().await)
+        })
+        .await;
+        drop(queued);
+        let Some((_lane, mut permit)) = acquired else {
+            if let Some(id) = id {
+                // Skipped like a cancelled job if it was cancelled meanwhile
+                if !self.jobs.start(id).map_err(JobError::Failed)? {
+                    return Ok(None);
+                }
+            }
+            return Err(timed_out(format!(
+                "the deadline passed while {} waited for a tab",
+                pair.url
+            )));
+        };
+        if let Some(id) = id {
+            if !self.jobs.start(id).map_err(JobError::Failed)? {
+                return Ok(None);
+            }
+        }
+        // --max-render-time starts with the conversion, the deadline of the request already runs
+        // A limit too far ahead for the clock counts as none
+        let render_limit = self
+            .max_render_time
+            .and_then(|max| tokio::time::Instant::now().checked_add(max));
+        let limit = deadline.into_iter().chain(render_limit).min();
+        let browser = self.browser();
+        let tab = self.metrics.open_tab();
+        let started = Instant::now();
+        let converted = within(limit, pdf_tab(&cli, &browser, &self.stages, None, 0))
+            .await
+            .map(|converted| converted.map_err(|e| JobError::Failed(e.to_string())));
+        drop(browser);
+        let output = match converted {
+            Some(Ok(_)) => {
+                permit.succeeded();
+                self.read_output(&pair.path).await
+            }
+            Some(Err(e)) => Err(e),
+            None if limit != deadline => Err(timed_out(format!(
+                "converting {} took longer than the --max-render-time of {}s",
+                pair.url,
+                self.max_render_time.unwrap_or_default().as_secs_f64()
+            ))),
+            None => Err(timed_out(format!(
+                "the deadline passed while converting {}",
+                pair.url
+            ))),
+        };
+        self.metrics.record(started.elapsed(), output.is_ok());
+        drop(tab);
+        let _ = tokio::fs::remove_file(&pair.path).await;
+        match output {
+            Ok(output) => {
+                info!("Created {} from {}", cli.format.extension(), pair.url);
+                Ok(Some((Bytes::from(output), cli.format)))
+            }
+            Err(JobError::Failed(e)) => {
+                error!(
+                    "Error creating pdf from \"{}\" with reason: {}",
+                    pair.url, e
+                );
+                Err(JobError::Failed(e))
+            }
+            Err(e) => Err(e),
+        }
+    }
+}
+
+/// Parses an origin of --allow-origin, e.g. `https://example.com`
+fn parse_origin(origin: &str) -> std::result::Result<String, String> {
+    let url = url::Url::parse(origin).map_err(|e| format!("invalid origin '{}': {}", origin, e))?;
+    match url.origin() {
+        url::Origin::Opaque(_) => Err(format!(
+            "'{}' has no origin, expected e.g. https://example.com",
+            origin
+        )),
+        tuple => Ok(tuple.ascii_serialization()),
+    }
+}
+
+/// Parses the options of a request or of --config, only `REQUEST_OPTIONS` are allowed
+fn request_options(
+    options: &Map<String, Value>,
+) -> std::result::Result<Map<String, Value>, String> {
+    let mut parsed = Map::new();
+    for (name, value) in options {
+        // Checked before parsing, as parsing some options already reads files
+        let long = name.trim().trim_start_matches("--").replace('_', "-");
+        if !REQUEST_OPTIONS.contains(&long.as_str()) {
+            return Err(format!("option {:?} can't be set per request", name));
+        }
+        let (field, value) =
+            parse_option(name, value).map_err(|e| format!("option {:?}: {}", name, e))?;
+        parsed.insert(field, value);
+    }
+    Ok(parsed)
+}
+
+/// Reads the defaults of the conversions of --config
+fn load_config(path: &std::path::Path) -> std::result::Result<Map<String, Value>, String> {
+    let config = std::fs::read(path).map_err(|e| format!("failed to read {:?}: {}", path, e))?;
+    serde_json::from_slice(&config)
+        .map_err(|e| e.to_string())
+        .and_then(|options| request_options(&options))
+        .map_err(|e| format!("invalid config {:?}: {}", path, e))
+}
+
+/// Closes a browser once no conversion uses it, a browser of --connect is left running
+///
+/// # Arguments
+/// * `browser` - The browser of a generation
+/// * `connected` - Whether the browser was attached to with --connect
+async fn close_browser(browser: Arc<RwLock<Browser>>, connected: bool) {
+    let mut browser = browser.write().await;
+    if connected {
+        // Disconnected once the last reference is dropped
+        return;
+    }
+    let closed = match browser.close().await {
+        Ok(_) => browser.wait().await.map(|_| ()).map_err(|e| e.to_string()),
+        Err(e) => Err(e.to_string()),
+    };
+    match closed {
+        Ok(()) => debug!("Closed browser"),
+        Err(e) => warn!("Failed to close the browser with reason: {}", e),
+    }
+}
+
+fn output_response(output: Bytes, format: OutputFormat) -> Response {
+    let content_type = match format {
+        OutputFormat::Pdf => "application/pdf",
+        OutputFormat::Png => "image/png",
+        OutputFormat::Jpeg => "image/jpeg",
+        OutputFormat::Mhtml => "multipart/related",
+        OutputFormat::Html => "text/html; charset=utf-8",
+    };
+    ([(header::CONTENT_TYPE, content_type)], output).into_response()
+}
+
+fn error_response(status: StatusCode, error: String) -> Response {
+    (status, Json(json!({ "error": error }))).into_response()
+}
+
+/// A 504 response, `"code": "timeout"` tells it apart from timeouts of proxies in between
+fn timeout_response(error: String) -> Response {
+    (
+        StatusCode::GATEWAY_TIMEOUT,
+        Json(json!({ "error": error, "code": "timeout" })),
+    )
+        .into_response()
+}
+
+/// Runs a future until the deadline
+///
+/// # Returns
+/// The output of the future, None if the deadline passed first
+async fn within<T>(
+    deadline: Option<tokio::time::Instant>,
+    future: impl std::future::Future<Output = T>,
+) -> Option<T> {
+    match deadline {
+        Some(deadline) => tokio::time::timeout_at(deadline, future).await.ok(),
+        None => Some(future.await),
+    }
+}
+
+/// The current time in seconds since the Unix epoch
+fn unix_time() -> f64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs_f64()
+}
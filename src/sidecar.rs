@@ -0,0 +1,64 @@
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+use tokio::fs;
+use web2pdf_lib::metadata::Metadata;
+
+use crate::{output_path::long_path, Result};
+
+/// JSON metadata written next to an output with --save-metadata, e.g. for archive search indexes
+#[derive(Debug, Serialize)]
+struct Sidecar<'a> {
+    /// The converted URL, None for merged PDFs
+    #[serde(skip_serializing_if = "Option::is_none")]
+    url: Option<&'a str>,
+    title: Option<&'a str>,
+    author: Option<&'a str>,
+    subject: Option<&'a str>,
+    keywords: Option<&'a str>,
+    /// The BCP 47 tag of the detected document language, e.g. "en-US"
+    language: Option<&'a str>,
+    pages: usize,
+}
+
+/// The path of the metadata sidecar of an output (`<output>.json`)
+///
+/// # Arguments
+/// * `output` - The path of the output file
+pub fn sidecar_path(output: &Path) -> PathBuf {
+    let mut path = output.as_os_str().to_owned();
+    path.push(".json");
+    PathBuf::from(path)
+}
+
+/// Writes the metadata sidecar of an output
+///
+/// # Arguments
+/// * `output` - The path of the output file
+/// * `url` - The converted URL, None for merged PDFs
+/// * `metadata` - The document metadata
+/// * `language` - The detected document language
+/// * `pages` - The page count of the output
+pub async fn write_sidecar(
+    output: &Path,
+    url: Option<&str>,
+    metadata: &Metadata,
+    language: Option<&str>,
+    pages: usize,
+) -> Result<()> {
+    let sidecar = Sidecar {
+        url,
+        title: metadata.title.as_deref(),
+        author: metadata.author.as_deref(),
+        subject: metadata.subject.as_deref(),
+        keywords: metadata.keywords.as_deref(),
+        language,
+        pages,
+    };
+    fs::write(
+        long_path(&sidecar_path(output)),
+        serde_json::to_string_pretty(&sidecar)?,
+    )
+    .await?;
+    Ok(())
+}
@@ -0,0 +1,141 @@
+use std::{
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
+use crate::{failure::FailureClass, monitor::ResourceStats};
+
+/// Statistics of a successfully created PDF
+#[derive(Debug, Clone)]
+pub struct PdfStats {
+    pub pages: usize,
+    pub size: usize,
+    /// Duration of every phase of the conversion, in order
+    pub phases: Vec<(&'static str, Duration)>,
+    /// Hash of the text content, see --alert-if-changed
+    pub content_hash: Option<String>,
+    /// Whether the content changed since the baseline, None if the page is not in it
+    pub changed: Option<bool>,
+    /// The earlier output this one is identical to, see --dedup
+    pub duplicate_of: Option<PathBuf>,
+}
+
+/// Measures the duration of consecutive phases
+#[derive(Debug)]
+pub struct PhaseTimer {
+    job: usize,
+    last: Instant,
+    phases: Vec<(&'static str, Duration)>,
+}
+impl PhaseTimer {
+    /// Starts the first phase of the job with the given index
+    pub fn new(job: usize) -> PhaseTimer {
+        PhaseTimer {
+            job,
+            last: Instant::now(),
+            phases: Vec::new(),
+        }
+    }
+
+    /// Ends the current phase, the next phase starts now
+    pub fn mark(&mut self, name: &'static str) {
+        let now = Instant::now();
+        #[cfg(feature = "otel")]
+        crate::otel::record_phase(name, now - self.last);
+        crate::progress::phase(self.job, name, now - self.last);
+        self.phases.push((name, now - self.last));
+        self.last = now;
+    }
+
+    /// The last phase that ended, None during the first one
+    pub fn last(&self) -> Option<&'static str> {
+        self.phases.last().map(|(name, _)| *name)
+    }
+
+    pub fn finish(&mut self) -> Vec<(&'static str, Duration)> {
+        std::mem::take(&mut self.phases)
+    }
+}
+
+/// Outcome of a single URL-Path pair
+#[derive(Debug, Clone)]
+pub struct JobSummary {
+    pub url: String,
+    pub path: PathBuf,
+    pub duration: Duration,
+    pub outcome: Result<PdfStats, String>,
+    /// The class of the failure, None if the job succeeded
+    pub failure: Option<FailureClass>,
+    /// Resource usage of the browser while the job was running
+    pub resources: Option<ResourceStats>,
+}
+
+/// Prints a table of all jobs to stderr
+///
+/// # Arguments
+/// * `jobs` - The finished jobs
+/// * `resources` - Resource usage of the browser over the whole run
+/// * `color` - Whether to colorize the status column
+pub fn print_summary(jobs: &[JobSummary], resources: Option<ResourceStats>, color: bool) {
+    eprintln!(
+        "\n{:<7} {:>6} {:>10} {:>8} {:>7} {:>10}  URL",
+        "STATUS", "PAGES", "SIZE", "TIME", "CPU", "RSS"
+    );
+    for job in jobs {
+        let (status, pages, size) = match &job.outcome {
+            Ok(stats) => ("ok", stats.pages.to_string(), format_size(stats.size)),
+            Err(_) => ("failed", String::from("-"), String::from("-")),
+        };
+        let status = format!("{:<7}", status);
+        let status = match (color, job.outcome.is_ok()) {
+            (false, _) => status,
+            (true, true) => format!("\x1b[32m{}\x1b[0m", status),
+            (true, false) => format!("\x1b[31m{}\x1b[0m", status),
+        };
+        let (cpu, rss) = match &job.resources {
+            Some(resources) => (
+                format!("{:.0}%", resources.cpu_peak),
+                format_size(resources.rss_peak as usize),
+            ),
+            None => (String::from("-"), String::from("-")),
+        };
+        eprintln!(
+            "{} {:>6} {:>10} {:>7.1}s {:>7} {:>10}  {}",
+            status,
+            pages,
+            size,
+            job.duration.as_secs_f64(),
+            cpu,
+            rss,
+            job.url
+        );
+    }
+
+    let failed = jobs.iter().filter(|job| job.outcome.is_err()).count();
+    eprintln!("\n{} succeeded, {} failed", jobs.len() - failed, failed);
+    if let Some(resources) = resources {
+        eprintln!(
+            "Browser CPU peak {:.0}% (avg {:.0}%), RSS peak {} (avg {})",
+            resources.cpu_peak,
+            resources.cpu_avg,
+            format_size(resources.rss_peak as usize),
+            format_size(resources.rss_avg as usize)
+        );
+    }
+}
+
+/// Formats a size in bytes as a human readable string
+fn format_size(size: usize) -> String {
+    const UNITS: [&str; 4] = ["B", "KiB", "MiB", "GiB"];
+    let mut size = size as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} {}", size, UNITS[unit])
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
@@ -0,0 +1,118 @@
+use std::path::PathBuf;
+
+use chromiumoxide::detection::{self, DetectionOptions};
+use serde::Serialize;
+use web2pdf_lib::{Browser, BrowserConfig, BrowserWeb2Pdf};
+
+use crate::Cli;
+
+/// Version of the JSON format, increased on incompatible changes
+const FORMAT_VERSION: u32 = 1;
+
+/// JSON of --version-json
+#[derive(Debug, Serialize)]
+struct VersionInfo {
+    format_version: u32,
+    version: &'static str,
+    git_hash: Option<&'static str>,
+    chromiumoxide_version: Option<&'static str>,
+    features: Vec<&'static str>,
+    browser: BrowserInfo,
+}
+
+/// The browser that would be used for converting
+#[derive(Debug, Default, Serialize)]
+struct BrowserInfo {
+    /// Executable path, None when connecting to a running browser
+    path: Option<PathBuf>,
+    product: Option<String>,
+    revision: Option<String>,
+    protocol_version: Option<String>,
+    user_agent: Option<String>,
+    js_version: Option<String>,
+    /// Why the browser could not be found or started
+    error: Option<String>,
+}
+
+/// Prints the versions of the tool and the rendering stack as JSON
+///
+/// # Arguments
+/// * `cli` - The cli, for the browser path and --connect
+pub async fn print_version_json(cli: &Cli) {
+    let mut features = Vec::new();
+    if cfg!(feature = "fetcher") {
+        features.push("fetcher");
+    }
+    if cfg!(feature = "otel") {
+        features.push("otel");
+    }
+    if cfg!(feature = "keyring") {
+        features.push("keyring");
+    }
+
+    let info = VersionInfo {
+        format_version: FORMAT_VERSION,
+        version: env!("CARGO_PKG_VERSION"),
+        git_hash: option_env!("WEB2PDF_GIT_HASH"),
+        chromiumoxide_version: option_env!("WEB2PDF_CHROMIUMOXIDE_VERSION"),
+        features,
+        browser: browser_info(cli).await,
+    };
+    println!(
+        "{}",
+        serde_json::to_string_pretty(&info).expect("version info is serializable")
+    );
+}
+
+/// Finds the browser the same way as a conversion and asks it for its version
+async fn browser_info(cli: &Cli) -> BrowserInfo {
+    let mut info = BrowserInfo::default();
+    let browser = if let Some(url) = &cli.connect {
+        Browser::web2pdf_connect(url.as_str()).await
+    } else {
+        let path = cli
+            .browser_path
+            .clone()
+            .or_else(|| detection::default_executable(DetectionOptions::default()).ok());
+        #[cfg(feature = "fetcher")]
+        let path = path.or_else(web2pdf_lib::fetcher::installed_executable);
+        let Some(path) = path else {
+            info.error = Some("no browser found".to_string());
+            return info;
+        };
+        info.path = Some(path.clone());
+        let mut config = BrowserConfig::builder()
+            .chrome_executable(path)
+            .args(&cli.chrome_args);
+        if cli.no_sandbox {
+            config = config.no_sandbox();
+        }
+        match config.build() {
+            Ok(config) => Browser::web2pdf_launch_from_config(config).await,
+            Err(e) => Err(e.into()),
+        }
+    };
+
+    let browser = match browser {
+        Ok(browser) => browser,
+        Err(e) => {
+            info.error = Some(e.to_string());
+            return info;
+        }
+    };
+    match browser.version().await {
+        Ok(version) => {
+            info.product = Some(version.product);
+            info.revision = Some(version.revision);
+            info.protocol_version = Some(version.protocol_version);
+            info.user_agent = Some(version.user_agent);
+            info.js_version = Some(version.js_version);
+        }
+        Err(e) => info.error = Some(e.to_string()),
+    }
+    // A browser attached to with --connect is left running
+    if cli.connect.is_none() {
+        let _ = browser.close_and_wait().await;
+    }
+    info
+}
@@ -0,0 +1,22 @@
+//! Regenerates include/web2pdf.h with the `header` feature and sets up linking of the Node addon
+
+fn main() {
+    #[cfg(feature = "header")]
+    {
+        let crate_dir =
+            std::env::var("CARGO_MANIFEST_DIR").expect("CARGO_MANIFEST_DIR is set by cargo");
+        cbindgen::Builder::new()
+            .with_crate(&crate_dir)
+            .with_language(cbindgen::Language::C)
+            .with_include_guard("WEB2PDF_H")
+            .with_documentation(true)
+            .generate()
+            .expect("Unable to generate the C header")
+            .write_to_file(std::path::Path::new(&crate_dir).join("include/web2pdf.h"));
+        println!("cargo:rerun-if-changed=src/lib.rs");
+    }
+    // Node-API symbols are resolved when Node loads the addon
+    #[cfg(feature = "node")]
+    napi_build::setup();
+    println!("cargo:rerun-if-changed=build.rs");
+}
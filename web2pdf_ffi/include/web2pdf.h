@@ -0,0 +1,35 @@
+#ifndef WEB2PDF_H
+#define WEB2PDF_H
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/**
+ * Converts a web page to a PDF file with the Chromium browser installed on the system
+ *
+ * # Arguments
+ * * `url` - The URL or the path of a local file
+ * * `path` - The path to save the PDF file to
+ * * `options_json` - The options as a JSON object (see `web2pdf_lib::simple::ConvertOptions`),
+ *   e.g. `{"paper": "a4", "landscape": true}`, or NULL for the defaults
+ *
+ * # Returns
+ * 0 if the PDF was saved, -1 otherwise (see `web2pdf_last_error`)
+ *
+ * # Safety
+ * `url`, `path` and `options_json` (unless NULL) must be valid NUL-terminated strings.
+ */
+int web2pdf_convert(const char *url, const char *path, const char *options_json);
+
+/**
+ * The message of the last failed call on this thread
+ *
+ * # Returns
+ * A NUL-terminated string owned by the library, valid until the next call on this thread,
+ * or NULL if the last call succeeded.
+ */
+const char *web2pdf_last_error(void);
+
+#endif  /* WEB2PDF_H */
@@ -0,0 +1,99 @@
+//! C ABI bindings of the conversion, see `include/web2pdf.h`
+//! Every function is blocking and may be called from any thread. Errors are reported with a
+//! return value of -1, the message of the last error of the calling thread is returned by
+//! `web2pdf_last_error`.
+//! With the `node` feature the library is also a Node addon, see `node`.
+
+use std::cell::RefCell;
+use std::ffi::{c_char, c_int, CStr, CString};
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+
+use web2pdf_lib::simple::{self, ConvertOptions};
+
+#[cfg(feature = "node")]
+pub mod node;
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+/// Converts a web page to a PDF file with the Chromium browser installed on the system
+///
+/// # Arguments
+/// * `url` - The URL or the path of a local file
+/// * `path` - The path to save the PDF file to
+/// * `options_json` - The options as a JSON object (see `web2pdf_lib::simple::ConvertOptions`),
+///   e.g. `{"paper": "a4", "landscape": true}`, or NULL for the defaults
+///
+/// # Returns
+/// 0 if the PDF was saved, -1 otherwise (see `web2pdf_last_error`)
+///
+/// # Safety
+/// `url`, `path` and `options_json` (unless NULL) must be valid NUL-terminated strings.
+#[no_mangle]
+pub unsafe extern "C" fn web2pdf_convert(
+    url: *const c_char,
+    path: *const c_char,
+    options_json: *const c_char,
+) -> c_int {
+    // Unwinding into the caller is undefined behavior
+    let converted = panic::catch_unwind(AssertUnwindSafe(|| {
+        let url = text(url, "url")?;
+        let path = text(path, "path")?;
+        let options = if options_json.is_null() {
+            ConvertOptions::default()
+        } else {
+            ConvertOptions::from_json(text(options_json, "options_json")?)
+                .map_err(|e| format!("invalid options: {}", e))?
+        };
+        simple::convert(url, path, &options).map_err(|e| e.to_string())
+    }));
+    let error = match converted {
+        Ok(Ok(())) => {
+            set_last_error(None);
+            return 0;
+        }
+        Ok(Err(error)) => error,
+        Err(_) => String::from("web2pdf panicked"),
+    };
+    set_last_error(Some(error));
+    -1
+}
+
+/// The message of the last failed call on this thread
+///
+/// # Returns
+/// A NUL-terminated string owned by the library, valid until the next call on this thread,
+/// or NULL if the last call succeeded.
+#[no_mangle]
+pub extern "C" fn web2pdf_last_error() -> *const c_char {
+    LAST_ERROR.with(|error| {
+        error
+            .borrow()
+            .as_ref()
+            .map_or(ptr::null(), |error| error.as_ptr())
+    })
+}
+
+/// Reads a string argument
+///
+/// # Safety
+/// `value` must be NULL or a valid NUL-terminated string that outlives the call.
+unsafe fn text<'a>(value: *const c_char, name: &str) -> Result<&'a str, String> {
+    if value.is_null() {
+        return Err(format!("{} is NULL", name));
+    }
+    CStr::from_ptr(value)
+        .to_str()
+        .map_err(|_| format!("{} is not valid UTF-8", name))
+}
+
+fn set_last_error(error: Option<String>) {
+    // Messages with NUL bytes are cut at the first one
+    let error = error.map(|error| {
+        let end = error.find('\0').unwrap_or(error.len());
+        CString::new(&error[..end]).unwrap_or_default()
+    });
+    LAST_ERROR.with(|last| *last.borrow_mut() = error);
+}
@@ -0,0 +1,186 @@
+//! Node-API bindings of the conversion, enabled with the `node` feature
+//! The conversions run on the thread pool of Node and return promises, e.g.
+//! `await convert("https://example.com", "example.pdf", { mono: true })`.
+
+use napi::bindgen_prelude::AsyncTask;
+use napi::{Env, Error, Result, Task};
+use napi_derive::napi;
+
+use web2pdf_lib::simple::{ConvertOptions, Converter};
+
+/// Options of a conversion, see `web2pdf_lib::simple::ConvertOptions`
+/// Lengths are in inches, like the print options of the browser.
+#[napi(object)]
+#[derive(Default)]
+pub struct Options {
+    /// Print a single page that fits the content instead of standard pages
+    pub mono: Option<bool>,
+    pub landscape: Option<bool>,
+    /// One of a3, a4, a5, letter or legal (default: the page's CSS page size or letter)
+    pub paper: Option<String>,
+    /// Margin on all sides (default: 0.4)
+    pub margin: Option<f64>,
+    /// Scale of the rendering, between 0.1 and 2
+    pub scale: Option<f64>,
+    /// Pages to print, e.g. "1-5, 8"
+    pub page_ranges: Option<String>,
+    /// Omit the background colors and images of the page
+    pub no_background: Option<bool>,
+    /// Render with the screen stylesheets instead of the print stylesheets
+    pub screen: Option<bool>,
+    pub header_template: Option<String>,
+    pub footer_template: Option<String>,
+    /// CSS selector of an element to wait for before printing
+    pub wait_for_selector: Option<String>,
+    pub user_agent: Option<String>,
+    /// Value of the Accept-Language header, e.g. "de-DE,de;q=0.9"
+    pub accept_language: Option<String>,
+    /// Time limit of every conversion in seconds
+    pub timeout_secs: Option<u32>,
+    /// Path of the Chromium executable (default: the browser installed on the system)
+    pub browser_path: Option<String>,
+}
+
+impl Options {
+    /// Splits the options into the options of the conversion and the browser path
+    fn split(self) -> (ConvertOptions, Option<String>) {
+        let options = ConvertOptions {
+            mono: self.mono.unwrap_or_default(),
+            landscape: self.landscape.unwrap_or_default(),
+            paper: self.paper,
+            margin: self.margin,
+            scale: self.scale,
+            page_ranges: self.page_ranges,
+            no_background: self.no_background.unwrap_or_default(),
+            screen: self.screen.unwrap_or_default(),
+            header_template: self.header_template,
+            footer_template: self.footer_template,
+            wait_for_selector: self.wait_for_selector,
+            user_agent: self.user_agent,
+            accept_language: self.accept_language,
+            timeout_secs: self.timeout_secs.map(u64::from),
+        };
+        (options, self.browser_path)
+    }
+}
+
+/// A page of `convertBatch`
+#[napi(object)]
+pub struct Page {
+    /// The URL or the path of a local file
+    pub url: String,
+    /// The path to save the PDF file to
+    pub path: String,
+}
+
+/// The outcome of a page of `convertBatch`
+#[napi(object)]
+pub struct PageResult {
+    pub url: String,
+    pub path: String,
+    /// Why the page could not be converted, undefined if the PDF was saved
+    pub error: Option<String>,
+}
+
+pub struct ConvertTask {
+    pages: Vec<Page>,
+    options: ConvertOptions,
+    browser_path: Option<String>,
+}
+
+impl ConvertTask {
+    fn new(pages: Vec<Page>, options: Option<Options>) -> ConvertTask {
+        let (options, browser_path) = options.unwrap_or_default().split();
+        ConvertTask {
+            pages,
+            options,
+            browser_path,
+        }
+    }
+}
+
+impl Task for ConvertTask {
+    type Output = Vec<PageResult>;
+    type JsValue = Vec<PageResult>;
+
+    /// Converts the pages one after another with a single browser
+    fn compute(&mut self) -> Result<Vec<PageResult>> {
+        let converter = match &self.browser_path {
+            Some(path) => Converter::with_browser_path(path),
+            None => Converter::new(),
+        }
+        .map_err(|e| Error::from_reason(format!("failed to launch the browser: {}", e)))?;
+        let results = self
+            .pages
+            .drain(..)
+            .map(|page| {
+                let error = converter
+                    .convert(&page.url, &page.path, &self.options)
+                    .err()
+                    .map(|e| e.to_string());
+                PageResult {
+                    url: page.url,
+                    path: page.path,
+                    error,
+                }
+            })
+            .collect();
+        // A failed close only leaks the browser process, the PDFs are saved
+        let _ = converter.close();
+        Ok(results)
+    }
+
+    fn resolve(&mut self, _env: Env, output: Vec<PageResult>) -> Result<Vec<PageResult>> {
+        Ok(output)
+    }
+}
+
+/// A batch of one page, its error rejects the promise
+pub struct SingleTask(ConvertTask);
+
+impl Task for SingleTask {
+    type Output = ();
+    type JsValue = ();
+
+    fn compute(&mut self) -> Result<()> {
+        match self.0.compute()?.pop().and_then(|result| result.error) {
+            Some(error) => Err(Error::from_reason(error)),
+            None => Ok(()),
+        }
+    }
+
+    fn resolve(&mut self, _env: Env, _output: ()) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Converts a web page to a PDF file
+///
+/// # Arguments
+/// * `url` - The URL or the path of a local file
+/// * `path` - The path to save the PDF file to
+/// * `options` - The options of the conversion
+///
+/// # Returns
+/// A promise that resolves once the PDF is saved and rejects with the reason otherwise
+#[napi(ts_return_type = "Promise<void>")]
+pub fn convert(url: String, path: String, options: Option<Options>) -> AsyncTask<SingleTask> {
+    AsyncTask::new(SingleTask(ConvertTask::new(
+        vec![Page { url, path }],
+        options,
+    )))
+}
+
+/// Converts web pages to PDF files, launching the browser only once
+/// A page that fails doesn't stop the others, its error is in its result.
+///
+/// # Arguments
+/// * `pages` - The URLs and paths of the pages
+/// * `options` - The options of every conversion
+///
+/// # Returns
+/// A promise of the results in the order of the pages, it rejects if the browser can't be launched
+#[napi(ts_return_type = "Promise<PageResult[]>")]
+pub fn convert_batch(pages: Vec<Page>, options: Option<Options>) -> AsyncTask<ConvertTask> {
+    AsyncTask::new(ConvertTask::new(pages, options))
+}
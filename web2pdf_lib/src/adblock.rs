@@ -0,0 +1,563 @@
+use std::{collections::HashMap, fmt, path::Path};
+
+use chromiumoxide::cdp::browser_protocol::network::ResourceType;
+use url::Url;
+
+use crate::Result;
+
+/// An Adblock-style filter list (e.g. EasyList)
+///
+/// Supported are network rules (`||ads.example.com^`, `/banner/*.gif`, `@@` exceptions) with the
+/// options `third-party`, `domain`, `match-case`, `important` and resource types,
+/// as well as element hiding rules (`##.ad`, `example.com##.ad`, `#@#` exceptions).
+/// Rules using unsupported features (regular expressions, redirects, scriptlets, procedural
+/// cosmetic filters, ...) are skipped.
+#[derive(Default)]
+pub struct FilterList {
+    /// Rules anchored to a host (`||example.com^`), indexed by that host
+    host_rules: HashMap<String, Vec<NetworkRule>>,
+    rules: Vec<NetworkRule>,
+    exceptions: Vec<NetworkRule>,
+    hide: Vec<CosmeticRule>,
+    unhide: Vec<CosmeticRule>,
+    skipped: usize,
+}
+impl fmt::Debug for FilterList {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("FilterList")
+            .field(
+                "network_rules",
+                &(self.host_rules.values().map(Vec::len).sum::<usize>() + self.rules.len()),
+            )
+            .field("exceptions", &self.exceptions.len())
+            .field("hiding_rules", &self.hide.len())
+            .field("skipped", &self.skipped)
+            .finish()
+    }
+}
+
+/// A request as seen by the filter list
+pub struct FilterRequest<'a> {
+    pub url: &'a str,
+    /// The URL of the document making the request
+    pub document_url: Option<&'a str>,
+    pub resource_type: &'a ResourceType,
+    /// Whether the request loads the top level document of the page
+    pub is_main_document: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Anchor {
+    None,
+    /// `|` at the start, the URL has to start with the pattern
+    Start,
+    /// `||` at the start, the pattern has to start at the beginning of a host label
+    Host,
+}
+
+#[derive(Debug)]
+struct NetworkRule {
+    /// The pattern, lowercase unless `match_case` is set
+    pattern: String,
+    /// Longest part of the pattern without wildcards, for a quick pre check
+    literal: String,
+    anchor: Anchor,
+    end_anchor: bool,
+    match_case: bool,
+    important: bool,
+    third_party: Option<bool>,
+    /// Resource types the rule applies to, all but the main document if empty
+    types: Vec<ResourceType>,
+    excluded_types: Vec<ResourceType>,
+    /// Domains of the document the rule applies to (`true`) or not (`false`)
+    domains: Vec<(String, bool)>,
+}
+
+#[derive(Debug)]
+struct CosmeticRule {
+    selector: String,
+    /// Domains the rule applies to (`true`) or not (`false`), generic if empty
+    domains: Vec<(String, bool)>,
+}
+
+impl FilterList {
+    /// Parses a filter list
+    ///
+    /// # Arguments
+    /// * `list` - The contents of the filter list
+    ///
+    /// # Returns
+    /// The parsed filter list, unsupported rules are skipped
+    pub fn parse(list: &str) -> FilterList {
+        let mut filter_list = FilterList::default();
+        for line in list.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('!') || line.starts_with('[') {
+                continue;
+            }
+            if !filter_list.add_rule(line) {
+                filter_list.skipped += 1;
+            }
+        }
+        tracing::debug!("Web2Pdf parsed filter list: {:?}", filter_list);
+        filter_list
+    }
+
+    /// Reads and parses a filter list file
+    ///
+    /// # Arguments
+    /// * `path` - The path of the filter list
+    ///
+    /// # Returns
+    /// A `Result` containing the parsed filter list or an error.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<FilterList> {
+        Ok(FilterList::parse(&std::fs::read_to_string(path)?))
+    }
+
+    /// Adds a single rule, returns false if it is not supported
+    fn add_rule(&mut self, line: &str) -> bool {
+        // Element hiding rules, extended syntaxes (#?#, #$#, #%#, #@$#, ...) are not supported
+        if let Some((domains, selector)) = line.split_once("#@#") {
+            return self.add_cosmetic_rule(domains, selector, true);
+        }
+        if let Some((domains, selector)) = line.split_once("##") {
+            return self.add_cosmetic_rule(domains, selector, false);
+        }
+        if ["#?#", "#$#", "#%#", "#@?#", "#@$#", "#@%#"]
+            .iter()
+            .any(|syntax| line.contains(syntax))
+        {
+            return false;
+        }
+
+        let (line, exception) = match line.strip_prefix("@@") {
+            Some(line) => (line, true),
+            None => (line, false),
+        };
+        let Some(rule) = parse_network_rule(line) else {
+            return false;
+        };
+
+        if exception {
+            self.exceptions.push(rule);
+        } else if let Some(host) = rule.indexed_host() {
+            self.host_rules.entry(host).or_default().push(rule);
+        } else {
+            self.rules.push(rule);
+        }
+        true
+    }
+
+    fn add_cosmetic_rule(&mut self, domains: &str, selector: &str, exception: bool) -> bool {
+        let selector = selector.trim();
+        if selector.is_empty()
+            || selector.starts_with('^')
+            || selector.starts_with("+js(")
+            || [
+                ":-abp-",
+                ":has-text(",
+                ":contains(",
+                ":xpath(",
+                ":upward(",
+                ":remove(",
+                ":style(",
+            ]
+            .iter()
+            .any(|procedural| selector.contains(procedural))
+        {
+            return false;
+        }
+        let rule = CosmeticRule {
+            selector: selector.to_string(),
+            domains: parse_domains(domains, ','),
+        };
+        if exception {
+            self.unhide.push(rule);
+        } else {
+            self.hide.push(rule);
+        }
+        true
+    }
+
+    /// Whether a request should be blocked
+    ///
+    /// # Arguments
+    /// * `request` - The request to check
+    pub fn is_blocked(&self, request: &FilterRequest) -> bool {
+        let Ok(url) = Url::parse(request.url) else {
+            return false;
+        };
+        if !matches!(url.scheme(), "http" | "https" | "ws" | "wss") {
+            return false;
+        }
+        let context = MatchContext::new(request, &url);
+
+        let candidates = self.rules.iter().chain(
+            host_suffixes(&context.host)
+                .filter_map(|host| self.host_rules.get(host))
+                .flatten(),
+        );
+        let mut matched = false;
+        for rule in candidates.filter(|rule| rule.matches(&context)) {
+            // Important rules can not be overridden by exceptions
+            if rule.important {
+                return true;
+            }
+            matched = true;
+        }
+        matched && !self.exceptions.iter().any(|rule| rule.matches(&context))
+    }
+
+    /// CSS selectors of the elements to hide on a page
+    ///
+    /// # Arguments
+    /// * `page_url` - The URL of the page
+    pub fn hidden_selectors(&self, page_url: &str) -> Vec<&str> {
+        let host = Url::parse(page_url)
+            .ok()
+            .and_then(|url| url.host_str().map(str::to_lowercase))
+            .unwrap_or_default();
+
+        let applies = |rule: &CosmeticRule| domains_match(&rule.domains, &host);
+        let unhidden: Vec<&str> = self
+            .unhide
+            .iter()
+            .filter(|rule| applies(rule))
+            .map(|rule| rule.selector.as_str())
+            .collect();
+        self.hide
+            .iter()
+            .filter(|rule| applies(rule) && !unhidden.contains(&rule.selector.as_str()))
+            .map(|rule| rule.selector.as_str())
+            .collect()
+    }
+}
+
+/// Everything about a request needed to match network rules
+struct MatchContext<'a> {
+    url: &'a str,
+    url_lowercase: String,
+    host: String,
+    document_host: String,
+    third_party: bool,
+    resource_type: &'a ResourceType,
+    is_main_document: bool,
+}
+impl<'a> MatchContext<'a> {
+    fn new(request: &'a FilterRequest, url: &Url) -> MatchContext<'a> {
+        let host = url.host_str().unwrap_or_default().to_lowercase();
+        let document_host = request
+            .document_url
+            .and_then(|url| Url::parse(url).ok())
+            .and_then(|url| url.host_str().map(str::to_lowercase))
+            .unwrap_or_default();
+        MatchContext {
+            url: request.url,
+            url_lowercase: request.url.to_lowercase(),
+            third_party: !document_host.is_empty()
+                && base_domain(&host) != base_domain(&document_host),
+            host,
+            document_host,
+            resource_type: request.resource_type,
+            is_main_document: request.is_main_document,
+        }
+    }
+}
+
+impl NetworkRule {
+    /// The host of a `||host^` rule, used to only check the rule for requests to that host
+    fn indexed_host(&self) -> Option<String> {
+        if self.anchor != Anchor::Host || self.match_case {
+            return None;
+        }
+        let end = self
+            .pattern
+            .find(['^', '/', '*', '|', ':'])
+            .unwrap_or(self.pattern.len());
+        let host = &self.pattern[..end];
+        // Only complete hosts, `||ads.` also matches `ads.example.com`
+        let complete = matches!(self.pattern[end..].chars().next(), None | Some('^' | '/'));
+        (complete && host.contains('.') && !host.ends_with('.')).then(|| host.to_string())
+    }
+
+    fn matches(&self, context: &MatchContext) -> bool {
+        let url = if self.match_case {
+            context.url
+        } else {
+            &context.url_lowercase
+        };
+        if !url.contains(&self.literal) {
+            return false;
+        }
+
+        if context.is_main_document {
+            // Blocking the page itself is never wanted when converting it
+            return false;
+        }
+        if !self.types.is_empty() && !self.types.contains(context.resource_type) {
+            return false;
+        }
+        if self.excluded_types.contains(context.resource_type) {
+            return false;
+        }
+        if self
+            .third_party
+            .is_some_and(|third_party| third_party != context.third_party)
+        {
+            return false;
+        }
+        if !domains_match(&self.domains, &context.document_host) {
+            return false;
+        }
+
+        let pattern = self.pattern.as_bytes();
+        let text = url.as_bytes();
+        match self.anchor {
+            Anchor::Start => wildcard_match(pattern, text, false, self.end_anchor),
+            Anchor::None => wildcard_match(pattern, text, true, self.end_anchor),
+            Anchor::Host => {
+                // The pattern may start at the host or any of its subdomains
+                let Some(host_start) = url.find("://").map(|i| i + 3) else {
+                    return false;
+                };
+                let host_end = url[host_start..]
+                    .find(['/', '?', '#'])
+                    .map_or(url.len(), |i| host_start + i);
+                let host_start = url[host_start..host_end]
+                    .rfind('@')
+                    .map_or(host_start, |i| host_start + i + 1);
+                std::iter::once(host_start)
+                    .chain(
+                        url[host_start..host_end]
+                            .match_indices('.')
+                            .map(|(i, _)| host_start + i + 1),
+                    )
+                    .any(|start| wildcard_match(pattern, &text[start..], false, self.end_anchor))
+            }
+        }
+    }
+}
+
+/// Parses a network rule (without the `@@` of exceptions)
+fn parse_network_rule(line: &str) -> Option<NetworkRule> {
+    // Regular expression rules
+    if line.starts_with('/') && line.len() > 1 && line.ends_with('/') {
+        return None;
+    }
+
+    let (pattern, options) = match line.rfind('$') {
+        // A `$` inside the pattern (e.g. in a query) is not an option separator
+        Some(i) if !line[i + 1..].contains('/') => (&line[..i], &line[i + 1..]),
+        _ => (line, ""),
+    };
+
+    let mut rule = NetworkRule {
+        pattern: String::new(),
+        literal: String::new(),
+        anchor: Anchor::None,
+        end_anchor: false,
+        match_case: false,
+        important: false,
+        third_party: None,
+        types: Vec::new(),
+        excluded_types: Vec::new(),
+        domains: Vec::new(),
+    };
+
+    for option in options.split(',').filter(|option| !option.is_empty()) {
+        let (option, negated) = match option.strip_prefix('~') {
+            Some(option) => (option, true),
+            None => (option, false),
+        };
+        match option {
+            "third-party" | "3p" => rule.third_party = Some(!negated),
+            "first-party" | "1p" => rule.third_party = Some(negated),
+            "match-case" => rule.match_case = true,
+            "important" => rule.important = true,
+            _ if option.starts_with("domain=") || option.starts_with("from=") => {
+                rule.domains = parse_domains(option.split_once('=')?.1, '|');
+            }
+            _ => {
+                let types = resource_types(option)?;
+                if negated {
+                    rule.excluded_types.extend(types);
+                } else {
+                    rule.types.extend(types);
+                }
+            }
+        }
+    }
+
+    let mut pattern = pattern;
+    if let Some(rest) = pattern.strip_prefix("||") {
+        rule.anchor = Anchor::Host;
+        pattern = rest;
+    } else if let Some(rest) = pattern.strip_prefix('|') {
+        rule.anchor = Anchor::Start;
+        pattern = rest;
+    }
+    if let Some(rest) = pattern.strip_suffix('|') {
+        rule.end_anchor = true;
+        pattern = rest;
+    }
+    if pattern.is_empty() && rule.domains.is_empty() {
+        // Would match every request
+        return None;
+    }
+
+    rule.pattern = if rule.match_case {
+        pattern.to_string()
+    } else {
+        pattern.to_lowercase()
+    };
+    rule.literal = rule
+        .pattern
+        .split(['*', '^'])
+        .max_by_key(|part| part.len())
+        .unwrap_or_default()
+        .to_string();
+    Some(rule)
+}
+
+/// Resource types of a type option, None if the option is not supported
+fn resource_types(option: &str) -> Option<Vec<ResourceType>> {
+    Some(match option {
+        "script" => vec![ResourceType::Script],
+        "image" => vec![ResourceType::Image],
+        "stylesheet" | "css" => vec![ResourceType::Stylesheet],
+        "font" => vec![ResourceType::Font],
+        "media" => vec![ResourceType::Media],
+        "xmlhttprequest" | "xhr" => vec![ResourceType::Xhr, ResourceType::Fetch],
+        "subdocument" | "frame" => vec![ResourceType::Document],
+        "websocket" => vec![ResourceType::WebSocket],
+        "ping" => vec![ResourceType::Ping, ResourceType::CspViolationReport],
+        "object" | "object-subrequest" => vec![ResourceType::Other],
+        "other" => vec![
+            ResourceType::Other,
+            ResourceType::TextTrack,
+            ResourceType::Prefetch,
+            ResourceType::EventSource,
+            ResourceType::Manifest,
+            ResourceType::SignedExchange,
+        ],
+        _ => return None,
+    })
+}
+
+/// Parses a list of domains, domains starting with `~` are excluded
+fn parse_domains(domains: &str, separator: char) -> Vec<(String, bool)> {
+    domains
+        .split(separator)
+        .map(str::trim)
+        .filter(|domain| !domain.is_empty())
+        .map(|domain| match domain.strip_prefix('~') {
+            Some(domain) => (domain.to_lowercase(), false),
+            None => (domain.to_lowercase(), true),
+        })
+        .collect()
+}
+
+/// Whether a host is matched by a list of included and excluded domains
+/// An empty list matches every host, a list of only exclusions every other host.
+fn domains_match(domains: &[(String, bool)], host: &str) -> bool {
+    let is_subdomain = |domain: &str| {
+        host == domain
+            || host
+                .strip_suffix(domain)
+                .is_some_and(|prefix| prefix.ends_with('.'))
+    };
+    if domains
+        .iter()
+        .any(|(domain, included)| !included && is_subdomain(domain))
+    {
+        return false;
+    }
+    let mut included = domains.iter().filter(|(_, included)| *included).peekable();
+    included.peek().is_none() || included.any(|(domain, _)| is_subdomain(domain))
+}
+
+/// The host and all of its parent domains, e.g. a.b.com, b.com, com
+fn host_suffixes(host: &str) -> impl Iterator<Item = &str> {
+    std::iter::once(host).chain(host.match_indices('.').map(|(i, _)| &host[i + 1..]))
+}
+
+/// Approximation of the registrable domain, without a public suffix list
+/// e.g. www.example.com -> example.com, news.bbc.co.uk -> bbc.co.uk
+fn base_domain(host: &str) -> &str {
+    let labels: Vec<&str> = host.rsplitn(4, '.').collect();
+    let count = if labels.len() >= 3
+        && labels[0].len() == 2
+        && matches!(
+            labels[1],
+            "co" | "com" | "net" | "org" | "gov" | "edu" | "ac"
+        ) {
+        3
+    } else {
+        2
+    };
+    if labels.len() <= count {
+        return host;
+    }
+    let suffix_len: usize = labels[..count]
+        .iter()
+        .map(|label| label.len())
+        .sum::<usize>()
+        + count
+        - 1;
+    &host[host.len() - suffix_len..]
+}
+
+/// Whether a character separates parts of a URL (matched by `^`)
+fn is_separator(c: u8) -> bool {
+    !(c.is_ascii_alphanumeric() || matches!(c, b'_' | b'-' | b'.' | b'%'))
+}
+
+/// Matches `text` against an Adblock pattern, where `*` matches any number of characters
+/// and `^` a separator or the end of the text
+///
+/// # Arguments
+/// * `floating` - Whether the pattern may start anywhere in the text instead of only at its start
+/// * `end_anchor` - Whether the pattern has to match until the end of the text
+fn wildcard_match(pattern: &[u8], text: &[u8], floating: bool, end_anchor: bool) -> bool {
+    let (mut p, mut t) = (0, 0);
+    // Position after the last `*` in the pattern and the text position it was tried at
+    let mut backtrack = floating.then_some((0, 0));
+    loop {
+        if p == pattern.len() {
+            if !end_anchor || t == text.len() {
+                return true;
+            }
+        } else {
+            match pattern[p] {
+                b'*' => {
+                    p += 1;
+                    backtrack = Some((p, t));
+                    continue;
+                }
+                b'^' if t == text.len() => {
+                    p += 1;
+                    continue;
+                }
+                b'^' if is_separator(text[t]) => {
+                    p += 1;
+                    t += 1;
+                    continue;
+                }
+                c if t < text.len() && c == text[t] => {
+                    p += 1;
+                    t += 1;
+                    continue;
+                }
+                _ => {}
+            }
+        }
+        match backtrack {
+            Some((star_p, star_t)) if star_t < text.len() => {
+                p = star_p;
+                t = star_t + 1;
+                backtrack = Some((star_p, star_t + 1));
+            }
+            _ => return false,
+        }
+    }
+}
@@ -0,0 +1,721 @@
+//! Imports cookies from the profiles of installed browsers
+//! Reads copies of the cookie stores, so the browser may keep running.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use aes::cipher::{BlockCipherDecrypt, BlockCipherEncrypt, KeyInit};
+use aes::{Aes128, Aes256};
+use base64::prelude::*;
+use chromiumoxide::cdp::browser_protocol::network::{CookieParam, CookieSameSite, TimeSinceEpoch};
+use rusqlite::types::Value;
+use rusqlite::{Connection, OpenFlags};
+use sha1::{Digest, Sha1};
+use tokio::fs;
+
+use crate::util::host_only_url;
+use crate::Result;
+
+/// Error for when importing cookies from a browser profile
+#[derive(Debug, Clone)]
+struct BrowserCookiesError {
+    error_message: String,
+}
+impl fmt::Display for BrowserCookiesError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Error importing browser cookies: {}", self.error_message)
+    }
+}
+impl std::error::Error for BrowserCookiesError {
+    fn description(&self) -> &str {
+        &self.error_message
+    }
+}
+
+fn browser_cookies_error(message: impl Into<String>) -> Box<dyn std::error::Error> {
+    Box::new(BrowserCookiesError {
+        error_message: message.into(),
+    })
+}
+
+/// A row of a table, by column name
+type Row = HashMap<String, Value>;
+
+/// A read-only copy of a cookie store, removed on drop
+/// The browser locks its database and keeps recent changes in the write-ahead log (`-wal`), so
+/// both are copied and the copy is opened instead.
+struct Database {
+    connection: Option<Connection>,
+    dir: PathBuf,
+}
+
+impl Database {
+    async fn open(path: impl AsRef<Path>) -> Result<Database> {
+        static COPIES: AtomicUsize = AtomicUsize::new(0);
+        let path = path.as_ref();
+        let dir = std::env::temp_dir().join(format!(
+            "web2pdf-cookies-{}-{}",
+            std::process::id(),
+            COPIES.fetch_add(1, Ordering::Relaxed)
+        ));
+        fs::create_dir_all(&dir).await?;
+        // Dropped on errors, which removes the copy
+        let mut database = Database {
+            connection: None,
+            dir,
+        };
+        let copy = database.dir.join("cookies.sqlite");
+        fs::copy(path, &copy)
+            .await
+            .map_err(|e| browser_cookies_error(format!("could not copy {:?}: {}", path, e)))?;
+        let mut wal = path.as_os_str().to_owned();
+        wal.push("-wal");
+        if fs::metadata(&wal).await.is_ok() {
+            fs::copy(&wal, database.dir.join("cookies.sqlite-wal")).await?;
+        }
+        database.connection = Some(Connection::open_with_flags(
+            &copy,
+            OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+        )?);
+        Ok(database)
+    }
+
+    /// Reads all rows of a table
+    fn table(&self, name: &str) -> Result<Vec<Row>> {
+        let connection = self
+            .connection
+            .as_ref()
+            .ok_or_else(|| browser_cookies_error("the database is closed"))?;
+        let mut statement = connection.prepare(&format!("SELECT * FROM \"{}\"", name))?;
+        let columns: Vec<String> = statement
+            .column_names()
+            .into_iter()
+            .map(str::to_string)
+            .collect();
+        let rows = statement
+            .query_map([], |row| {
+                columns
+                    .iter()
+                    .enumerate()
+                    .map(|(index, column)| Ok((column.clone(), row.get::<_, Value>(index)?)))
+                    .collect::<rusqlite::Result<Row>>()
+            })?
+            .collect::<rusqlite::Result<Vec<Row>>>()?;
+        Ok(rows)
+    }
+}
+
+impl Drop for Database {
+    fn drop(&mut self) {
+        // Closed first, as open files can't be removed on Windows
+        drop(self.connection.take());
+        let _ = std::fs::remove_dir_all(&self.dir);
+    }
+}
+
+/// Reads the cookies of a Firefox profile (`cookies.sqlite`)
+/// Expired cookies and cookies of containers or partitioned storage are left out.
+///
+/// # Arguments
+/// * `profile` - The profile directory or profile name, None for the default profile
+///
+/// # Returns
+/// * The cookies, ready to be set in the browser
+pub async fn firefox_cookies(profile: Option<&str>) -> Result<Vec<CookieParam>> {
+    let profile = find_firefox_profile(profile).await?;
+    tracing::debug!("Reading Firefox cookies from {:?}", profile);
+    let database = Database::open(profile.join("cookies.sqlite")).await?;
+
+    let now = unix_time();
+    let mut cookies = Vec::new();
+    for row in database.table("moz_cookies")? {
+        // Cookies of containers and partitioned (third-party) storage are kept apart
+        if text(&row, "originAttributes").is_some_and(|attributes| !attributes.is_empty()) {
+            continue;
+        }
+        let (Some(name), Some(host)) = (text(&row, "name"), text(&row, "host")) else {
+            continue;
+        };
+        let expires = match integer(&row, "expiry") {
+            // Newer versions store milliseconds
+            Some(expiry) if expiry > 100_000_000_000 => expiry as f64 / 1000.0,
+            Some(expiry) => expiry as f64,
+            None => continue,
+        };
+        if expires < now {
+            continue;
+        }
+        let path = text(&row, "path").unwrap_or("/");
+        let secure = integer(&row, "isSecure") == Some(1);
+
+        let mut cookie_builder = CookieParam::builder()
+            .name(name)
+            .value(text(&row, "value").unwrap_or_default())
+            .path(path)
+            .secure(secure)
+            .http_only(integer(&row, "isHttpOnly") == Some(1))
+            .expires(TimeSinceEpoch::new(expires));
+        // Domain cookies start with a dot
+        cookie_builder = if host.starts_with('.') {
+            cookie_builder.domain(host)
+        } else {
+            cookie_builder.url(host_only_url(host, path, secure))
+        };
+        // Chrome rejects SameSite=None cookies that are not secure
+        let same_site = match integer(&row, "sameSite") {
+            Some(0) if secure => Some(CookieSameSite::None),
+            Some(1) => Some(CookieSameSite::Lax),
+            Some(2) => Some(CookieSameSite::Strict),
+            _ => None,
+        };
+        if let Some(same_site) = same_site {
+            cookie_builder = cookie_builder.same_site(same_site);
+        }
+        cookies.push(cookie_builder.build()?);
+    }
+    Ok(cookies)
+}
+
+/// Finds the directory of a Firefox profile
+///
+/// # Arguments
+/// * `profile` - A profile directory, the name of a profile (as listed in `profiles.ini`)
+///   or None for the default profile
+pub async fn find_firefox_profile(profile: Option<&str>) -> Result<PathBuf> {
+    if let Some(profile) = profile {
+        let path = PathBuf::from(profile);
+        if fs::metadata(path.join("cookies.sqlite")).await.is_ok() {
+            return Ok(path);
+        }
+    }
+
+    for root in firefox_roots() {
+        let Ok(profiles_ini) = fs::read_to_string(root.join("profiles.ini")).await else {
+            continue;
+        };
+        if let Some(path) = select_firefox_profile(&profiles_ini, profile) {
+            let path = root.join(path);
+            if fs::metadata(path.join("cookies.sqlite")).await.is_ok() {
+                return Ok(path);
+            }
+        }
+    }
+    Err(browser_cookies_error(match profile {
+        Some(profile) => format!("no Firefox profile {:?} found", profile),
+        None => "no Firefox profile found".to_string(),
+    }))
+}
+
+/// The directories Firefox keeps its profiles (and `profiles.ini`) in
+fn firefox_roots() -> Vec<PathBuf> {
+    let home = std::env::var_os("HOME").map(PathBuf::from);
+    let mut roots = Vec::new();
+    if cfg!(windows) {
+        if let Some(app_data) = std::env::var_os("APPDATA") {
+            roots.push(Path::new(&app_data).join("Mozilla").join("Firefox"));
+        }
+    } else if let Some(home) = home {
+        if cfg!(target_os = "macos") {
+            roots.push(home.join("Library/Application Support/Firefox"));
+        } else {
+            roots.push(home.join(".mozilla/firefox"));
+            roots.push(home.join("snap/firefox/common/.mozilla/firefox"));
+            roots.push(home.join(".var/app/org.mozilla.firefox/.mozilla/firefox"));
+        }
+    }
+    roots
+}
+
+/// Selects a profile from `profiles.ini`, returns its path relative to the Firefox directory
+/// (or absolute). Without name the default profile of the installation is preferred.
+fn select_firefox_profile(profiles_ini: &str, name: Option<&str>) -> Option<String> {
+    // (section, key, value) of every entry
+    let mut section = String::new();
+    let mut entries = Vec::new();
+    for line in profiles_ini.lines().map(str::trim) {
+        if let Some(name) = line
+            .strip_prefix('[')
+            .and_then(|line| line.strip_suffix(']'))
+        {
+            section = name.to_string();
+        } else if let Some((key, value)) = line.split_once('=') {
+            entries.push((section.clone(), key.trim(), value.trim()));
+        }
+    }
+    let value = |section: &str, key: &str| {
+        entries
+            .iter()
+            .find(|(entry_section, entry_key, _)| entry_section == section && *entry_key == key)
+            .map(|(_, _, value)| *value)
+    };
+    let mut profiles: Vec<&str> = entries
+        .iter()
+        .filter(|(section, key, _)| section.starts_with("Profile") && *key == "Path")
+        .map(|(section, _, _)| section.as_str())
+        .collect();
+    profiles.dedup();
+
+    let profile = match name {
+        Some(name) => profiles.into_iter().find(|profile| {
+            value(profile, "Name") == Some(name)
+                || value(profile, "Path").is_some_and(|path| path.ends_with(name))
+        })?,
+        None => {
+            // Firefox 67+ keeps a default profile per installation
+            let install_default = entries
+                .iter()
+                .find(|(section, key, _)| section.starts_with("Install") && *key == "Default")
+                .map(|(_, _, path)| *path);
+            if let Some(path) = install_default {
+                return Some(path.to_string());
+            }
+            profiles
+                .iter()
+                .find(|profile| value(profile, "Default") == Some("1"))
+                .or(profiles.first())
+                .copied()?
+        }
+    };
+    value(profile, "Path").map(str::to_string)
+}
+
+/// Seconds between 1601-01-01 (the epoch of Chrome timestamps) and 1970-01-01
+const CHROME_EPOCH_OFFSET: f64 = 11_644_473_600.0;
+
+/// Reads the cookies of a Chrome or Chromium profile (`Network/Cookies`)
+/// Values are decrypted with the key of the operating system: the keyring on Linux (through
+/// `secret-tool` or `kwallet-query`), the keychain on macOS (through `security`) and DPAPI on
+/// Windows (through PowerShell). Expired, partitioned and undecryptable cookies (e.g. the
+/// app-bound encryption of Chrome 127+ on Windows) are left out.
+///
+/// # Arguments
+/// * `profile` - The profile directory, e.g. "~/.config/google-chrome/Default", None for the
+///   default profile of Chrome or Chromium
+///
+/// # Returns
+/// * The cookies, ready to be set in the browser
+pub async fn chrome_cookies(profile: Option<&Path>) -> Result<Vec<CookieParam>> {
+    let profile = find_chrome_profile(profile).await?;
+    let mut database_path = profile.join("Network").join("Cookies");
+    if fs::metadata(&database_path).await.is_err() {
+        database_path = profile.join("Cookies");
+    }
+    tracing::debug!("Reading Chrome cookies from {:?}", database_path);
+    let database = Database::open(&database_path).await?;
+
+    // Since version 24 values start with the SHA-256 hash of their domain
+    let version = database
+        .table("meta")?
+        .iter()
+        .find(|row| text(row, "key") == Some("version"))
+        .and_then(|row| text(row, "value").and_then(|version| version.parse::<i64>().ok()))
+        .unwrap_or(0);
+    let keys = ChromeKeys::load(&profile).await;
+
+    let now = unix_time();
+    let mut cookies = Vec::new();
+    let mut undecryptable = 0;
+    for row in database.table("cookies")? {
+        if text(&row, "top_frame_site_key").is_some_and(|key| !key.is_empty()) {
+            continue;
+        }
+        let (Some(name), Some(host)) = (text(&row, "name"), text(&row, "host_key")) else {
+            continue;
+        };
+        let persistent = integer(&row, "is_persistent").or(integer(&row, "has_expires")) != Some(0);
+        let expires = integer(&row, "expires_utc")
+            .filter(|expires| persistent && *expires > 0)
+            .map(|expires| expires as f64 / 1_000_000.0 - CHROME_EPOCH_OFFSET);
+        if expires.is_some_and(|expires| expires < now) {
+            continue;
+        }
+
+        let value = match text(&row, "value").filter(|value| !value.is_empty()) {
+            Some(value) => value.to_string(),
+            None => {
+                let encrypted = match row.get("encrypted_value") {
+                    Some(Value::Blob(value)) => value.as_slice(),
+                    Some(Value::Text(value)) => value.as_bytes(),
+                    _ => &[],
+                };
+                match keys.decrypt(encrypted) {
+                    Some(mut value) => {
+                        if version >= 24 && value.len() >= 32 {
+                            value.drain(..32);
+                        }
+                        String::from_utf8_lossy(&value).into_owned()
+                    }
+                    None if encrypted.is_empty() => String::new(),
+                    None => {
+                        undecryptable += 1;
+                        continue;
+                    }
+                }
+            }
+        };
+        let path = text(&row, "path").unwrap_or("/");
+        let secure = integer(&row, "is_secure") == Some(1);
+
+        let mut cookie_builder = CookieParam::builder()
+            .name(name)
+            .value(value)
+            .path(path)
+            .secure(secure)
+            .http_only(integer(&row, "is_httponly") == Some(1));
+        if let Some(expires) = expires {
+            cookie_builder = cookie_builder.expires(TimeSinceEpoch::new(expires));
+        }
+        // Domain cookies start with a dot
+        cookie_builder = if host.starts_with('.') {
+            cookie_builder.domain(host)
+        } else {
+            cookie_builder.url(host_only_url(host, path, secure))
+        };
+        let same_site = match integer(&row, "samesite") {
+            Some(0) if secure => Some(CookieSameSite::None),
+            Some(1) => Some(CookieSameSite::Lax),
+            Some(2) => Some(CookieSameSite::Strict),
+            _ => None,
+        };
+        if let Some(same_site) = same_site {
+            cookie_builder = cookie_builder.same_site(same_site);
+        }
+        cookies.push(cookie_builder.build()?);
+    }
+    if undecryptable > 0 {
+        tracing::warn!(
+            "Could not decrypt {} cookies of the Chrome profile {:?}",
+            undecryptable,
+            profile
+        );
+    }
+    Ok(cookies)
+}
+
+/// Finds the directory of a Chrome or Chromium profile
+///
+/// # Arguments
+/// * `profile` - A profile directory or None for the default profile
+pub async fn find_chrome_profile(profile: Option<&Path>) -> Result<PathBuf> {
+    let candidates = match profile {
+        Some(profile) => vec![profile.to_path_buf()],
+        None => chrome_roots()
+            .into_iter()
+            .map(|root| root.join("Default"))
+            .collect(),
+    };
+    for candidate in candidates {
+        for database in [
+            Path::new("Network").join("Cookies"),
+            PathBuf::from("Cookies"),
+        ] {
+            if fs::metadata(candidate.join(database)).await.is_ok() {
+                return Ok(candidate);
+            }
+        }
+    }
+    Err(browser_cookies_error(match profile {
+        Some(profile) => format!("no Chrome cookies found in {:?}", profile),
+        None => "no Chrome or Chromium profile found".to_string(),
+    }))
+}
+
+/// The user data directories of Chrome and Chromium
+fn chrome_roots() -> Vec<PathBuf> {
+    let home = std::env::var_os("HOME").map(PathBuf::from);
+    let mut roots = Vec::new();
+    if cfg!(windows) {
+        if let Some(local_app_data) = std::env::var_os("LOCALAPPDATA") {
+            let local_app_data = Path::new(&local_app_data);
+            roots.push(local_app_data.join("Google/Chrome/User Data"));
+            roots.push(local_app_data.join("Chromium/User Data"));
+        }
+    } else if let Some(home) = home {
+        if cfg!(target_os = "macos") {
+            roots.push(home.join("Library/Application Support/Google/Chrome"));
+            roots.push(home.join("Library/Application Support/Chromium"));
+        } else {
+            roots.push(home.join(".config/google-chrome"));
+            roots.push(home.join(".config/chromium"));
+            roots.push(home.join("snap/chromium/common/chromium"));
+        }
+    }
+    roots
+}
+
+/// The keys Chrome encrypts cookie values with
+struct ChromeKeys {
+    /// AES-128-CBC key of values prefixed with "v10" (Linux and macOS)
+    v10: Option<[u8; 16]>,
+    /// AES-128-CBC key of values prefixed with "v11" (Linux, derived from the keyring password)
+    v11: Option<[u8; 16]>,
+    /// AES-256-GCM key of values prefixed with "v10" (Windows)
+    gcm: Option<[u8; 32]>,
+}
+
+impl ChromeKeys {
+    async fn load(profile: &Path) -> ChromeKeys {
+        // The keyring entries are named after the browser
+        let profile_name = profile.to_string_lossy().to_lowercase();
+        let browser = if profile_name.contains("chromium") {
+            "Chromium"
+        } else {
+            "Chrome"
+        };
+
+        if cfg!(windows) {
+            let local_state = match profile.parent() {
+                Some(user_data) => fs::read_to_string(user_data.join("Local State")).await.ok(),
+                None => None,
+            };
+            let gcm = match local_state.as_deref().and_then(windows_encrypted_key) {
+                Some(encrypted_key) => run_blocking(move || dpapi_decrypt(&encrypted_key))
+                    .await
+                    .and_then(|key| key.try_into().ok()),
+                None => None,
+            };
+            return ChromeKeys {
+                v10: None,
+                v11: None,
+                gcm,
+            };
+        }
+
+        if cfg!(target_os = "macos") {
+            let password = run_blocking(move || {
+                command_output(
+                    "security",
+                    &[
+                        "find-generic-password",
+                        "-w",
+                        "-s",
+                        &format!("{} Safe Storage", browser),
+                    ],
+                )
+            })
+            .await;
+            return ChromeKeys {
+                v10: password.map(|password| pbkdf2_sha1(password.as_bytes(), 1003)),
+                v11: None,
+                gcm: None,
+            };
+        }
+
+        let application = browser.to_lowercase();
+        let password = run_blocking(move || {
+            command_output("secret-tool", &["lookup", "application", &application]).or_else(|| {
+                command_output(
+                    "kwallet-query",
+                    &[
+                        "--read-password",
+                        &format!("{} Safe Storage", browser),
+                        "--folder",
+                        &format!("{} Keys", browser),
+                        "kdewallet",
+                    ],
+                )
+            })
+        })
+        .await;
+        ChromeKeys {
+            // Used without keyring
+            v10: Some(pbkdf2_sha1(b"peanuts", 1)),
+            v11: password.map(|password| pbkdf2_sha1(password.as_bytes(), 1)),
+            gcm: None,
+        }
+    }
+
+    fn decrypt(&self, encrypted: &[u8]) -> Option<Vec<u8>> {
+        let (prefix, data) = encrypted.split_at_checked(3)?;
+        match prefix {
+            b"v10" => match (&self.gcm, &self.v10) {
+                (Some(key), _) => aes_256_gcm_decrypt(key, data),
+                (None, Some(key)) => aes_128_cbc_decrypt(key, data),
+                (None, None) => None,
+            },
+            b"v11" => aes_128_cbc_decrypt(self.v11.as_ref()?, data),
+            _ => None,
+        }
+    }
+}
+
+/// The DPAPI encrypted cookie key of `Local State` (Windows)
+fn windows_encrypted_key(local_state: &str) -> Option<Vec<u8>> {
+    let local_state: serde_json::Value = serde_json::from_str(local_state).ok()?;
+    let key = local_state.pointer("/os_crypt/encrypted_key")?.as_str()?;
+    let key = BASE64_STANDARD.decode(key).ok()?;
+    key.strip_prefix(b"DPAPI").map(<[u8]>::to_vec)
+}
+
+/// Decrypts data with DPAPI for the current user, through PowerShell
+fn dpapi_decrypt(data: &[u8]) -> Option<Vec<u8>> {
+    let script = format!(
+        "Add-Type -AssemblyName System.Security; [Convert]::ToBase64String([Security.Cryptography.ProtectedData]::Unprotect([Convert]::FromBase64String('{}'), $null, 'CurrentUser'))",
+        BASE64_STANDARD.encode(data)
+    );
+    let output = command_output(
+        "powershell",
+        &["-NoProfile", "-NonInteractive", "-Command", &script],
+    )?;
+    BASE64_STANDARD.decode(output).ok()
+}
+
+/// Runs a command, returns its trimmed standard output if it succeeded
+fn command_output(program: &str, args: &[&str]) -> Option<String> {
+    let output = std::process::Command::new(program)
+        .args(args)
+        .output()
+        .ok()?;
+    let stdout = String::from_utf8(output.stdout).ok()?;
+    let stdout = stdout.trim_end_matches(['\r', '\n']);
+    (output.status.success() && !stdout.is_empty()).then(|| stdout.to_string())
+}
+
+async fn run_blocking<T: Send + 'static>(
+    f: impl FnOnce() -> Option<T> + Send + 'static,
+) -> Option<T> {
+    tokio::task::spawn_blocking(f).await.ok().flatten()
+}
+
+/// Derives the AES-128 key of Chrome from a password (PBKDF2-HMAC-SHA1, salt "saltysalt")
+fn pbkdf2_sha1(password: &[u8], iterations: usize) -> [u8; 16] {
+    let mut block = hmac_sha1(password, b"saltysalt\0\0\0\x01");
+    let mut key = block;
+    for _ in 1..iterations {
+        block = hmac_sha1(password, &block);
+        key.iter_mut()
+            .zip(block)
+            .for_each(|(key, byte)| *key ^= byte);
+    }
+    let mut aes_key = [0; 16];
+    aes_key.copy_from_slice(&key[..16]);
+    aes_key
+}
+
+fn hmac_sha1(key: &[u8], message: &[u8]) -> [u8; 20] {
+    let mut padded_key = [0u8; 64];
+    if key.len() > 64 {
+        padded_key[..20].copy_from_slice(&Sha1::digest(key));
+    } else {
+        padded_key[..key.len()].copy_from_slice(key);
+    }
+    let mut inner = Sha1::new();
+    inner.update(padded_key.map(|byte| byte ^ 0x36));
+    inner.update(message);
+    let mut outer = Sha1::new();
+    outer.update(padded_key.map(|byte| byte ^ 0x5c));
+    outer.update(inner.finalize());
+    outer.finalize().into()
+}
+
+/// Decrypts AES-128-CBC with the IV of Chrome (16 spaces) and PKCS#7 padding
+fn aes_128_cbc_decrypt(key: &[u8; 16], data: &[u8]) -> Option<Vec<u8>> {
+    if data.is_empty() || !data.len().is_multiple_of(16) {
+        return None;
+    }
+    let cipher = Aes128::new(key.into());
+    let mut previous = [b' '; 16];
+    let mut plain = Vec::with_capacity(data.len());
+    for chunk in data.chunks_exact(16) {
+        let mut block = aes::Block::try_from(chunk).ok()?;
+        cipher.decrypt_block(&mut block);
+        plain.extend(block.iter().zip(previous).map(|(byte, iv)| byte ^ iv));
+        previous.copy_from_slice(chunk);
+    }
+    let padding = usize::from(*plain.last()?);
+    if padding == 0
+        || padding > 16
+        || plain[plain.len() - padding..]
+            .iter()
+            .any(|&byte| usize::from(byte) != padding)
+    {
+        return None;
+    }
+    plain.truncate(plain.len() - padding);
+    Some(plain)
+}
+
+/// Decrypts AES-256-GCM data laid out as 12 byte nonce, ciphertext and 16 byte tag
+fn aes_256_gcm_decrypt(key: &[u8; 32], data: &[u8]) -> Option<Vec<u8>> {
+    if data.len() < 12 + 16 {
+        return None;
+    }
+    let (nonce, rest) = data.split_at(12);
+    let (ciphertext, tag) = rest.split_at(rest.len() - 16);
+    let cipher = Aes256::new(key.into());
+    let encrypt = |block: [u8; 16]| {
+        let mut block = aes::Block::from(block);
+        cipher.encrypt_block(&mut block);
+        u128::from_be_bytes(block.into())
+    };
+
+    let h = encrypt([0; 16]);
+    let mut counter = [0u8; 16];
+    counter[..12].copy_from_slice(nonce);
+    counter[15] = 1;
+    let tag_mask = encrypt(counter);
+
+    // GHASH of the ciphertext (there is no additional data) and the lengths
+    let mut hash = 0u128;
+    for chunk in ciphertext.chunks(16) {
+        let mut block = [0u8; 16];
+        block[..chunk.len()].copy_from_slice(chunk);
+        hash = gf_multiply(hash ^ u128::from_be_bytes(block), h);
+    }
+    hash = gf_multiply(hash ^ (ciphertext.len() as u128 * 8), h);
+    if (hash ^ tag_mask).to_be_bytes() != tag {
+        return None;
+    }
+
+    let mut plain = Vec::with_capacity(ciphertext.len());
+    for (index, chunk) in ciphertext.chunks(16).enumerate() {
+        let mut block = counter;
+        block[12..].copy_from_slice(&(index as u32 + 2).to_be_bytes());
+        let stream = encrypt(block).to_be_bytes();
+        plain.extend(chunk.iter().zip(stream).map(|(byte, key)| byte ^ key));
+    }
+    Some(plain)
+}
+
+/// Multiplication in GF(2^128) as defined for GCM
+fn gf_multiply(x: u128, y: u128) -> u128 {
+    let mut product = 0;
+    let mut v = y;
+    for bit in 0..128 {
+        if x & (1 << (127 - bit)) != 0 {
+            product ^= v;
+        }
+        v = if v & 1 != 0 {
+            (v >> 1) ^ (0xE1 << 120)
+        } else {
+            v >> 1
+        };
+    }
+    product
+}
+
+fn text<'a>(row: &'a Row, column: &str) -> Option<&'a str> {
+    match row.get(column) {
+        Some(Value::Text(value)) => Some(value),
+        _ => None,
+    }
+}
+
+fn integer(row: &Row, column: &str) -> Option<i64> {
+    match row.get(column) {
+        Some(Value::Integer(value)) => Some(*value),
+        Some(Value::Real(value)) => Some(*value as i64),
+        _ => None,
+    }
+}
+
+/// The current time in seconds since the Unix epoch
+fn unix_time() -> f64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs_f64())
+        .unwrap_or(0.0)
+}
@@ -0,0 +1,161 @@
+//! Client certificates (mutual TLS) for the launched browser
+//! Chromium on Linux reads client certificates from the NSS database in `$HOME/.pki/nssdb`,
+//! so a PKCS#12 file is imported into a database of a dedicated home directory, which the
+//! browser is launched with. The profile is told to select the certificate without asking,
+//! as headless browsers can't show the selection dialog.
+//! On macOS and Windows the browser uses the certificates of the system store instead.
+//! Uses `certutil` and `pk12util` of the NSS tools for the import.
+
+use std::fmt;
+use std::path::Path;
+use std::process::Command;
+
+use serde_json::json;
+
+use crate::Result;
+
+/// Error for when setting up a client certificate
+#[derive(Debug, Clone)]
+struct ClientCertError {
+    error_message: String,
+}
+impl fmt::Display for ClientCertError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Error setting up client certificate: {}",
+            self.error_message
+        )
+    }
+}
+impl std::error::Error for ClientCertError {
+    fn description(&self) -> &str {
+        &self.error_message
+    }
+}
+
+fn client_cert_error(message: impl Into<String>) -> Box<dyn std::error::Error> {
+    Box::new(ClientCertError {
+        error_message: message.into(),
+    })
+}
+
+/// Network errors of a navigation caused by client certificates
+const CERT_NEEDED_ERROR: &str = "ERR_SSL_CLIENT_AUTH_CERT_NEEDED";
+const CERT_REJECTED_ERRORS: [&str; 2] = [
+    "ERR_BAD_SSL_CLIENT_AUTH_CERT",
+    "ERR_SSL_CLIENT_AUTH_SIGNATURE_FAILED",
+];
+
+/// Explains a navigation error caused by a missing or rejected client certificate
+///
+/// # Arguments
+/// * `error` - The message of the navigation error
+/// * `configured` - Whether a client certificate was configured
+///
+/// # Returns
+/// The explanation, None if the error is not about client certificates
+pub fn explain_navigation_error(error: &str, configured: bool) -> Option<&'static str> {
+    if error.contains(CERT_NEEDED_ERROR) {
+        Some(if configured {
+            "the site requires a client certificate, but the configured one was not offered (see --client-cert)"
+        } else {
+            "the site requires a client certificate, give one with --client-cert"
+        })
+    } else if CERT_REJECTED_ERRORS.iter().any(|name| error.contains(name)) {
+        Some("the site rejected the client certificate")
+    } else {
+        None
+    }
+}
+
+/// Imports a PKCS#12 file into the NSS database of a new home directory for the browser
+///
+/// # Arguments
+/// * `certificate` - The PKCS#12 file (.p12 or .pfx) with the certificate and its private key
+/// * `password` - The password of the file, may be empty
+/// * `home` - The new home directory, contains the private key until removed
+///
+/// # Returns
+/// A `Result` containing an empty `()` value or an error, e.g. if the NSS tools are not installed.
+pub fn import_certificate(certificate: &Path, password: &str, home: &Path) -> Result<()> {
+    if !cfg!(target_os = "linux") {
+        return Err(client_cert_error(
+            "only supported on Linux, import the certificate into the certificate store of the system instead",
+        ));
+    }
+    let database = home.join(".pki").join("nssdb");
+    std::fs::create_dir_all(&database)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(home, std::fs::Permissions::from_mode(0o700))?;
+    }
+    let database = format!("sql:{}", database.display());
+
+    let mut create = Command::new("certutil");
+    create.args(["-N", "--empty-password", "-d", &database]);
+    run(create, "certutil")?;
+    // Passed in a file, as arguments are visible to other users
+    let password_file = home.join("password");
+    std::fs::write(&password_file, password)?;
+    let mut import = Command::new("pk12util");
+    import
+        .arg("-i")
+        .arg(certificate)
+        .args(["-d", &database, "-w"])
+        .arg(&password_file);
+    let imported = run(import, "pk12util");
+    std::fs::remove_file(&password_file)?;
+    imported?;
+
+    tracing::debug!("Web2Pdf imported client certificate {:?}", certificate);
+    Ok(())
+}
+
+/// Lets a browser profile select a client certificate without asking, for all sites
+/// The setting is merged into the preferences of the profile.
+///
+/// # Arguments
+/// * `user_data_dir` - The user data directory of the browser
+///
+/// # Returns
+/// A `Result` containing an empty `()` value or an error.
+pub fn auto_select_certificate(user_data_dir: &Path) -> Result<()> {
+    let path = user_data_dir.join("Default").join("Preferences");
+    let mut preferences: serde_json::Value = match std::fs::read_to_string(&path) {
+        Ok(contents) => serde_json::from_str(&contents)?,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => json!({}),
+        Err(e) => return Err(e.into()),
+    };
+    // The same setting as the AutoSelectCertificateForUrls policy, an empty filter matches any certificate
+    preferences["profile"]["content_settings"]["exceptions"]["auto_select_certificate"] = json!({
+        "*,*": { "setting": { "filters": [{}] } }
+    });
+    std::fs::create_dir_all(path.parent().expect("the preferences are in a profile"))?;
+    std::fs::write(&path, serde_json::to_string(&preferences)?)?;
+    Ok(())
+}
+
+/// Runs a command, the error contains the last line of its output
+fn run(mut command: Command, name: &str) -> Result<()> {
+    let output = command.output().map_err(|e| {
+        client_cert_error(format!(
+            "failed to run {} (install the NSS tools): {}",
+            name, e
+        ))
+    })?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let reason = stderr
+            .trim()
+            .lines()
+            .last()
+            .or_else(|| stdout.trim().lines().last())
+            .unwrap_or("no output");
+        Err(client_cert_error(format!("{} failed: {}", name, reason)))
+    }
+}
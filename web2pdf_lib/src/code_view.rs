@@ -0,0 +1,542 @@
+use std::fmt::Write;
+use std::sync::OnceLock;
+
+use syntect::easy::HighlightLines;
+use syntect::highlighting::{Style, Theme, ThemeSet};
+use syntect::html::{styled_line_to_highlighted_html, IncludeBackground};
+use syntect::parsing::{SyntaxReference, SyntaxSet};
+use syntect::util::LinesWithEndings;
+use url::Url;
+
+use crate::util;
+
+/// Color theme of rendered source code
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CodeTheme {
+    #[default]
+    Light,
+    Dark,
+}
+
+impl CodeTheme {
+    /// The syntax highlighting theme matching the color theme
+    fn syntax_theme(self) -> &'static Theme {
+        static THEMES: OnceLock<ThemeSet> = OnceLock::new();
+        let themes = THEMES.get_or_init(ThemeSet::load_defaults);
+        match self {
+            CodeTheme::Light => &themes.themes["InspiredGitHub"],
+            CodeTheme::Dark => &themes.themes["base16-ocean.dark"],
+        }
+    }
+}
+
+/// Sources larger than this are rendered without syntax highlighting
+const MAX_HIGHLIGHT_SIZE: usize = 1024 * 1024;
+
+/// A render-optimized view of a code hosting URL (GitHub, GitLab)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CodeView {
+    /// A source file, rendered from its raw contents with line numbers
+    Source { raw_url: String, file_name: String },
+    /// A rendered document (e.g. a README), shown without the surrounding web UI
+    Document {
+        url: String,
+        /// CSS selector of the rendered document
+        selector: &'static str,
+    },
+    /// A pull/merge request or commit, rendered from its unified diff
+    Diff { diff_url: String, title: String },
+}
+
+const GITHUB_DOCUMENT: &str = "article.markdown-body";
+const GITLAB_DOCUMENT: &str = ".blob-viewer[data-type='rich'] .md, .file-content.md";
+
+/// File extensions of documents that are rendered by GitHub and GitLab
+const DOCUMENT_EXTENSIONS: [&str; 7] = ["md", "markdown", "mdown", "mkd", "rst", "adoc", "org"];
+
+impl CodeView {
+    /// A source listing of any URL, e.g. a raw file or a local source file
+    ///
+    /// # Arguments
+    /// * `url` - The URL of the file, its contents are taken as plain text
+    pub fn raw(url: &str) -> CodeView {
+        let file_name = util::url_file_name(url).unwrap_or_else(|| url.to_string());
+        CodeView::Source {
+            raw_url: url.to_string(),
+            file_name,
+        }
+    }
+
+    /// Finds a render-optimized view of a GitHub or GitLab URL
+    ///
+    /// # Arguments
+    /// * `url` - The URL of a file, repository, pull/merge request or commit
+    ///
+    /// # Returns
+    /// The view or None if the URL is not supported
+    pub fn resolve(url: &str) -> Option<CodeView> {
+        let url = Url::parse(url).ok()?;
+        let host = url.host_str()?.to_lowercase();
+        let origin = url.origin().ascii_serialization();
+        let segments: Vec<&str> = url.path_segments()?.filter(|s| !s.is_empty()).collect();
+
+        if host == "github.com" || host == "www.github.com" {
+            let [owner, repo, rest @ ..] = segments.as_slice() else {
+                return None;
+            };
+            let project = format!("{}/{}", owner, repo);
+            return match rest {
+                [] | ["tree", ..] => Some(CodeView::Document {
+                    url: url.to_string(),
+                    selector: GITHUB_DOCUMENT,
+                }),
+                ["blob", reference, path @ ..] if !path.is_empty() => Some(file_view(
+                    &url,
+                    format!(
+                        "{}/{}/raw/{}/{}",
+                        origin,
+                        project,
+                        reference,
+                        path.join("/")
+                    ),
+                    path,
+                    GITHUB_DOCUMENT,
+                )),
+                ["pull", number, ..] if number.chars().all(|c| c.is_ascii_digit()) => {
+                    Some(CodeView::Diff {
+                        diff_url: format!("{}/{}/pull/{}.diff", origin, project, number),
+                        title: format!("{}#{}", project, number),
+                    })
+                }
+                ["commit", sha, ..] => Some(commit_view(&origin, &project, "commit", sha)),
+                _ => None,
+            };
+        }
+
+        if host == "gitlab.com" || host.starts_with("gitlab.") {
+            let separator = segments.iter().position(|segment| *segment == "-")?;
+            let project = segments[..separator].join("/");
+            return match &segments[separator + 1..] {
+                ["blob", reference, path @ ..] if !path.is_empty() => Some(file_view(
+                    &url,
+                    format!(
+                        "{}/{}/-/raw/{}/{}",
+                        origin,
+                        project,
+                        reference,
+                        path.join("/")
+                    ),
+                    path,
+                    GITLAB_DOCUMENT,
+                )),
+                ["merge_requests", number, ..] if number.chars().all(|c| c.is_ascii_digit()) => {
+                    Some(CodeView::Diff {
+                        diff_url: format!(
+                            "{}/{}/-/merge_requests/{}.diff",
+                            origin, project, number
+                        ),
+                        title: format!("{}!{}", project, number),
+                    })
+                }
+                ["commit", sha, ..] => Some(commit_view(&origin, &project, "-/commit", sha)),
+                _ => None,
+            };
+        }
+
+        None
+    }
+}
+
+fn file_view(url: &Url, raw_url: String, path: &[&str], selector: &'static str) -> CodeView {
+    let file_name = path.join("/");
+    let is_document = file_name.rsplit_once('.').is_some_and(|(_, extension)| {
+        DOCUMENT_EXTENSIONS.contains(&extension.to_lowercase().as_str())
+    });
+    // `?plain=1` asks GitHub for the source of a document instead of the rendered view
+    let plain = url
+        .query_pairs()
+        .any(|(key, value)| key == "plain" && value == "1");
+    if is_document && !plain {
+        CodeView::Document {
+            url: url.to_string(),
+            selector,
+        }
+    } else {
+        CodeView::Source { raw_url, file_name }
+    }
+}
+
+fn commit_view(origin: &str, project: &str, route: &str, sha: &str) -> CodeView {
+    CodeView::Diff {
+        diff_url: format!("{}/{}/{}/{}.diff", origin, project, route, sha),
+        title: format!("{}@{}", project, &sha[..sha.len().min(7)]),
+    }
+}
+
+/// Escapes text for use in HTML
+pub fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn stylesheet(theme: CodeTheme) -> &'static str {
+    match theme {
+        CodeTheme::Light => {
+            ":root { --fg: #1f2328; --bg: #ffffff; --muted: #6e7781; --border: #d0d7de; --header: #f6f8fa; \
+             --added: #dafbe1; --removed: #ffebe9; --hunk: #ddf4ff; }"
+        }
+        CodeTheme::Dark => {
+            ":root { --fg: #e6edf3; --bg: #0d1117; --muted: #7d8590; --border: #30363d; --header: #161b22; \
+             --added: #033a16; --removed: #490202; --hunk: #0c2d6b; }"
+        }
+    }
+}
+
+const BASE_CSS: &str = "
+* { box-sizing: border-box; }
+html, body { margin: 0; background: var(--bg); color: var(--fg);
+  -webkit-print-color-adjust: exact; print-color-adjust: exact; }
+body { font: 12px/1.45 ui-monospace, SFMono-Regular, 'SF Mono', Menlo, Consolas, 'Liberation Mono', monospace; }
+h1 { font-size: 14px; margin: 0; padding: 8px 12px; background: var(--header);
+  border: 1px solid var(--border); border-bottom: none; border-radius: 6px 6px 0 0; }
+h2 { font-size: 13px; margin: 16px 0 0; padding: 6px 12px; background: var(--header);
+  border: 1px solid var(--border); border-bottom: none; }
+table { width: 100%; border-collapse: collapse; border: 1px solid var(--border); table-layout: fixed; }
+td { padding: 0 8px; vertical-align: top; }
+td.ln { width: 5em; text-align: right; color: var(--muted); user-select: none; border-right: 1px solid var(--border); }
+td.code { white-space: pre-wrap; overflow-wrap: anywhere; }
+tr { break-inside: avoid; }
+tr.added { background: var(--added); }
+tr.removed { background: var(--removed); }
+tr.hunk { background: var(--hunk); color: var(--muted); }
+";
+
+fn page(title: &str, theme: CodeTheme, body: &str) -> String {
+    format!(
+        "<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\"><title>{}</title><style>{}{}</style></head><body>{}</body></html>",
+        escape_html(title),
+        stylesheet(theme),
+        BASE_CSS,
+        body
+    )
+}
+
+fn syntax_set() -> &'static SyntaxSet {
+    static SYNTAXES: OnceLock<SyntaxSet> = OnceLock::new();
+    SYNTAXES.get_or_init(SyntaxSet::load_defaults_newlines)
+}
+
+/// Finds the syntax of a source file by its name (extension) or its first line (e.g. a shebang)
+fn find_syntax(file_name: &str, source: &str) -> Option<&'static SyntaxReference> {
+    let syntaxes = syntax_set();
+    let extension = file_name
+        .rsplit_once('.')
+        .map_or("", |(_, extension)| extension);
+    let base_name = file_name.rsplit('/').next().unwrap_or(file_name);
+    syntaxes
+        .find_syntax_by_extension(base_name)
+        .or_else(|| syntaxes.find_syntax_by_extension(extension))
+        .or_else(|| syntaxes.find_syntax_by_first_line(source.lines().next()?))
+}
+
+/// Renders a source file as HTML with a file name header, line numbers and syntax highlighting
+/// The language is detected from the file name or the first line, other files are shown as plain text.
+///
+/// # Arguments
+/// * `file_name` - The name shown in the header
+/// * `source` - The contents of the file
+/// * `theme` - The color theme
+pub fn render_source(file_name: &str, source: &str, theme: CodeTheme) -> String {
+    render_listing(file_name, source, find_syntax(file_name, source), theme)
+}
+
+/// Renders structured data as HTML source listing, pretty-printed and syntax highlighted
+///
+/// # Arguments
+/// * `title` - The title shown in the header, e.g. the URL
+/// * `data` - The data
+/// * `format` - The format of the data
+/// * `theme` - The color theme
+pub fn render_data(title: &str, data: &str, format: DataFormat, theme: CodeTheme) -> String {
+    let (source, extension) = match format {
+        DataFormat::Json => (pretty_json(data), "json"),
+        // XML is pretty-printed from the DOM, see `PRETTY_DATA_JS`
+        DataFormat::Xml => (data.to_string(), "xml"),
+    };
+    render_listing(
+        title,
+        &source,
+        syntax_set().find_syntax_by_extension(extension),
+        theme,
+    )
+}
+
+fn render_listing(
+    title: &str,
+    source: &str,
+    syntax: Option<&'static SyntaxReference>,
+    theme: CodeTheme,
+) -> String {
+    let mut highlighter = syntax
+        .filter(|_| source.len() <= MAX_HIGHLIGHT_SIZE)
+        .map(|syntax| HighlightLines::new(syntax, theme.syntax_theme()));
+
+    let mut body = format!("<h1>{}</h1><table>", escape_html(title));
+    for (number, line) in LinesWithEndings::from(source).enumerate() {
+        let highlighted = highlighter.as_mut().and_then(|highlighter| {
+            let regions = highlighter.highlight_line(line, syntax_set()).ok()?;
+            let regions: Vec<(Style, &str)> = regions
+                .into_iter()
+                .map(|(style, text)| (style, text.trim_end_matches(['\r', '\n'])))
+                .collect();
+            styled_line_to_highlighted_html(&regions, IncludeBackground::No).ok()
+        });
+        let _ = write!(
+            body,
+            "<tr><td class=\"ln\">{}</td><td class=\"code\">{}</td></tr>",
+            number + 1,
+            highlighted.unwrap_or_else(|| escape_html(line.trim_end_matches(['\r', '\n'])))
+        );
+    }
+    body.push_str("</table>");
+    page(title, theme, &body)
+}
+
+/// Formats of structured data that can be pretty-printed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DataFormat {
+    Json,
+    Xml,
+}
+
+/// Pretty-prints JSON with an indentation of two spaces
+/// Only whitespace is changed, so key order and number precision are kept (even for invalid JSON).
+pub fn pretty_json(json: &str) -> String {
+    let mut pretty = String::with_capacity(json.len() * 2);
+    let mut depth = 0usize;
+    let mut in_string = false;
+    let mut escaped = false;
+    let mut chars = json.trim_start_matches('\u{feff}').chars().peekable();
+    let newline = |pretty: &mut String, depth: usize| {
+        pretty.push('\n');
+        pretty.push_str(&"  ".repeat(depth));
+    };
+
+    while let Some(c) = chars.next() {
+        if in_string {
+            pretty.push(c);
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match c {
+            '"' => {
+                in_string = true;
+                pretty.push(c);
+            }
+            '{' | '[' => {
+                pretty.push(c);
+                while chars.peek().is_some_and(|c| c.is_whitespace()) {
+                    chars.next();
+                }
+                // Keep empty objects and arrays on one line
+                if matches!(chars.peek(), Some('}' | ']')) {
+                    pretty.push(chars.next().unwrap());
+                } else {
+                    depth += 1;
+                    newline(&mut pretty, depth);
+                }
+            }
+            '}' | ']' => {
+                depth = depth.saturating_sub(1);
+                newline(&mut pretty, depth);
+                pretty.push(c);
+            }
+            ',' => {
+                pretty.push(c);
+                newline(&mut pretty, depth);
+            }
+            ':' => pretty.push_str(": "),
+            c if c.is_whitespace() => {}
+            c => pretty.push(c),
+        }
+    }
+    pretty
+}
+
+/// Javascript detecting JSON and XML documents, returns the format and the data
+/// XML is pretty-printed from the parsed document, as the browser may replace it with its viewer.
+pub const PRETTY_DATA_JS: &str = r#"(() => {
+    const type = (document.contentType || '').toLowerCase();
+    if (/^(application|text)\/([\w.-]+\+)?json$/.test(type)) {
+        const pre = document.querySelector('body > pre');
+        return ['json', pre ? pre.textContent : document.body.innerText];
+    }
+    if (!/^(application|text)\/([\w.-]+\+)?xml$/.test(type) || type === 'application/xhtml+xml') {
+        return [null, ''];
+    }
+
+    const text = value => value.replace(/&/g, '&amp;').replace(/</g, '&lt;').replace(/>/g, '&gt;');
+    const attribute = value => text(value).replace(/"/g, '&quot;');
+    const lines = [`<?xml version="${document.xmlVersion || '1.0'}" encoding="UTF-8"?>`];
+    const visit = (node, depth) => {
+        const indent = '  '.repeat(depth);
+        switch (node.nodeType) {
+            case Node.ELEMENT_NODE: {
+                const attributes = Array.from(node.attributes)
+                    .map(a => ` ${a.name}="${attribute(a.value)}"`).join('');
+                const children = Array.from(node.childNodes)
+                    .filter(child => child.nodeType !== Node.TEXT_NODE || child.nodeValue.trim());
+                if (children.length === 0) {
+                    lines.push(`${indent}<${node.nodeName}${attributes}/>`);
+                } else if (children.length === 1 && children[0].nodeType === Node.TEXT_NODE) {
+                    lines.push(`${indent}<${node.nodeName}${attributes}>${text(children[0].nodeValue.trim())}</${node.nodeName}>`);
+                } else {
+                    lines.push(`${indent}<${node.nodeName}${attributes}>`);
+                    children.forEach(child => visit(child, depth + 1));
+                    lines.push(`${indent}</${node.nodeName}>`);
+                }
+                break;
+            }
+            case Node.TEXT_NODE:
+                lines.push(indent + text(node.nodeValue.trim()));
+                break;
+            case Node.CDATA_SECTION_NODE:
+                lines.push(`${indent}<![CDATA[${node.nodeValue}]]>`);
+                break;
+            case Node.COMMENT_NODE:
+                lines.push(`${indent}<!--${node.nodeValue}-->`);
+                break;
+            case Node.PROCESSING_INSTRUCTION_NODE:
+                lines.push(`${indent}<?${node.target} ${node.data}?>`);
+                break;
+            case Node.DOCUMENT_TYPE_NODE:
+                lines.push(new XMLSerializer().serializeToString(node));
+                break;
+        }
+    };
+    // Older versions of the XML viewer keep the source in a hidden element
+    const source = document.getElementById('webkit-xml-viewer-source-xml');
+    Array.from((source || document).childNodes).forEach(node => visit(node, 0));
+    return ['xml', lines.join('\n')];
+})()"#;
+
+/// Renders a unified diff as HTML, with the old and new line numbers of every line
+///
+/// # Arguments
+/// * `title` - The title shown in the header
+/// * `diff` - The unified diff (e.g. from `git diff`)
+/// * `theme` - The color theme
+pub fn render_diff(title: &str, diff: &str, theme: CodeTheme) -> String {
+    let mut body = format!("<h1>{}</h1>", escape_html(title));
+    let mut in_table = false;
+    let (mut old, mut new) = (0usize, 0usize);
+    let mut in_hunk = false;
+
+    for line in diff.lines() {
+        if let Some(files) = line.strip_prefix("diff --git ") {
+            if in_table {
+                body.push_str("</table>");
+            }
+            // "a/path b/path", show the new path
+            let file = files
+                .rsplit_once(" b/")
+                .map_or(files, |(_, new_path)| new_path);
+            let _ = write!(body, "<h2>{}</h2><table>", escape_html(file));
+            in_table = true;
+            in_hunk = false;
+            continue;
+        }
+        if !in_table {
+            // Commit message and headers before the first file
+            let _ = write!(body, "<div class=\"code\">{}</div>", escape_html(line));
+            continue;
+        }
+
+        if let Some(hunk) = line.strip_prefix("@@ ") {
+            // "-old,count +new,count @@ context"
+            let mut ranges = hunk.split_whitespace();
+            let start = |range: Option<&str>| {
+                range
+                    .and_then(|range| range[1..].split(',').next()?.parse::<usize>().ok())
+                    .unwrap_or(1)
+            };
+            old = start(ranges.next());
+            new = start(ranges.next());
+            in_hunk = true;
+            let _ = write!(
+                body,
+                "<tr class=\"hunk\"><td class=\"ln\"></td><td class=\"ln\"></td><td class=\"code\">{}</td></tr>",
+                escape_html(line)
+            );
+            continue;
+        }
+        if !in_hunk {
+            // File headers (index, ---, +++, mode changes)
+            continue;
+        }
+
+        let (class, old_number, new_number) = match line.chars().next() {
+            Some('+') => {
+                new += 1;
+                ("added", String::new(), (new - 1).to_string())
+            }
+            Some('-') => {
+                old += 1;
+                ("removed", (old - 1).to_string(), String::new())
+            }
+            Some('\\') => ("hunk", String::new(), String::new()),
+            _ => {
+                old += 1;
+                new += 1;
+                ("", (old - 1).to_string(), (new - 1).to_string())
+            }
+        };
+        let _ = write!(
+            body,
+            "<tr class=\"{}\"><td class=\"ln\">{}</td><td class=\"ln\">{}</td><td class=\"code\">{}</td></tr>",
+            class,
+            old_number,
+            new_number,
+            escape_html(line)
+        );
+    }
+    if in_table {
+        body.push_str("</table>");
+    }
+    page(title, theme, &body)
+}
+
+/// Javascript replacing the page with a single element, takes the selector and whether to use a dark theme
+pub const ISOLATE_ELEMENT_JS: &str = r#"((selector, dark) => {
+    const element = document.querySelector(selector);
+    if (!element) return false;
+    if (dark) {
+        document.documentElement.setAttribute('data-color-mode', 'dark');
+        document.documentElement.setAttribute('data-dark-theme', 'dark');
+    } else {
+        document.documentElement.setAttribute('data-color-mode', 'light');
+        document.documentElement.setAttribute('data-light-theme', 'light');
+    }
+    document.body.replaceChildren(element);
+    element.style.maxWidth = 'none';
+    element.style.margin = '0';
+    element.style.padding = '16px';
+    document.body.style.background = getComputedStyle(element).backgroundColor;
+    return true;
+})"#;
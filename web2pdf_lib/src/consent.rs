@@ -0,0 +1,118 @@
+/// Javascript dismissing cookie consent banners, returns the number of clicked or removed elements
+///
+/// The ruleset consists of three steps:
+/// 1. Click the reject (or accept) buttons of common consent managers
+/// 2. Click buttons with a reject (or accept) label inside elements that look like a consent banner
+/// 3. Remove the remaining consent manager containers and restore scrolling
+pub const DISMISS_COOKIE_BANNERS_JS: &str = r#"(() => {
+    const rejectButtons = [
+        '#onetrust-reject-all-handler',
+        '#CybotCookiebotDialogBodyButtonDecline',
+        '#CybotCookiebotDialogBodyLevelButtonLevelOptinDeclineAll',
+        '#didomi-notice-disagree-button',
+        '.qc-cmp2-summary-buttons button[mode="secondary"]',
+        '[data-testid="uc-deny-all-button"]',
+        '.cmplz-deny',
+        '.cky-btn-reject',
+        '.osano-cm-denyAll',
+        'a[data-cookie-refuse]',
+        '.cm-btn-decline',
+        '#cn-refuse-cookie',
+        '.fc-cta-do-not-consent',
+    ];
+    const acceptButtons = [
+        '#onetrust-accept-btn-handler',
+        '#CybotCookiebotDialogBodyLevelButtonLevelOptinAllowAll',
+        '#didomi-notice-agree-button',
+        '.qc-cmp2-summary-buttons button[mode="primary"]',
+        '[data-testid="uc-accept-all-button"]',
+        '.cmplz-accept',
+        '.cky-btn-accept',
+        '.osano-cm-accept-all',
+        '#truste-consent-button',
+        '.cc-dismiss',
+        '#cookie_action_close_header',
+        '#cn-accept-cookie',
+        '.fc-cta-consent',
+    ];
+    const containers = [
+        '#onetrust-consent-sdk',
+        '#CybotCookiebotDialog',
+        '#usercentrics-root',
+        '#didomi-host',
+        '.qc-cmp2-container',
+        '#truste-consent-track',
+        '.cky-consent-container',
+        '.osano-cm-window',
+        '#cmplz-cookiebanner-container',
+        '.cc-window',
+        '#cookie-law-info-bar',
+        '#cookie-notice',
+        '.fc-consent-root',
+        '#BorlabsCookieBox',
+        '.klaro',
+    ];
+    const rejectLabel = /^(reject|decline|deny|refuse|ablehnen|alle ablehnen|nur notwendige|refuser|tout refuser|rechazar|rifiuta|weigeren|only (necessary|essential)|necessary only|essential only)/i;
+    const acceptLabel = /^(accept|agree|allow|got it|ok|akzeptieren|alle akzeptieren|zustimmen|accepter|tout accepter|aceptar|accetta|accepteren|i understand)/i;
+    const bannerHint = /cookie|consent|gdpr|privacy|cmp/i;
+
+    // Consent managers like Usercentrics render into a shadow root
+    const roots = [document];
+    for (const host of document.querySelectorAll('#usercentrics-root, [id*="consent" i]')) {
+        if (host.shadowRoot) {
+            roots.push(host.shadowRoot);
+        }
+    }
+    const isVisible = (element) => {
+        const rect = element.getBoundingClientRect();
+        return rect.width > 0 && rect.height > 0;
+    };
+    const clickFirst = (selectors) => {
+        for (const root of roots) {
+            for (const selector of selectors) {
+                const element = root.querySelector(selector);
+                if (element && isVisible(element)) {
+                    element.click();
+                    return true;
+                }
+            }
+        }
+        return false;
+    };
+    const clickByLabel = (label) => {
+        for (const root of roots) {
+            for (const element of root.querySelectorAll('button, a, [role="button"], input[type="button"], input[type="submit"]')) {
+                const text = (element.innerText || element.value || '').trim();
+                if (!text || text.length > 40 || !label.test(text) || !isVisible(element)) {
+                    continue;
+                }
+                for (let parent = element; parent; parent = parent.parentElement) {
+                    if (bannerHint.test(parent.id + ' ' + parent.className)) {
+                        element.click();
+                        return true;
+                    }
+                }
+            }
+        }
+        return false;
+    };
+
+    let actions = 0;
+    if (clickFirst(rejectButtons) || clickFirst(acceptButtons) || clickByLabel(rejectLabel) || clickByLabel(acceptLabel)) {
+        actions++;
+    }
+    for (const selector of containers) {
+        for (const element of document.querySelectorAll(selector)) {
+            element.remove();
+            actions++;
+        }
+    }
+    if (actions > 0) {
+        for (const element of [document.documentElement, document.body]) {
+            if (element && getComputedStyle(element).overflow === 'hidden') {
+                element.style.setProperty('overflow', 'visible', 'important');
+            }
+        }
+    }
+    return actions;
+})()"#;
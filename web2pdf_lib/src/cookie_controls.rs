@@ -0,0 +1,31 @@
+//! The `Network.setCookieControls` command of the DevTools protocol (Chrome 132+), which is newer
+//! than the protocol of chromiumoxide
+
+use chromiumoxide::types::{Command, Method, MethodId};
+use serde::{Deserialize, Serialize};
+
+/// Controls the access of third-party cookies of a page
+/// The page has to be loaded again to see the change.
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SetCookieControlsParams {
+    /// Block third-party cookies, like the setting of the browser
+    pub enable_third_party_cookie_restriction: bool,
+    /// Don't grant exceptions based on the metadata of the cookies (the grace period)
+    pub disable_third_party_cookie_metadata: bool,
+    /// Don't grant exceptions based on heuristics (e.g. a popup of the third party)
+    pub disable_third_party_cookie_heuristics: bool,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SetCookieControlsReturns {}
+
+impl Method for SetCookieControlsParams {
+    fn identifier(&self) -> MethodId {
+        "Network.setCookieControls".into()
+    }
+}
+
+impl Command for SetCookieControlsParams {
+    type Response = SetCookieControlsReturns;
+}
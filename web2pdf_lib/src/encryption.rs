@@ -0,0 +1,80 @@
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use lopdf::encryption::crypt_filters::{Aes256CryptFilter, CryptFilter};
+use lopdf::{Document, EncryptionState, EncryptionVersion, Permissions};
+
+use crate::pdf::save_to_vec;
+use crate::Result;
+
+/// Name of the crypt filter used for all strings and streams
+const CRYPT_FILTER: &[u8] = b"StdCF";
+
+/// Passwords and permissions of an encrypted PDF
+#[derive(Debug, Clone)]
+pub struct Encryption {
+    /// Password granting full access, a random one is used if not set
+    pub owner_password: Option<String>,
+    /// Password required to open the PDF, may be empty
+    pub user_password: String,
+    /// Whether printing is allowed without the owner password
+    pub allow_print: bool,
+    /// Whether copying text and graphics is allowed without the owner password
+    pub allow_copy: bool,
+}
+
+impl Encryption {
+    fn permissions(&self) -> Permissions {
+        let mut permissions = Permissions::all();
+        if !self.allow_print {
+            permissions.remove(Permissions::PRINTABLE | Permissions::PRINTABLE_IN_HIGH_QUALITY);
+        }
+        if !self.allow_copy {
+            // Assistive technology is still allowed to extract the text
+            permissions.remove(Permissions::COPYABLE);
+        }
+        permissions
+    }
+}
+
+/// Encrypts a PDF with the standard security handler (AES-256, PDF 2.0)
+///
+/// # Arguments
+/// * `pdf` - The PDF data
+/// * `encryption` - The passwords and permissions
+///
+/// # Returns
+/// * The encrypted PDF data
+pub fn encrypt(pdf: &[u8], encryption: &Encryption) -> Result<Vec<u8>> {
+    let mut document = Document::load_mem(pdf)?;
+
+    let owner_password = match &encryption.owner_password {
+        Some(password) => password.clone(),
+        None => random_password()?,
+    };
+    let mut file_encryption_key = [0u8; 32];
+    getrandom::fill(&mut file_encryption_key)?;
+
+    let crypt_filter: Arc<dyn CryptFilter> = Arc::new(Aes256CryptFilter);
+    let state = EncryptionState::try_from(EncryptionVersion::V5 {
+        encrypt_metadata: true,
+        crypt_filters: BTreeMap::from([(CRYPT_FILTER.to_vec(), crypt_filter)]),
+        file_encryption_key: &file_encryption_key,
+        stream_filter: CRYPT_FILTER.to_vec(),
+        string_filter: CRYPT_FILTER.to_vec(),
+        owner_password: &owner_password,
+        user_password: &encryption.user_password,
+        permissions: encryption.permissions(),
+    })?;
+    document.encrypt(&state)?;
+    document.version = String::from("2.0");
+
+    save_to_vec(&mut document)
+}
+
+/// A random password of 32 hexadecimal digits
+fn random_password() -> Result<String> {
+    let mut bytes = [0u8; 16];
+    getrandom::fill(&mut bytes)?;
+    Ok(bytes.iter().map(|byte| format!("{:02x}", byte)).collect())
+}
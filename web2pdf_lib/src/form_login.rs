@@ -0,0 +1,113 @@
+//! Logging in through a login form before converting, see `BrowserWeb2Pdf::web2pdf_form_login`
+//! The fields are typed like a user would, so forms validating key events accept the input.
+//! The cookies of the login stay in the browser and are used by all pages created afterwards.
+
+use std::fmt;
+use std::time::Duration;
+
+use chromiumoxide::Page;
+
+use crate::{PageWeb2Pdf, Result};
+
+/// How long the fields of the form may take to appear
+pub const FORM_TIMEOUT: Duration = Duration::from_secs(15);
+/// How long the login may take after submitting the form
+pub const SUBMIT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Error for when logging in
+#[derive(Debug, Clone)]
+struct FormLoginError {
+    error_message: String,
+}
+impl fmt::Display for FormLoginError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Error logging in: {}", self.error_message)
+    }
+}
+impl std::error::Error for FormLoginError {
+    fn description(&self) -> &str {
+        &self.error_message
+    }
+}
+
+fn form_login_error(message: impl Into<String>) -> Box<dyn std::error::Error> {
+    Box::new(FormLoginError {
+        error_message: message.into(),
+    })
+}
+
+/// A login form and the credentials to fill in
+#[derive(Debug, Clone)]
+pub struct FormLogin {
+    /// URL of the page with the login form
+    pub url: String,
+    /// CSS selector of the user name field
+    pub user_selector: String,
+    /// CSS selector of the password field
+    pub password_selector: String,
+    /// CSS selector of the submit button, the form is submitted with Enter if not set
+    pub submit_selector: Option<String>,
+    pub username: String,
+    pub password: String,
+}
+
+/// Fills in and submits the login form on a new page
+///
+/// # Arguments
+/// * `page` - The page to log in with, navigated to the login form
+/// * `login` - The form and credentials
+///
+/// # Returns
+/// A `Result` containing an empty `()` value or an error, e.g. if the form is still shown after submitting.
+pub(crate) async fn log_in(page: &Page, login: &FormLogin) -> Result<()> {
+    page.goto(&login.url).await?;
+    let form_url = page.url().await?;
+    for selector in [&login.user_selector, &login.password_selector] {
+        if !page
+            .web2pdf_wait_for_selector(selector, FORM_TIMEOUT)
+            .await?
+        {
+            return Err(form_login_error(format!(
+                "no field matching {:?} on {}",
+                selector, login.url
+            )));
+        }
+    }
+
+    page.find_element(&login.user_selector)
+        .await?
+        .click()
+        .await?
+        .type_str(&login.username)
+        .await?;
+    let password = page.find_element(&login.password_selector).await?;
+    password.click().await?.type_str(&login.password).await?;
+
+    // Logins without a page load (e.g. submitted with fetch) end with the timeout
+    let navigation = async {
+        match &login.submit_selector {
+            Some(selector) => {
+                page.find_element(selector).await?.click().await?;
+            }
+            None => {
+                password.press_key("Enter").await?;
+            }
+        }
+        page.wait_for_navigation().await?;
+        Ok::<_, Box<dyn std::error::Error + Send + Sync>>(())
+    };
+    match tokio::time::timeout(SUBMIT_TIMEOUT, navigation).await {
+        Ok(result) => result.map_err(|e| form_login_error(e.to_string()))?,
+        Err(_) => tracing::debug!("Web2Pdf login caused no page load"),
+    }
+
+    let url = page.url().await?;
+    if url == form_url && page.find_element(&login.password_selector).await.is_ok() {
+        return Err(form_login_error(format!(
+            "the login form of {} is still shown after submitting, check the credentials",
+            login.url
+        )));
+    }
+    tracing::debug!("Web2Pdf logged in at {}, now at {:?}", login.url, url);
+    Ok(())
+}
@@ -0,0 +1,278 @@
+use std::{collections::HashSet, sync::Arc};
+
+use chromiumoxide::auth::Credentials;
+use chromiumoxide::cdp::browser_protocol::fetch::{
+    AuthChallengeResponse, AuthChallengeResponseResponse, AuthChallengeSource,
+    ContinueRequestParams, ContinueWithAuthParams, EnableParams, EventAuthRequired,
+    EventRequestPaused, FailRequestParams, RequestPattern,
+};
+use chromiumoxide::cdp::browser_protocol::network::{ErrorReason, ResourceType};
+use chromiumoxide::Page;
+use futures::{stream, StreamExt};
+
+use crate::adblock::{FilterList, FilterRequest};
+use crate::private_network;
+use crate::Result;
+
+/// Configuration of the request interception of a page
+///
+/// Requests are paused by the browser (`Fetch` domain) and continued, blocked or
+/// authenticated according to this configuration.
+#[derive(Debug, Clone, Default)]
+pub struct InterceptConfig {
+    /// Credentials answering HTTP authentication challenges (e.g. basic auth)
+    pub credentials: Option<Credentials>,
+    /// Credentials answering authentication challenges of a proxy server
+    pub proxy_credentials: Option<Credentials>,
+    /// URL patterns of requests to block, `*` matches any number of characters and `?` a single one
+    pub block_urls: Vec<String>,
+    /// Types of resources to block, e.g. images or fonts
+    pub block_resource_types: Vec<ResourceType>,
+    /// Adblock-style filter lists deciding which requests to block
+    pub filter_lists: Vec<Arc<FilterList>>,
+    /// Block requests to hosts that are or resolve to private addresses, and hosts that don't resolve
+    pub deny_private_networks: bool,
+}
+impl InterceptConfig {
+    /// Whether the configuration requires intercepting requests at all
+    pub fn is_empty(&self) -> bool {
+        self.credentials.is_none()
+            && self.proxy_credentials.is_none()
+            && self.block_urls.is_empty()
+            && self.block_resource_types.is_empty()
+            && self.filter_lists.is_empty()
+            && !self.deny_private_networks
+    }
+
+    /// Whether a paused request should be blocked
+    ///
+    /// # Arguments
+    /// * `event` - The paused request
+    /// * `document_url` - The URL of the page making the request
+    /// * `is_main_document` - Whether the request loads the page itself
+    fn is_blocked(
+        &self,
+        event: &EventRequestPaused,
+        document_url: Option<&str>,
+        is_main_document: bool,
+    ) -> bool {
+        if self.block_resource_types.contains(&event.resource_type)
+            || self
+                .block_urls
+                .iter()
+                .any(|pattern| wildcard_match(pattern.as_bytes(), event.request.url.as_bytes()))
+        {
+            return true;
+        }
+
+        let request = FilterRequest {
+            url: &event.request.url,
+            document_url,
+            resource_type: &event.resource_type,
+            is_main_document,
+        };
+        self.filter_lists
+            .iter()
+            .any(|filter_list| filter_list.is_blocked(&request))
+    }
+}
+
+enum FetchEvent {
+    Paused(EventRequestPaused),
+    Auth(EventAuthRequired),
+}
+
+/// Enables request interception on a page and spawns a task handling the paused requests
+/// The task ends when the page is closed.
+///
+/// # Arguments
+/// * `page` - The page to intercept the requests of
+/// * `config` - The interception configuration
+pub(crate) async fn enable(page: &Page, config: InterceptConfig) -> Result<()> {
+    // Listen before enabling, so no paused request is missed
+    let paused = page.event_listener::<EventRequestPaused>().await?;
+    let auth = page.event_listener::<EventAuthRequired>().await?;
+
+    page.execute(
+        EnableParams::builder()
+            .pattern(RequestPattern::builder().url_pattern("*").build())
+            .handle_auth_requests(
+                config.credentials.is_some() || config.proxy_credentials.is_some(),
+            )
+            .build(),
+    )
+    .await?;
+
+    let main_frame = page.mainframe().await?;
+    let page = page.clone();
+    let mut events = stream::select(
+        paused.map(|event| FetchEvent::Paused((*event).clone())),
+        auth.map(|event| FetchEvent::Auth((*event).clone())),
+    );
+    tokio::spawn(async move {
+        let mut attempted_authentications = HashSet::new();
+        let mut document_url: Option<String> = None;
+        while let Some(event) = events.next().await {
+            let page = page.clone();
+            match event {
+                FetchEvent::Paused(event) => {
+                    let is_main_document = event.resource_type == ResourceType::Document
+                        && main_frame.as_ref() == Some(&event.frame_id);
+                    if is_main_document {
+                        document_url = Some(event.request.url.clone());
+                    }
+                    let blocked =
+                        config.is_blocked(&event, document_url.as_deref(), is_main_document);
+                    if blocked {
+                        tracing::debug!("Web2Pdf blocked request to {}", event.request.url);
+                    }
+                    let deny_private_networks = config.deny_private_networks && !blocked;
+                    tokio::spawn(async move {
+                        // Resolved here, so a slow lookup doesn't hold up the other requests
+                        let blocked = blocked
+                            || deny_private_networks
+                                && !matches!(
+                                    private_network::is_private_url(&event.request.url).await,
+                                    Ok(false)
+                                );
+                        if blocked && deny_private_networks {
+                            tracing::debug!(
+                                "Web2Pdf blocked request into a private network to {}",
+                                event.request.url
+                            );
+                        }
+                        let _ = if blocked {
+                            page.execute(FailRequestParams::new(
+                                event.request_id,
+                                ErrorReason::BlockedByClient,
+                            ))
+                            .await
+                            .map(|_| ())
+                        } else {
+                            page.execute(ContinueRequestParams::new(event.request_id))
+                                .await
+                                .map(|_| ())
+                        };
+                    });
+                }
+                FetchEvent::Auth(event) => {
+                    let credentials = match event.auth_challenge.source {
+                        Some(AuthChallengeSource::Proxy) => &config.proxy_credentials,
+                        _ => &config.credentials,
+                    };
+                    // Only answer a challenge once, otherwise wrong credentials would loop forever
+                    let mut response = match credentials {
+                        Some(_) if attempted_authentications.insert(event.request_id.clone()) => {
+                            AuthChallengeResponse::new(
+                                AuthChallengeResponseResponse::ProvideCredentials,
+                            )
+                        }
+                        Some(_) => {
+                            tracing::warn!(
+                                "Web2Pdf authentication failed for {}",
+                                event.request.url
+                            );
+                            AuthChallengeResponse::new(AuthChallengeResponseResponse::CancelAuth)
+                        }
+                        None => AuthChallengeResponse::new(AuthChallengeResponseResponse::Default),
+                    };
+                    if let Some(credentials) = credentials {
+                        response.username = Some(credentials.username.clone());
+                        response.password = Some(credentials.password.clone());
+                    }
+                    tokio::spawn(async move {
+                        let _ = page
+                            .execute(ContinueWithAuthParams::new(event.request_id, response))
+                            .await;
+                    });
+                }
+            }
+        }
+        tracing::trace!("Web2Pdf request interception ended");
+    });
+
+    tracing::debug!("Web2Pdf request interception enabled");
+    Ok(())
+}
+
+/// Matches `text` against a pattern where `*` matches any number of characters and `?` a single one
+fn wildcard_match(pattern: &[u8], text: &[u8]) -> bool {
+    let (mut p, mut t) = (0, 0);
+    // Position after the last `*` in the pattern and the text position it was tried at
+    let mut backtrack = None;
+    while t < text.len() {
+        match pattern.get(p) {
+            Some(b'*') => {
+                p += 1;
+                backtrack = Some((p, t));
+            }
+            Some(&c) if c == b'?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match backtrack {
+                Some((star_p, star_t)) => {
+                    p = star_p;
+                    t = star_t + 1;
+                    backtrack = Some((star_p, star_t + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == b'*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::wildcard_match;
+
+    fn matches(pattern: &str, text: &str) -> bool {
+        wildcard_match(pattern.as_bytes(), text.as_bytes())
+    }
+
+    #[test]
+    fn literal_patterns_match_exactly() {
+        assert!(matches("", ""));
+        assert!(matches("https://a.com/x", "https://a.com/x"));
+        assert!(!matches("https://a.com/x", "https://a.com/xy"));
+        assert!(!matches("https://a.com/xy", "https://a.com/x"));
+        assert!(!matches("", "a"));
+    }
+
+    #[test]
+    fn star_matches_any_run() {
+        assert!(matches("*", ""));
+        assert!(matches("*", "anything"));
+        assert!(matches("**", "anything"));
+        assert!(matches("a*", "a"));
+        assert!(matches("*.js", "https://a.com/app.js"));
+        assert!(!matches("*.js", "https://a.com/app.json"));
+        assert!(matches(
+            "https://*.example.com/*",
+            "https://ads.example.com/x?y=1"
+        ));
+        assert!(!matches("https://*.example.com/*", "https://example.com/x"));
+        assert!(!matches("a*", "ba"));
+    }
+
+    #[test]
+    fn star_backtracks() {
+        assert!(matches("*ab", "aab"));
+        assert!(matches("a*b*c", "axxbyybzc"));
+        assert!(matches("*a*b", "xaxaxb"));
+        assert!(!matches("a*b*c", "axxbyyb"));
+        assert!(matches("*/ads/*", "https://a.com/x/ads/ads/y"));
+    }
+
+    #[test]
+    fn question_mark_matches_one_byte() {
+        assert!(matches("a?c", "abc"));
+        assert!(!matches("a?c", "ac"));
+        assert!(!matches("a?c", "abbc"));
+        assert!(matches("??", "ab"));
+        assert!(!matches("?", ""));
+        assert!(matches("*?", "a"));
+        assert!(!matches("*?", ""));
+    }
+}
@@ -0,0 +1,345 @@
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+/// Javascript returning the declared document language (or null) and a sample of the rendered text
+pub const DETECT_LANGUAGE_JS: &str = r#"(() => {
+    const html = document.documentElement;
+    let lang = html.getAttribute('lang') || html.getAttribute('xml:lang');
+    if (!lang) {
+        const meta = document.querySelector('meta[http-equiv="content-language" i], meta[name="language" i], meta[property="og:locale"]');
+        lang = meta && meta.getAttribute('content');
+    }
+    const text = document.body ? document.body.innerText.slice(0, 20000) : '';
+    return [lang || null, text];
+})()"#;
+
+/// Normalizes a declared language into a BCP 47 language tag
+/// e.g. "en_US" -> "en-US", "de, en" -> "de"
+///
+/// # Arguments
+/// * `lang` - The declared language
+///
+/// # Returns
+/// * The normalized language tag or None if the value is not a plausible tag
+pub fn normalize_language_tag(lang: &str) -> Option<String> {
+    let tag = lang.split(',').next()?.trim().replace('_', "-");
+
+    if tag.is_empty()
+        || tag.len() > 35
+        || !tag.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+        || !tag
+            .split('-')
+            .next()?
+            .chars()
+            .all(|c| c.is_ascii_alphabetic())
+    {
+        return None;
+    }
+
+    Some(tag)
+}
+
+// Frequent short words used to tell apart languages written in latin script
+const STOPWORDS: &[(&str, &[&str])] = &[
+    (
+        "en",
+        &[
+            "the", "and", "of", "to", "is", "in", "that", "with", "for", "this",
+        ],
+    ),
+    (
+        "de",
+        &[
+            "der", "die", "und", "das", "ist", "nicht", "mit", "ein", "sie", "auf",
+        ],
+    ),
+    (
+        "fr",
+        &[
+            "le", "la", "les", "et", "des", "est", "une", "pour", "dans", "que",
+        ],
+    ),
+    (
+        "es",
+        &[
+            "el", "los", "las", "y", "del", "es", "una", "por", "con", "que",
+        ],
+    ),
+    (
+        "it",
+        &[
+            "il", "della", "che", "di", "e", "una", "per", "sono", "gli", "non",
+        ],
+    ),
+    (
+        "nl",
+        &[
+            "de", "het", "een", "en", "van", "is", "niet", "op", "dat", "zijn",
+        ],
+    ),
+    (
+        "pt",
+        &[
+            "o", "os", "as", "e", "do", "da", "uma", "para", "com", "não",
+        ],
+    ),
+];
+
+/// Guesses the language of a text sample
+/// Non latin scripts are detected by their unicode ranges, latin scripts by counting common words
+///
+/// # Arguments
+/// * `text` - The text to guess the language of
+///
+/// # Returns
+/// * The guessed language tag or None if there is not enough evidence
+pub fn guess_language(text: &str) -> Option<&'static str> {
+    // Counts per script: latin, han, kana, hangul, arabic, hebrew, cyrillic, greek, thai, devanagari
+    let mut scripts = [0usize; 10];
+    let mut ukrainian = 0;
+    for c in text.chars() {
+        let index = match c {
+            'a'..='z' | 'A'..='Z' | '\u{00C0}'..='\u{024F}' => 0,
+            '\u{3400}'..='\u{4DBF}' | '\u{4E00}'..='\u{9FFF}' => 1,
+            '\u{3040}'..='\u{30FF}' => 2,
+            '\u{1100}'..='\u{11FF}' | '\u{AC00}'..='\u{D7AF}' => 3,
+            '\u{0600}'..='\u{06FF}' => 4,
+            '\u{0590}'..='\u{05FF}' => 5,
+            '\u{0400}'..='\u{04FF}' => {
+                if matches!(c, 'і' | 'ї' | 'є' | 'ґ' | 'І' | 'Ї' | 'Є' | 'Ґ') {
+                    ukrainian += 1;
+                }
+                6
+            }
+            '\u{0370}'..='\u{03FF}' => 7,
+            '\u{0E00}'..='\u{0E7F}' => 8,
+            '\u{0900}'..='\u{097F}' => 9,
+            _ => continue,
+        };
+        scripts[index] += 1;
+    }
+
+    let total: usize = scripts.iter().sum();
+    if total < 20 {
+        return None;
+    }
+
+    // Japanese mixes kana with han characters, so any significant amount of kana decides it
+    if scripts[2] * 10 > total {
+        return Some("ja");
+    }
+
+    let (script, count) = scripts
+        .iter()
+        .enumerate()
+        .max_by_key(|(_, count)| **count)?;
+    if count * 2 < total {
+        return None;
+    }
+
+    match script {
+        0 => guess_latin_language(text),
+        1 => Some("zh"),
+        2 => Some("ja"),
+        3 => Some("ko"),
+        4 => Some("ar"),
+        5 => Some("he"),
+        6 if ukrainian * 100 > *count => Some("uk"),
+        6 => Some("ru"),
+        7 => Some("el"),
+        8 => Some("th"),
+        _ => Some("hi"),
+    }
+}
+
+/// Guesses the language of a latin script text sample by counting common words
+fn guess_latin_language(text: &str) -> Option<&'static str> {
+    let mut scores = [0usize; STOPWORDS.len()];
+    let mut words = 0;
+    for word in text
+        .split(|c: char| !c.is_alphabetic())
+        .filter(|word| !word.is_empty())
+    {
+        words += 1;
+        let word = word.to_lowercase();
+        for (score, (_, stopwords)) in scores.iter_mut().zip(STOPWORDS) {
+            if stopwords.contains(&word.as_str()) {
+                *score += 1;
+            }
+        }
+    }
+
+    let (index, score) = scores.iter().enumerate().max_by_key(|(_, score)| **score)?;
+    // Require that at least 5% of the words are common words of the language
+    if *score < 5 || score * 20 < words {
+        return None;
+    }
+
+    Some(STOPWORDS[index].0)
+}
+
+/// Direction of the text of a document
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TextDirection {
+    Ltr,
+    Rtl,
+}
+impl TextDirection {
+    /// The value of the html `dir` attribute
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TextDirection::Ltr => "ltr",
+            TextDirection::Rtl => "rtl",
+        }
+    }
+}
+impl fmt::Display for TextDirection {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+impl FromStr for TextDirection {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "ltr" => Ok(TextDirection::Ltr),
+            "rtl" => Ok(TextDirection::Rtl),
+            _ => Err(format!(
+                "invalid text direction '{}', expected rtl or ltr",
+                s
+            )),
+        }
+    }
+}
+
+/// Checks whether a language tag refers to a language written right-to-left
+///
+/// # Arguments
+/// * `lang` - The language tag, e.g. "ar-EG"
+pub fn is_rtl_language(lang: &str) -> bool {
+    let primary = lang.split(['-', '_']).next().unwrap_or_default();
+    [
+        "ar", "he", "iw", "fa", "ur", "ps", "yi", "dv", "sd", "ug", "ckb",
+    ]
+    .iter()
+    .any(|rtl| primary.eq_ignore_ascii_case(rtl))
+}
+
+/// Javascript collecting layout metrics relevant for right-to-left documents:
+/// [declared language, computed root direction, text blocks with rtl text,
+/// of those blocks rendered ltr, of those blocks aligned to the left, horizontal overflow]
+pub const RTL_LAYOUT_JS: &str = r#"(() => {
+    const html = document.documentElement;
+    const lang = html.getAttribute('lang') || html.getAttribute('xml:lang');
+    const rtlChar = /[\u0590-\u08FF\uFB1D-\uFDFF\uFE70-\uFEFF]/g;
+    let rtlBlocks = 0, ltrBlocks = 0, leftAligned = 0;
+    for (const element of document.querySelectorAll('p, li, td, th, h1, h2, h3, h4, h5, h6, blockquote')) {
+        const text = element.textContent.trim();
+        const rtlCount = (text.match(rtlChar) || []).length;
+        if (text.length === 0 || rtlCount * 2 < text.replace(/\s/g, '').length) {
+            continue;
+        }
+        rtlBlocks++;
+        const style = getComputedStyle(element);
+        if (style.direction === 'ltr') {
+            ltrBlocks++;
+        }
+        if (style.textAlign === 'left' || (style.textAlign === 'start' && style.direction === 'ltr')) {
+            leftAligned++;
+        }
+    }
+    return [lang || null, getComputedStyle(html).direction, rtlBlocks, ltrBlocks, leftAligned, html.scrollWidth > html.clientWidth];
+})()"#;
+
+/// Locale settings emulated together, so pages render consistently for a language,
+/// see `PageWeb2Pdf::web2pdf_set_lang_profile`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LangProfile {
+    /// The locale of `navigator.language` and of number and date formats, e.g. "de-DE"
+    pub locale: String,
+    /// The Accept-Language header, e.g. "de-DE,de;q=0.9"
+    pub accept_language: String,
+    /// The IANA timezone, e.g. "Europe/Berlin"
+    pub timezone: String,
+}
+
+/// Built-in profiles: name, locale and timezone
+const LANG_PROFILES: &[(&str, &str, &str)] = &[
+    ("ar", "ar-SA", "Asia/Riyadh"),
+    ("cs", "cs-CZ", "Europe/Prague"),
+    ("da", "da-DK", "Europe/Copenhagen"),
+    ("de", "de-DE", "Europe/Berlin"),
+    ("de-at", "de-AT", "Europe/Vienna"),
+    ("de-ch", "de-CH", "Europe/Zurich"),
+    ("en", "en-US", "America/New_York"),
+    ("en-gb", "en-GB", "Europe/London"),
+    ("es", "es-ES", "Europe/Madrid"),
+    ("es-mx", "es-MX", "America/Mexico_City"),
+    ("fi", "fi-FI", "Europe/Helsinki"),
+    ("fr", "fr-FR", "Europe/Paris"),
+    ("he", "he-IL", "Asia/Jerusalem"),
+    ("hi", "hi-IN", "Asia/Kolkata"),
+    ("it", "it-IT", "Europe/Rome"),
+    ("ja", "ja-JP", "Asia/Tokyo"),
+    ("ko", "ko-KR", "Asia/Seoul"),
+    ("nl", "nl-NL", "Europe/Amsterdam"),
+    ("no", "nb-NO", "Europe/Oslo"),
+    ("pl", "pl-PL", "Europe/Warsaw"),
+    ("pt", "pt-PT", "Europe/Lisbon"),
+    ("pt-br", "pt-BR", "America/Sao_Paulo"),
+    ("ru", "ru-RU", "Europe/Moscow"),
+    ("sv", "sv-SE", "Europe/Stockholm"),
+    ("tr", "tr-TR", "Europe/Istanbul"),
+    ("uk", "uk-UA", "Europe/Kyiv"),
+    ("zh", "zh-CN", "Asia/Shanghai"),
+    ("zh-tw", "zh-TW", "Asia/Taipei"),
+];
+
+impl LangProfile {
+    /// The names of the built-in profiles
+    pub fn names() -> impl Iterator<Item = &'static str> {
+        LANG_PROFILES.iter().map(|(name, _, _)| *name)
+    }
+
+    /// Finds a built-in profile, other language tags use the timezone of their language,
+    /// e.g. "fr-CA" uses "Europe/Paris"
+    ///
+    /// # Arguments
+    /// * `name` - The name of the profile or a language tag, e.g. "de" or "de-AT"
+    pub fn resolve(name: &str) -> Option<LangProfile> {
+        let tag = normalize_language_tag(name)?;
+        let primary = tag.split('-').next()?.to_ascii_lowercase();
+        let (_, locale, timezone) = LANG_PROFILES
+            .iter()
+            .find(|(name, _, _)| name.eq_ignore_ascii_case(&tag))
+            .or_else(|| LANG_PROFILES.iter().find(|(name, _, _)| *name == primary))?;
+        let locale = if tag.contains('-') && !locale.eq_ignore_ascii_case(&tag) {
+            tag.clone()
+        } else {
+            locale.to_string()
+        };
+        let language = locale.split('-').next().unwrap_or_default();
+        Some(LangProfile {
+            accept_language: format!("{},{};q=0.9", locale, language),
+            locale,
+            timezone: timezone.to_string(),
+        })
+    }
+}
+impl FromStr for LangProfile {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        LangProfile::resolve(s).ok_or_else(|| {
+            format!(
+                "unknown language profile '{}', expected a language tag of one of: {}",
+                s,
+                LangProfile::names().collect::<Vec<_>>().join(", ")
+            )
+        })
+    }
+}
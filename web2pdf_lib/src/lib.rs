@@ -1,17 +1,81 @@
 use std::future::Future;
 use std::path::Path;
+use std::time::Duration;
 use tokio::fs;
 
-use chromiumoxide::cdp::browser_protocol::page::PrintToPdfParams;
-use chromiumoxide::cdp::browser_protocol::target::CreateTargetParams;
+use base64::prelude::*;
+use chromiumoxide::cdp::browser_protocol::browser::BrowserContextId;
+use chromiumoxide::cdp::browser_protocol::emulation::{
+    MediaFeature, SetDeviceMetricsOverrideParams, SetEmulatedMediaParams, SetLocaleOverrideParams,
+    SetTimezoneOverrideParams, SetUserAgentOverrideParams,
+};
+use chromiumoxide::cdp::browser_protocol::input::{DispatchKeyEventParams, DispatchKeyEventType};
+use chromiumoxide::cdp::browser_protocol::io::{self as cdp_io, ReadParams};
+use chromiumoxide::cdp::browser_protocol::network::{
+    EmulateNetworkConditionsParams, Headers, LoadNetworkResourceOptions, LoadNetworkResourceParams,
+    SetExtraHttpHeadersParams,
+};
+use chromiumoxide::cdp::browser_protocol::page::{
+    self as cdp_page, AddScriptToEvaluateOnNewDocumentParams, CaptureScreenshotFormat,
+    CaptureScreenshotParams, CaptureSnapshotFormat, CaptureSnapshotParams, PrintToPdfParams,
+    SetDocumentContentParams,
+};
+use chromiumoxide::cdp::browser_protocol::storage::SetCookiesParams;
+use chromiumoxide::cdp::browser_protocol::target::{
+    CreateBrowserContextParams, CreateTargetParams,
+};
 use chromiumoxide::handler::viewport::Viewport;
+use chromiumoxide::handler::HandlerConfig;
 use chromiumoxide::page::MediaTypeParams;
 use chromiumoxide::Page;
 use futures::StreamExt;
 
+use code_view::{CodeTheme, CodeView, DataFormat};
+use cookie_controls::SetCookieControlsParams;
+use form_login::FormLogin;
+use intercept::InterceptConfig;
+use lang::{LangProfile, TextDirection};
+use metadata::Metadata;
+use mono::MonoOptions;
+use outline::Heading;
+use paper::PaperSize;
+use util::{CookieFormat, CookieParsing};
+
+pub use chromiumoxide::auth::Credentials;
 pub use chromiumoxide::browser::Browser;
 pub use chromiumoxide::browser::BrowserConfig;
+pub mod adblock;
+#[cfg(feature = "browser-cookies")]
+pub mod browser_cookies;
+pub mod client_cert;
+pub mod code_view;
+pub mod consent;
+pub mod cookie_controls;
+pub mod encryption;
+#[cfg(feature = "fetcher")]
+pub mod fetcher;
+pub mod form_login;
+pub mod intercept;
+pub mod lang;
+pub mod metadata;
+pub mod mono;
+pub mod outline;
+pub mod paper;
+pub mod pdf;
+pub mod pdfa;
+pub mod pool;
+pub mod private_network;
+pub mod session;
+pub mod simple;
+pub mod single_file;
+pub mod site_fixes;
+pub mod slides;
+pub mod svg;
+pub mod table;
+pub mod toc;
+pub mod trust;
 pub mod util;
+pub mod watermark;
 
 pub type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
 
@@ -24,6 +88,7 @@ pub trait BrowserWeb2Pdf {
         path: impl AsRef<Path> + Send,
     ) -> impl Future<Output = Result<Browser>> + Send;
     fn close_and_wait(self) -> impl Future<Output = Result<Browser>> + Send;
+    fn web2pdf_pid(&mut self) -> Option<u32>;
     fn web2pdf_new_page(
         &self,
         params: impl Into<CreateTargetParams> + Send,
@@ -32,6 +97,42 @@ pub trait BrowserWeb2Pdf {
         &self,
         file: impl AsRef<Path> + Send,
     ) -> impl Future<Output = Result<()>> + Send;
+    fn web2pdf_save_cookie_file(
+        &self,
+        file: impl AsRef<Path> + Send,
+    ) -> impl Future<Output = Result<usize>> + Send;
+    fn web2pdf_load_cookie_file_as(
+        &self,
+        file: impl AsRef<Path> + Send,
+        format: Option<CookieFormat>,
+        parsing: CookieParsing,
+    ) -> impl Future<Output = Result<()>> + Send;
+    #[cfg(feature = "browser-cookies")]
+    fn web2pdf_load_firefox_cookies(
+        &self,
+        profile: Option<&str>,
+    ) -> impl Future<Output = Result<usize>> + Send;
+    #[cfg(feature = "browser-cookies")]
+    fn web2pdf_load_chrome_cookies(
+        &self,
+        profile: Option<&Path>,
+    ) -> impl Future<Output = Result<usize>> + Send;
+    fn web2pdf_connect_with_viewport(
+        url: impl Into<String> + Send,
+        viewport: Viewport,
+    ) -> impl Future<Output = Result<Browser>> + Send;
+    fn web2pdf_connect(
+        url: impl Into<String> + Send,
+    ) -> impl Future<Output = Result<Browser>> + Send;
+    fn web2pdf_create_isolated_context(
+        &self,
+    ) -> impl Future<Output = Result<BrowserContextId>> + Send;
+    fn web2pdf_new_page_in_context(
+        &self,
+        params: impl Into<CreateTargetParams> + Send,
+        context: BrowserContextId,
+    ) -> impl Future<Output = Result<Page>> + Send;
+    fn web2pdf_form_login(&self, login: &FormLogin) -> impl Future<Output = Result<()>> + Send;
 }
 
 pub trait PageWeb2Pdf {
@@ -39,6 +140,10 @@ pub trait PageWeb2Pdf {
         &self,
         output: impl AsRef<Path> + Send,
     ) -> impl Future<Output = chromiumoxide::Result<Vec<u8>>> + Send;
+    fn web2pdf_pdf_mono(
+        &self,
+        opts: PrintToPdfParams,
+    ) -> impl Future<Output = chromiumoxide::Result<Vec<u8>>> + Send;
     fn web2pdf_save_pdf_mono(
         &self,
         opts: PrintToPdfParams,
@@ -48,10 +153,120 @@ pub trait PageWeb2Pdf {
         &self,
         output: impl AsRef<Path> + Send,
     ) -> impl Future<Output = chromiumoxide::Result<Vec<u8>>> + Send;
+    fn web2pdf_screenshot_fullpage(
+        &self,
+        format: CaptureScreenshotFormat,
+        quality: Option<i64>,
+    ) -> impl Future<Output = Result<Vec<u8>>> + Send;
+    fn web2pdf_save_screenshot_fullpage(
+        &self,
+        format: CaptureScreenshotFormat,
+        quality: Option<i64>,
+        output: impl AsRef<Path> + Send,
+    ) -> impl Future<Output = Result<Vec<u8>>> + Send;
+    fn web2pdf_mhtml(&self) -> impl Future<Output = Result<String>> + Send;
+    fn web2pdf_save_mhtml(
+        &self,
+        output: impl AsRef<Path> + Send,
+    ) -> impl Future<Output = Result<String>> + Send;
+    fn web2pdf_single_file_html(&self) -> impl Future<Output = Result<String>> + Send;
+    fn web2pdf_save_single_file_html(
+        &self,
+        output: impl AsRef<Path> + Send,
+    ) -> impl Future<Output = Result<String>> + Send;
+    fn web2pdf_detect_language(&self) -> impl Future<Output = Result<Option<String>>> + Send;
+    fn web2pdf_remove_elements(
+        &self,
+        selector: impl AsRef<str> + Send,
+    ) -> impl Future<Output = Result<u64>> + Send;
+    fn web2pdf_set_direction(
+        &self,
+        direction: TextDirection,
+    ) -> impl Future<Output = Result<()>> + Send;
+    fn web2pdf_check_rtl_layout(&self) -> impl Future<Output = Result<Vec<String>>> + Send;
+    fn web2pdf_dismiss_cookie_banners(&self) -> impl Future<Output = Result<u64>> + Send;
+    fn web2pdf_emulate_media(
+        &self,
+        media_type: MediaTypeParams,
+        features: Vec<MediaFeature>,
+    ) -> impl Future<Output = Result<()>> + Send;
+    fn web2pdf_set_user_agent(
+        &self,
+        user_agent: impl Into<String> + Send,
+    ) -> impl Future<Output = Result<()>> + Send;
+    fn web2pdf_set_viewport_width(&self, width: u32) -> impl Future<Output = Result<()>> + Send;
+    fn web2pdf_set_extra_headers(
+        &self,
+        headers: &[(String, String)],
+    ) -> impl Future<Output = Result<()>> + Send;
+    fn web2pdf_intercept(&self, config: InterceptConfig)
+        -> impl Future<Output = Result<()>> + Send;
+    fn web2pdf_goto_code_view(
+        &self,
+        view: &CodeView,
+        theme: CodeTheme,
+    ) -> impl Future<Output = Result<()>> + Send;
+    fn web2pdf_text_content(&self) -> impl Future<Output = Result<String>> + Send;
+    fn web2pdf_hide_elements(&self, selectors: &[&str]) -> impl Future<Output = Result<()>> + Send;
+    fn web2pdf_add_style(&self, css: &str) -> impl Future<Output = Result<()>> + Send;
+    fn web2pdf_add_init_script(
+        &self,
+        script: impl Into<String> + Send,
+    ) -> impl Future<Output = Result<()>> + Send;
+    fn web2pdf_wait_for_selector(
+        &self,
+        selector: impl AsRef<str> + Send,
+        timeout: Duration,
+    ) -> impl Future<Output = Result<bool>> + Send;
+    fn web2pdf_collect_headings(&self) -> impl Future<Output = Result<Vec<Heading>>> + Send;
+    fn web2pdf_fetch(&self, url: &str) -> impl Future<Output = Result<Vec<u8>>> + Send;
+    fn web2pdf_set_content(
+        &self,
+        html: impl Into<String> + Send,
+    ) -> impl Future<Output = Result<()>> + Send;
+    fn web2pdf_goto_table(
+        &self,
+        url: &str,
+        delimiter: char,
+    ) -> impl Future<Output = Result<()>> + Send;
+    fn web2pdf_table_overflows(&self) -> impl Future<Output = Result<bool>> + Send;
+    fn web2pdf_metadata(&self) -> impl Future<Output = Result<Metadata>> + Send;
+    fn web2pdf_pretty_data(&self, theme: CodeTheme) -> impl Future<Output = Result<bool>> + Send;
+    fn web2pdf_goto_svg(&self, url: &str) -> impl Future<Output = Result<()>> + Send;
+    fn web2pdf_pdf_slides(
+        &self,
+        opts: PrintToPdfParams,
+    ) -> impl Future<Output = Result<Vec<u8>>> + Send;
+    fn web2pdf_press_key(
+        &self,
+        key: &str,
+        windows_virtual_key_code: i64,
+    ) -> impl Future<Output = Result<()>> + Send;
+    fn web2pdf_pdf_mono_with(
+        &self,
+        opts: PrintToPdfParams,
+        mono: MonoOptions,
+    ) -> impl Future<Output = chromiumoxide::Result<Vec<u8>>> + Send;
+    fn web2pdf_content_hash(&self) -> impl Future<Output = Result<String>> + Send;
+    fn web2pdf_limit_bandwidth(
+        &self,
+        bytes_per_second: f64,
+    ) -> impl Future<Output = Result<()>> + Send;
+    fn web2pdf_block_third_party_cookies(&self) -> impl Future<Output = Result<bool>> + Send;
+    fn web2pdf_set_lang_profile(
+        &self,
+        profile: &LangProfile,
+    ) -> impl Future<Output = Result<()>> + Send;
+    fn web2pdf_set_accept_language(
+        &self,
+        accept_language: impl Into<String> + Send,
+    ) -> impl Future<Output = Result<()>> + Send;
+    fn web2pdf_links(&self) -> impl Future<Output = Result<Vec<String>>> + Send;
 }
 
 pub trait ViewportWeb2Pdf {
     fn web2pdf_viewport() -> Viewport;
+    fn web2pdf_paper_viewport(width: f64, height: f64) -> Viewport;
 }
 
 impl BrowserWeb2Pdf for Browser {
@@ -63,36 +278,30 @@ impl BrowserWeb2Pdf for Browser {
     ///
     /// # Returns
     /// A `Result` containing a new `Web2Pdf` instance or an error.
-    fn web2pdf_launch_from_config(
-        browser_config: BrowserConfig,
-    ) -> impl Future<Output = Result<Browser>> + Send {
-        async {
-            let (browser, mut handler) = Browser::launch(browser_config).await?;
+    async fn web2pdf_launch_from_config(browser_config: BrowserConfig) -> Result<Browser> {
+        let (browser, mut handler) = Browser::launch(browser_config).await?;
 
-            // Spawn a task to handle the browser events
-            let _ = tokio::spawn(async move { while let Some(_) = handler.next().await {} });
+        // Spawn a task to handle the browser events
+        tokio::spawn(async move { while handler.next().await.is_some() {} });
 
-            tracing::debug!("Web2Pdf browser launched");
+        tracing::debug!("Web2Pdf browser launched");
 
-            Ok(browser)
-        }
+        Ok(browser)
     }
 
     /// Creates a new `Browser` instance using the system's installed Chromium browser.
     ///
     /// # Returns
     /// A `Result` containing a new `Browser` instance or an error.
-    fn web2pdf_launch() -> impl Future<Output = Result<Browser>> + Send {
-        async {
-            // Attempt to find a system installation of chromium
-            let browser_config = BrowserConfig::builder()
-                .viewport(Some(Viewport::web2pdf_viewport()))
-                .build()?;
+    async fn web2pdf_launch() -> Result<Browser> {
+        // Attempt to find a system installation of chromium
+        let browser_config = BrowserConfig::builder()
+            .viewport(Some(Viewport::web2pdf_viewport()))
+            .build()?;
 
-            tracing::debug!("Web2Pdf browser launching using standard config");
+        tracing::debug!("Web2Pdf browser launching using standard config");
 
-            Self::web2pdf_launch_from_config(browser_config).await
-        }
+        Self::web2pdf_launch_from_config(browser_config).await
     }
 
     /// Creates a new `Browser` instance using a specific Chromium executable path.
@@ -102,32 +311,35 @@ impl BrowserWeb2Pdf for Browser {
     ///
     /// # Returns
     /// A `Result` containing a new `Browser` instance or an error.
-    fn web2pdf_launch_from_executable_path(
-        path: impl AsRef<Path> + Send,
-    ) -> impl Future<Output = Result<Browser>> + Send {
-        async move {
-            // Load a browser from a specific executable path
-            let browser_config = BrowserConfig::builder()
-                .viewport(Some(Viewport::web2pdf_viewport()))
-                .chrome_executable(&path)
-                .build()?;
+    async fn web2pdf_launch_from_executable_path(path: impl AsRef<Path> + Send) -> Result<Browser> {
+        // Load a browser from a specific executable path
+        let browser_config = BrowserConfig::builder()
+            .viewport(Some(Viewport::web2pdf_viewport()))
+            .chrome_executable(&path)
+            .build()?;
 
-            tracing::debug!("Web2Pdf browser launching using executable path");
+        tracing::debug!("Web2Pdf browser launching using executable path");
 
-            Self::web2pdf_launch_from_config(browser_config).await
-        }
+        Self::web2pdf_launch_from_config(browser_config).await
     }
 
     /// Closes the browser instance and waits for it to terminate.
     ///
     /// # Returns
     /// A `Result` containing an empty `()` value or an error.
-    fn close_and_wait(mut self) -> impl Future<Output = Result<Browser>> + Send {
-        async move {
-            self.close().await?;
-            self.wait().await?;
-            Ok(self)
-        }
+    async fn close_and_wait(mut self) -> Result<Browser> {
+        self.close().await?;
+        self.wait().await?;
+        Ok(self)
+    }
+
+    /// The process id of the browser
+    ///
+    /// # Returns
+    /// The process id or None if the browser was not launched by this instance
+    /// (e.g. when connected to an already running browser) or has already exited.
+    fn web2pdf_pid(&mut self) -> Option<u32> {
+        self.get_mut_child()?.as_mut_inner().id()
     }
 
     /// Create a new browser page
@@ -137,31 +349,196 @@ impl BrowserWeb2Pdf for Browser {
     ///
     /// # Returns
     /// A `Result` containing a new `Page` instance or an error.
-    fn web2pdf_new_page(
-        &self,
-        params: impl Into<CreateTargetParams> + Send,
-    ) -> impl Future<Output = Result<Page>> + Send {
-        async move {
-            let page = self.new_page(params).await?;
-            page.emulate_media_type(MediaTypeParams::Print).await?;
-            tracing::debug!("Web2Pdf new page created");
-            Ok(page)
-        }
+    async fn web2pdf_new_page(&self, params: impl Into<CreateTargetParams> + Send) -> Result<Page> {
+        let page = self.new_page(params).await?;
+        page.emulate_media_type(MediaTypeParams::Print).await?;
+        tracing::debug!("Web2Pdf new page created");
+        Ok(page)
     }
 
-    /// Load a cookie file
-    fn web2pdf_load_cookie_file(
+    /// Load a cookie file, its format (Netscape or JSON) is detected from the contents
+    async fn web2pdf_load_cookie_file(&self, file: impl AsRef<Path> + Send) -> Result<()> {
+        let file_contents = fs::read_to_string(file).await?;
+
+        let cookies = util::parse_cookie_file(&file_contents)?;
+
+        self.set_cookies(cookies).await?;
+        Ok(())
+    }
+
+    /// Saves all cookies of the browser to a cookie file (in Netscape format)
+    ///
+    /// # Arguments
+    /// * `file` - The path of the cookie file, it is overwritten
+    ///
+    /// # Returns
+    /// A `Result` containing the number of saved cookies or an error.
+    async fn web2pdf_save_cookie_file(&self, file: impl AsRef<Path> + Send) -> Result<usize> {
+        let cookies = self.get_cookies().await?;
+
+        fs::write(file, util::format_cookie_file(&cookies)).await?;
+
+        tracing::debug!("Web2Pdf saved {} cookies", cookies.len());
+        Ok(cookies.len())
+    }
+
+    /// Loads a cookie file of a given format
+    ///
+    /// # Arguments
+    /// * `file` - The path of the cookie file
+    /// * `format` - The format of the cookie file, None to detect it
+    /// * `parsing` - Whether malformed entries are errors or skipped
+    ///
+    /// # Returns
+    /// A `Result` containing an empty `()` value or an error.
+    async fn web2pdf_load_cookie_file_as(
         &self,
         file: impl AsRef<Path> + Send,
-    ) -> impl Future<Output = Result<()>> + Send {
-        async move {
-            let file_contents = fs::read_to_string(file).await?;
+        format: Option<CookieFormat>,
+        parsing: CookieParsing,
+    ) -> Result<()> {
+        let file_contents = fs::read_to_string(file).await?;
+
+        let cookies = util::parse_cookie_file_as(&file_contents, format, parsing)?;
+
+        self.set_cookies(cookies).await?;
+        Ok(())
+    }
+
+    /// Loads the cookies of a Firefox profile, see `browser_cookies::firefox_cookies`
+    ///
+    /// # Arguments
+    /// * `profile` - The profile directory or profile name, None for the default profile
+    ///
+    /// # Returns
+    /// A `Result` containing the number of loaded cookies or an error.
+    #[cfg(feature = "browser-cookies")]
+    async fn web2pdf_load_firefox_cookies(&self, profile: Option<&str>) -> Result<usize> {
+        let cookies = browser_cookies::firefox_cookies(profile).await?;
+        let count = cookies.len();
+
+        self.set_cookies(cookies).await?;
+        tracing::debug!("Web2Pdf loaded {} Firefox cookies", count);
+        Ok(count)
+    }
+
+    /// Loads the cookies of a Chrome or Chromium profile, see `browser_cookies::chrome_cookies`
+    ///
+    /// # Arguments
+    /// * `profile` - The profile directory, None for the default profile
+    ///
+    /// # Returns
+    /// A `Result` containing the number of loaded cookies or an error.
+    #[cfg(feature = "browser-cookies")]
+    async fn web2pdf_load_chrome_cookies(&self, profile: Option<&Path>) -> Result<usize> {
+        let cookies = browser_cookies::chrome_cookies(profile).await?;
+        let count = cookies.len();
+
+        self.set_cookies(cookies).await?;
+        tracing::debug!("Web2Pdf loaded {} Chrome cookies", count);
+        Ok(count)
+    }
+
+    /// Connects to an already running browser with remote debugging enabled
+    /// (e.g. started with `--remote-debugging-port=9222`).
+    /// Dropping the returned `Browser` only disconnects, `close_and_wait` closes the running browser.
+    ///
+    /// # Arguments
+    /// * `url` - The websocket debugger url, e.g. `ws://127.0.0.1:9222/devtools/browser/<id>`,
+    ///   or the http url of the debugging endpoint, e.g. `http://127.0.0.1:9222`
+    /// * `viewport` - The viewport used for new pages
+    ///
+    /// # Returns
+    /// A `Result` containing the connected `Browser` instance or an error.
+    async fn web2pdf_connect_with_viewport(
+        url: impl Into<String> + Send,
+        viewport: Viewport,
+    ) -> Result<Browser> {
+        let config = HandlerConfig {
+            viewport: Some(viewport),
+            ..Default::default()
+        };
+        let (browser, mut handler) = Browser::connect_with_config(url, config).await?;
+
+        // Spawn a task to handle the browser events
+        tokio::spawn(async move { while handler.next().await.is_some() {} });
+
+        tracing::debug!("Web2Pdf browser connected");
+
+        Ok(browser)
+    }
 
-            let cookies = util::parse_cookie_file(&file_contents)?;
+    /// Connects to an already running browser using the standard viewport,
+    /// see `web2pdf_connect_with_viewport`
+    ///
+    /// # Arguments
+    /// * `url` - The websocket or http url of the debugging endpoint
+    ///
+    /// # Returns
+    /// A `Result` containing the connected `Browser` instance or an error.
+    async fn web2pdf_connect(url: impl Into<String> + Send) -> Result<Browser> {
+        Self::web2pdf_connect_with_viewport(url, Viewport::web2pdf_viewport()).await
+    }
 
-            self.set_cookies(cookies).await?;
-            Ok(())
+    /// Creates an incognito browser context, so its pages share no cookies, storage or cache
+    /// with other contexts. The current cookies of the browser (e.g. from a cookie file) are copied into it.
+    /// Dispose it with `dispose_browser_context` once done, which also closes its pages.
+    ///
+    /// # Returns
+    /// A `Result` containing the id of the new context or an error.
+    async fn web2pdf_create_isolated_context(&self) -> Result<BrowserContextId> {
+        let context = self
+            .create_browser_context(
+                CreateBrowserContextParams::builder()
+                    .dispose_on_detach(true)
+                    .build(),
+            )
+            .await?;
+
+        let cookies = util::cookie_params(self.get_cookies().await?);
+        if !cookies.is_empty() {
+            let mut params = SetCookiesParams::new(cookies);
+            params.browser_context_id = Some(context.clone());
+            self.execute(params).await?;
         }
+
+        tracing::debug!("Web2Pdf isolated browser context created");
+        Ok(context)
+    }
+
+    /// Create a new browser page in a browser context, see `web2pdf_create_isolated_context`
+    ///
+    /// # Arguments
+    /// * `params` - The `CreateTargetParams` to use for creating the page.
+    /// * `context` - The browser context of the page
+    ///
+    /// # Returns
+    /// A `Result` containing a new `Page` instance or an error.
+    async fn web2pdf_new_page_in_context(
+        &self,
+        params: impl Into<CreateTargetParams> + Send,
+        context: BrowserContextId,
+    ) -> Result<Page> {
+        let mut params = params.into();
+        params.browser_context_id = Some(context);
+        self.web2pdf_new_page(params).await
+    }
+
+    /// Logs in through a login form, the cookies of the login are used by all pages created afterwards
+    ///
+    /// # Arguments
+    /// * `login` - The login form and the credentials
+    ///
+    /// # Returns
+    /// A `Result` containing an empty `()` value or an error, e.g. if the login form is still shown after submitting.
+    async fn web2pdf_form_login(&self, login: &FormLogin) -> Result<()> {
+        let page = self.web2pdf_new_page("about:blank").await?;
+        // The error is kept as a message, as the future has to be sendable while closing the page
+        let result = form_login::log_in(&page, login)
+            .await
+            .map_err(|e| e.to_string());
+        page.close().await?;
+        Ok(result?)
     }
 }
 
@@ -174,19 +551,30 @@ impl PageWeb2Pdf for Page {
     /// # Returns
     /// A `Result` containing a `Vec<u8>` containing the PDF data or an error.
     /// (The Page is already saved as PDF at the specified path)
-    fn web2pdf_save_pdf_standard(
+    async fn web2pdf_save_pdf_standard(
         &self,
         output: impl AsRef<Path> + Send,
-    ) -> impl Future<Output = chromiumoxide::Result<Vec<u8>>> + Send {
-        async move {
-            let pdf_params = PrintToPdfParams::builder()
-                .print_background(true)
-                .prefer_css_page_size(true)
-                .build();
-            let pdf = self.save_pdf(pdf_params, output).await?;
-
-            Ok(pdf)
-        }
+    ) -> chromiumoxide::Result<Vec<u8>> {
+        let pdf_params = PrintToPdfParams::builder()
+            .print_background(true)
+            .prefer_css_page_size(true)
+            .build();
+        let pdf = self.save_pdf(pdf_params, output).await?;
+
+        Ok(pdf)
+    }
+
+    /// Renders the page as a single PDF page, without saving it
+    ///
+    /// # Note use web2pdf_launch or web2pdf_launch_from_executable_path for correct results
+    /// # Arguments
+    /// * `opts` - The `PrintToPdfParams` to use for rendering the PDF.
+    ///
+    /// # Returns
+    /// A `Result` containing a `Vec<u8>` containing the PDF data or an error.
+    async fn web2pdf_pdf_mono(&self, opts: PrintToPdfParams) -> chromiumoxide::Result<Vec<u8>> {
+        self.web2pdf_pdf_mono_with(opts, MonoOptions::default())
+            .await
     }
 
     /// Saves the page as a single PDF page
@@ -199,38 +587,160 @@ impl PageWeb2Pdf for Page {
     /// # Returns
     /// A `Result` containing a `Vec<u8>` containing the PDF data or an error.
     /// (The Page is already saved as PDF at the specified path)
-    fn web2pdf_save_pdf_mono(
+    async fn web2pdf_save_pdf_mono(
         &self,
-        mut opts: PrintToPdfParams,
+        opts: PrintToPdfParams,
         output: impl AsRef<Path> + Send,
-    ) -> impl Future<Output = chromiumoxide::Result<Vec<u8>>> + Send {
-        async move {
-            let layout = self.layout_metrics().await?;
-
-            opts.scale = None;
-            opts.landscape = Some(false);
-
-            // See: https://developer.mozilla.org/en-US/docs/Web/CSS/length#absolute_length_units
-            opts.paper_height = Some(
-                (layout.css_content_size.height / 96.0)
-                    + opts.margin_top.unwrap_or(0.4)
-                    + opts.margin_bottom.unwrap_or(0.4),
-            );
-            opts.paper_width = Some(
-                (layout.css_content_size.width / 96.0)
-                    + opts.margin_left.unwrap_or(0.4)
-                    + opts.margin_right.unwrap_or(0.4),
-            );
+    ) -> chromiumoxide::Result<Vec<u8>> {
+        let pdf = self.web2pdf_pdf_mono(opts).await?;
+        fs::write(output.as_ref(), &pdf).await?;
+
+        Ok(pdf)
+    }
+
+    /// Captures a screenshot of the whole page, not only the visible viewport, without saving it
+    ///
+    /// # Arguments
+    /// * `format` - The image format, e.g. png or jpeg
+    /// * `quality` - The compression quality from 0 to 100 (jpeg and webp only)
+    ///
+    /// # Returns
+    /// A `Result` containing a `Vec<u8>` containing the image data or an error.
+    async fn web2pdf_screenshot_fullpage(
+        &self,
+        format: CaptureScreenshotFormat,
+        quality: Option<i64>,
+    ) -> Result<Vec<u8>> {
+        let layout = self.layout_metrics().await?;
+
+        let mut params = CaptureScreenshotParams::builder()
+            .format(format)
+            .capture_beyond_viewport(true)
+            .clip(cdp_page::Viewport {
+                x: 0.0,
+                y: 0.0,
+                width: layout.css_content_size.width,
+                height: layout.css_content_size.height,
+                scale: 1.0,
+            });
+        if let Some(quality) = quality {
+            params = params.quality(quality);
+        }
+
+        tracing::trace!("Web2Pdf full page screenshot layout: {:?}", layout);
+
+        let screenshot = self.execute(params.build()).await?;
+        Ok(BASE64_STANDARD.decode(&screenshot.result.data)?)
+    }
+
+    /// Saves a screenshot of the whole page, not only the visible viewport
+    ///
+    /// # Arguments
+    /// * `format` - The image format, e.g. png or jpeg
+    /// * `quality` - The compression quality from 0 to 100 (jpeg and webp only)
+    /// * `output` - The path to save the image to.
+    ///
+    /// # Returns
+    /// A `Result` containing a `Vec<u8>` containing the image data or an error.
+    /// (The screenshot is already saved at the specified path)
+    async fn web2pdf_save_screenshot_fullpage(
+        &self,
+        format: CaptureScreenshotFormat,
+        quality: Option<i64>,
+        output: impl AsRef<Path> + Send,
+    ) -> Result<Vec<u8>> {
+        let screenshot = self.web2pdf_screenshot_fullpage(format, quality).await?;
+        fs::write(output.as_ref(), &screenshot).await?;
 
-            // Some websites force a second (empty) page due to their CSS
-            opts.page_ranges = Some("1".to_string());
+        Ok(screenshot)
+    }
 
-            tracing::trace!("Web2Pdf mono page layout: {:?}", layout);
+    /// Captures an MHTML snapshot of the page (a single file web archive), without saving it
+    ///
+    /// # Returns
+    /// A `Result` containing the MHTML data or an error.
+    async fn web2pdf_mhtml(&self) -> Result<String> {
+        let snapshot = self
+            .execute(
+                CaptureSnapshotParams::builder()
+                    .format(CaptureSnapshotFormat::Mhtml)
+                    .build(),
+            )
+            .await?;
 
-            let pdf = self.save_pdf(opts, output).await?;
+        Ok(snapshot.result.data)
+    }
 
-            Ok(pdf)
+    /// Saves an MHTML snapshot of the page (a single file web archive)
+    ///
+    /// # Arguments
+    /// * `output` - The path to save the MHTML file to.
+    ///
+    /// # Returns
+    /// A `Result` containing the MHTML data or an error.
+    /// (The snapshot is already saved at the specified path)
+    async fn web2pdf_save_mhtml(&self, output: impl AsRef<Path> + Send) -> Result<String> {
+        let mhtml = self.web2pdf_mhtml().await?;
+        fs::write(output.as_ref(), &mhtml).await?;
+
+        Ok(mhtml)
+    }
+
+    /// Serializes the rendered page into a self-contained HTML file, without saving it
+    /// Stylesheets, images and fonts are inlined, scripts are removed.
+    ///
+    /// # Returns
+    /// A `Result` containing the HTML or an error.
+    async fn web2pdf_single_file_html(&self) -> Result<String> {
+        let resources = single_file::collect_resources(self).await?;
+        let html: String = self
+            .evaluate_expression(format!("{}({})", single_file::SINGLE_FILE_JS, resources))
+            .await?
+            .into_value()?;
+
+        Ok(html)
+    }
+
+    /// Saves the rendered page as a self-contained HTML file
+    /// Stylesheets, images and fonts are inlined, scripts are removed.
+    ///
+    /// # Arguments
+    /// * `output` - The path to save the HTML file to.
+    ///
+    /// # Returns
+    /// A `Result` containing the HTML or an error.
+    /// (The HTML is already saved at the specified path)
+    async fn web2pdf_save_single_file_html(
+        &self,
+        output: impl AsRef<Path> + Send,
+    ) -> Result<String> {
+        let html = self.web2pdf_single_file_html().await?;
+        fs::write(output.as_ref(), &html).await?;
+
+        Ok(html)
+    }
+
+    /// Detects the language of the page
+    ///
+    /// Uses the declared language (`lang` attribute, `Content-Language` or `og:locale` meta tags)
+    /// and falls back to guessing the language from the rendered text.
+    ///
+    /// # Returns
+    /// A `Result` containing the detected BCP 47 language tag, if any, or an error.
+    async fn web2pdf_detect_language(&self) -> Result<Option<String>> {
+        let (declared, text): (Option<String>, String) = self
+            .evaluate(lang::DETECT_LANGUAGE_JS)
+            .await?
+            .into_value()?;
+
+        if let Some(lang) = declared.as_deref().and_then(lang::normalize_language_tag) {
+            tracing::debug!("Web2Pdf page declares language: {}", lang);
+            return Ok(Some(lang));
         }
+
+        let guess = lang::guess_language(&text).map(String::from);
+        tracing::debug!("Web2Pdf guessed page language: {:?}", guess);
+        Ok(guess)
     }
 
     /// Saves the page as a single PDF page
@@ -242,18 +752,867 @@ impl PageWeb2Pdf for Page {
     /// # Returns
     /// A `Result` containing a `Vec<u8>` containing the PDF data or an error.
     /// (The Page is already saved as PDF at the specified path)
-    fn web2pdf_save_pdf_mono_standard(
+    async fn web2pdf_save_pdf_mono_standard(
         &self,
         output: impl AsRef<Path> + Send,
-    ) -> impl Future<Output = chromiumoxide::Result<Vec<u8>>> + Send {
-        async move {
-            let opts = PrintToPdfParams::builder()
-                .print_background(true)
-                .prefer_css_page_size(true)
-                .build();
-
-            self.web2pdf_save_pdf_mono(opts, output).await
+    ) -> chromiumoxide::Result<Vec<u8>> {
+        let opts = PrintToPdfParams::builder()
+            .print_background(true)
+            .prefer_css_page_size(true)
+            .build();
+
+        self.web2pdf_save_pdf_mono(opts, output).await
+    }
+
+    /// Removes all elements matching a CSS selector from the DOM
+    ///
+    /// # Arguments
+    /// * `selector` - The CSS selector (list) of the elements to remove, e.g. ".cookie-banner, nav"
+    ///
+    /// # Returns
+    /// A `Result` containing the number of removed elements or an error.
+    async fn web2pdf_remove_elements(&self, selector: impl AsRef<str> + Send) -> Result<u64> {
+        let removed: u64 = self
+            .evaluate(format!(
+                "(() => {{ const elements = document.querySelectorAll({}); elements.forEach(e => e.remove()); return elements.length; }})()",
+                serde_json::to_string(selector.as_ref())?
+            ))
+            .await?
+            .into_value()?;
+
+        tracing::debug!(
+            "Web2Pdf removed {} elements matching {:?}",
+            removed,
+            selector.as_ref()
+        );
+        Ok(removed)
+    }
+
+    /// Overrides the text direction of the document
+    ///
+    /// # Arguments
+    /// * `direction` - The direction to set on the root element
+    ///
+    /// # Returns
+    /// A `Result` containing an empty `()` value or an error.
+    async fn web2pdf_set_direction(&self, direction: TextDirection) -> Result<()> {
+        self.evaluate(format!(
+            "document.documentElement.setAttribute('dir', '{}')",
+            direction
+        ))
+        .await?;
+        tracing::debug!("Web2Pdf set document direction to {}", direction);
+        Ok(())
+    }
+
+    /// Checks whether a page in a right-to-left language is rendered with a left-to-right layout
+    ///
+    /// # Returns
+    /// A `Result` containing a list of warnings (empty if the layout looks fine or the page is
+    /// not in a right-to-left language) or an error.
+    async fn web2pdf_check_rtl_layout(&self) -> Result<Vec<String>> {
+        let (lang, direction, rtl_blocks, ltr_blocks, left_aligned, overflow): (
+            Option<String>,
+            String,
+            u64,
+            u64,
+            u64,
+            bool,
+        ) = self.evaluate(lang::RTL_LAYOUT_JS).await?.into_value()?;
+
+        let lang = match lang.as_deref().and_then(lang::normalize_language_tag) {
+            Some(lang) => Some(lang),
+            None => self.web2pdf_detect_language().await?,
+        };
+        let lang = match lang {
+            Some(lang) if lang::is_rtl_language(&lang) => lang,
+            _ => return Ok(Vec::new()),
+        };
+
+        let mut warnings = Vec::new();
+        if direction == "ltr" {
+            warnings.push(format!(
+                "Document language is '{}' but the document direction is ltr",
+                lang
+            ));
         }
+        if ltr_blocks * 3 > rtl_blocks {
+            warnings.push(format!(
+                "{} of {} right-to-left text blocks are laid out left-to-right",
+                ltr_blocks, rtl_blocks
+            ));
+        }
+        if left_aligned * 3 > rtl_blocks {
+            warnings.push(format!(
+                "{} of {} right-to-left text blocks are aligned to the left (mirrored margins)",
+                left_aligned, rtl_blocks
+            ));
+        }
+        if overflow {
+            warnings.push(String::from(
+                "Page overflows horizontally, which often indicates a mirrored layout",
+            ));
+        }
+
+        Ok(warnings)
+    }
+
+    /// Dismisses cookie consent banners using a bundled ruleset of common consent managers
+    /// Prefers rejecting over accepting, remaining banners are removed from the DOM.
+    ///
+    /// # Returns
+    /// A `Result` containing the number of clicked or removed elements or an error.
+    async fn web2pdf_dismiss_cookie_banners(&self) -> Result<u64> {
+        let actions: u64 = self
+            .evaluate(consent::DISMISS_COOKIE_BANNERS_JS)
+            .await?
+            .into_value()?;
+
+        if actions > 0 {
+            // Give the page some time to hide the banner and reflow
+            tokio::time::sleep(Duration::from_millis(500)).await;
+        }
+
+        tracing::debug!("Web2Pdf dismissed cookie banners with {} actions", actions);
+        Ok(actions)
+    }
+
+    /// Emulates a CSS media type together with media features (e.g. `prefers-color-scheme`)
+    /// Both are set at once, as setting only one of them resets the other.
+    ///
+    /// # Arguments
+    /// * `media_type` - The media type to emulate, e.g. `MediaTypeParams::Print`
+    /// * `features` - The media features to emulate
+    ///
+    /// # Returns
+    /// A `Result` containing an empty `()` value or an error.
+    async fn web2pdf_emulate_media(
+        &self,
+        media_type: MediaTypeParams,
+        features: Vec<MediaFeature>,
+    ) -> Result<()> {
+        tracing::debug!(
+            "Web2Pdf emulating media {:?} with features {:?}",
+            media_type,
+            features
+        );
+        self.execute(
+            SetEmulatedMediaParams::builder()
+                .media(media_type)
+                .features(features)
+                .build(),
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Overrides the User-Agent used for requests and reported by `navigator.userAgent`
+    /// Call this before navigating, so the page is loaded with the new User-Agent.
+    ///
+    /// # Arguments
+    /// * `user_agent` - The User-Agent string
+    ///
+    /// # Returns
+    /// A `Result` containing an empty `()` value or an error.
+    async fn web2pdf_set_user_agent(&self, user_agent: impl Into<String> + Send) -> Result<()> {
+        let user_agent = user_agent.into();
+        tracing::debug!("Web2Pdf setting user agent to {:?}", user_agent);
+        self.execute(SetUserAgentOverrideParams::new(user_agent))
+            .await?;
+        Ok(())
+    }
+
+    /// Overrides the width of the viewport, e.g. to render a page at a responsive breakpoint
+    /// The height of the viewport is kept. Call this before navigating, as some pages only
+    /// evaluate their width while loading.
+    ///
+    /// # Arguments
+    /// * `width` - The width of the viewport in CSS pixels
+    ///
+    /// # Returns
+    /// A `Result` containing an empty `()` value or an error.
+    async fn web2pdf_set_viewport_width(&self, width: u32) -> Result<()> {
+        let height = self
+            .layout_metrics()
+            .await?
+            .css_layout_viewport
+            .client_height;
+        tracing::debug!("Web2Pdf setting viewport to {}x{}", width, height);
+        // A device scale factor of 0 keeps the one of the browser
+        self.execute(SetDeviceMetricsOverrideParams::new(
+            i64::from(width),
+            height,
+            0.0,
+            false,
+        ))
+        .await?;
+        Ok(())
+    }
+
+    /// Sends additional HTTP headers with every request of the page
+    /// Headers given multiple times are combined into a comma separated list.
+    ///
+    /// # Arguments
+    /// * `headers` - Name-value pairs of the headers
+    ///
+    /// # Returns
+    /// A `Result` containing an empty `()` value or an error.
+    async fn web2pdf_set_extra_headers(&self, headers: &[(String, String)]) -> Result<()> {
+        let mut map = serde_json::Map::new();
+        for (name, value) in headers {
+            let value = match map.get(name).and_then(|v| v.as_str()) {
+                Some(previous) => format!("{}, {}", previous, value),
+                None => value.clone(),
+            };
+            map.insert(name.clone(), serde_json::Value::String(value));
+        }
+
+        tracing::debug!("Web2Pdf setting extra HTTP headers: {:?}", map.keys());
+        self.execute(SetExtraHttpHeadersParams::new(Headers::new(map)))
+            .await?;
+        Ok(())
+    }
+
+    /// Intercepts all requests of the page, e.g. to answer HTTP authentication challenges
+    /// Call this before navigating.
+    ///
+    /// # Arguments
+    /// * `config` - The interception configuration
+    ///
+    /// # Returns
+    /// A `Result` containing an empty `()` value or an error.
+    async fn web2pdf_intercept(&self, config: InterceptConfig) -> Result<()> {
+        intercept::enable(self, config).await
+    }
+
+    /// Navigates to a render-optimized view of a GitHub or GitLab URL
+    /// Source files and diffs are fetched raw and rendered with line numbers,
+    /// documents (e.g. READMEs) are shown without the surrounding web UI.
+    ///
+    /// # Arguments
+    /// * `view` - The view, see `CodeView::resolve`
+    /// * `theme` - The color theme
+    ///
+    /// # Returns
+    /// A `Result` containing an empty `()` value or an error.
+    async fn web2pdf_goto_code_view(&self, view: &CodeView, theme: CodeTheme) -> Result<()> {
+        let html = match view {
+            CodeView::Document { url, selector } => {
+                self.goto(url).await?;
+                self.web2pdf_wait_for_selector(selector, Duration::from_secs(10))
+                    .await?;
+                let isolated: bool = self
+                    .evaluate_expression(format!(
+                        "{}({}, {})",
+                        code_view::ISOLATE_ELEMENT_JS,
+                        serde_json::to_string(selector)?,
+                        theme == CodeTheme::Dark
+                    ))
+                    .await?
+                    .into_value()?;
+                if !isolated {
+                    tracing::warn!("Web2Pdf code view found no document in {}", url);
+                }
+                return Ok(());
+            }
+            CodeView::Source { raw_url, file_name } => {
+                self.goto(raw_url).await?;
+                code_view::render_source(file_name, &self.web2pdf_text_content().await?, theme)
+            }
+            CodeView::Diff { diff_url, title } => {
+                self.goto(diff_url).await?;
+                code_view::render_diff(title, &self.web2pdf_text_content().await?, theme)
+            }
+        };
+
+        self.web2pdf_set_content(html).await?;
+        tracing::debug!("Web2Pdf rendered code view of {:?}", view);
+        Ok(())
+    }
+
+    /// The text content of the page, e.g. of a plain text response
+    ///
+    /// # Returns
+    /// A `Result` containing the text or an error.
+    async fn web2pdf_text_content(&self) -> Result<String> {
+        let text: String = self
+            .evaluate("(() => { const pre = document.querySelector('body > pre'); return pre ? pre.textContent : document.documentElement.textContent; })()")
+            .await?
+            .into_value()?;
+        Ok(text)
+    }
+
+    /// Hides all elements matching any of the CSS selectors using a stylesheet
+    /// Invalid selectors are ignored.
+    ///
+    /// # Arguments
+    /// * `selectors` - The CSS selectors of the elements to hide
+    ///
+    /// # Returns
+    /// A `Result` containing an empty `()` value or an error.
+    async fn web2pdf_hide_elements(&self, selectors: &[&str]) -> Result<()> {
+        if selectors.is_empty() {
+            return Ok(());
+        }
+        // One rule per selector, so an invalid selector does not invalidate the others
+        let css: String = selectors
+            .iter()
+            .map(|selector| format!("{} {{ display: none !important; }}\n", selector))
+            .collect();
+        self.web2pdf_add_style(&css).await?;
+
+        tracing::debug!("Web2Pdf hid elements of {} selectors", selectors.len());
+        Ok(())
+    }
+
+    /// Adds a stylesheet to the document
+    ///
+    /// # Arguments
+    /// * `css` - The CSS to add
+    ///
+    /// # Returns
+    /// A `Result` containing an empty `()` value or an error.
+    async fn web2pdf_add_style(&self, css: &str) -> Result<()> {
+        self.evaluate(format!(
+            "(() => {{ const style = document.createElement('style'); style.textContent = {}; (document.head || document.documentElement).appendChild(style); }})()",
+            serde_json::to_string(css)?
+        ))
+        .await?;
+        Ok(())
+    }
+
+    /// Adds a script that is evaluated in every new document before any of its own scripts
+    /// Call this before navigating.
+    ///
+    /// # Arguments
+    /// * `script` - The Javascript source
+    ///
+    /// # Returns
+    /// A `Result` containing an empty `()` value or an error.
+    async fn web2pdf_add_init_script(&self, script: impl Into<String> + Send) -> Result<()> {
+        self.execute(AddScriptToEvaluateOnNewDocumentParams::new(script))
+            .await?;
+        Ok(())
+    }
+
+    /// Waits until an element matching a CSS selector exists
+    ///
+    /// # Arguments
+    /// * `selector` - The CSS selector to wait for
+    /// * `timeout` - The maximum time to wait
+    ///
+    /// # Returns
+    /// A `Result` containing whether a matching element was found before the timeout or an error.
+    async fn web2pdf_wait_for_selector(
+        &self,
+        selector: impl AsRef<str> + Send,
+        timeout: Duration,
+    ) -> Result<bool> {
+        let found: bool = self
+            .evaluate(format!(
+                "new Promise(resolve => {{ const end = Date.now() + {}; const check = () => {{ if (document.querySelector({})) resolve(true); else if (Date.now() > end) resolve(false); else setTimeout(check, 100); }}; check(); }})",
+                timeout.as_millis(),
+                serde_json::to_string(selector.as_ref())?
+            ))
+            .await?
+            .into_value()?;
+
+        tracing::debug!(
+            "Web2Pdf waited for {:?}, found: {}",
+            selector.as_ref(),
+            found
+        );
+        Ok(found)
+    }
+
+    /// Collects the h1–h3 headings of the page for the PDF outline
+    /// Call right before printing, see `pdf::add_outline`.
+    ///
+    /// # Returns
+    /// A `Result` containing the headings in document order or an error.
+    async fn web2pdf_collect_headings(&self) -> Result<Vec<Heading>> {
+        let headings: Vec<Heading> = self
+            .evaluate(outline::COLLECT_HEADINGS_JS)
+            .await?
+            .into_value()?;
+        tracing::debug!("Web2Pdf collected {} headings", headings.len());
+        Ok(headings)
+    }
+
+    /// Loads a resource through the browser (with its cookies and proxy) without navigating
+    /// Local files (`file://` URLs) are read directly.
+    ///
+    /// # Arguments
+    /// * `url` - The URL of the resource
+    ///
+    /// # Returns
+    /// A `Result` containing the body of the resource or an error.
+    async fn web2pdf_fetch(&self, url: &str) -> Result<Vec<u8>> {
+        if let Some(path) = url.strip_prefix("file://") {
+            let path = url::Url::parse(url)
+                .ok()
+                .and_then(|url| url.to_file_path().ok())
+                .unwrap_or_else(|| path.into());
+            return Ok(fs::read(path).await?);
+        }
+
+        let frame = self
+            .mainframe()
+            .await?
+            .ok_or("Web2Pdf fetch: page has no main frame")?;
+        let resource = self
+            .execute(
+                LoadNetworkResourceParams::builder()
+                    .frame_id(frame)
+                    .url(url)
+                    .options(LoadNetworkResourceOptions::new(false, true))
+                    .build()?,
+            )
+            .await?
+            .result
+            .resource;
+        let stream = match (resource.success, resource.stream) {
+            (true, Some(stream)) => stream,
+            _ => {
+                return Err(format!(
+                    "Web2Pdf could not load {}: {}",
+                    url,
+                    resource
+                        .net_error_name
+                        .or(resource
+                            .http_status_code
+                            .map(|status| format!("HTTP {}", status)))
+                        .unwrap_or_else(|| String::from("unknown error"))
+                )
+                .into())
+            }
+        };
+
+        let mut body = Vec::new();
+        loop {
+            let chunk = self.execute(ReadParams::new(stream.clone())).await?.result;
+            if chunk.base64_encoded == Some(true) {
+                body.extend(BASE64_STANDARD.decode(chunk.data)?);
+            } else {
+                body.extend(chunk.data.into_bytes());
+            }
+            if chunk.eof {
+                break;
+            }
+        }
+        self.execute(cdp_io::CloseParams::new(stream)).await?;
+
+        tracing::debug!("Web2Pdf fetched {} bytes from {}", body.len(), url);
+        Ok(body)
+    }
+
+    /// Replaces the page with a HTML document
+    /// The document is shown on a blank page, so the origin of its source cannot forbid styles.
+    ///
+    /// # Arguments
+    /// * `html` - The HTML document
+    ///
+    /// # Returns
+    /// A `Result` containing an empty `()` value or an error.
+    async fn web2pdf_set_content(&self, html: impl Into<String> + Send) -> Result<()> {
+        self.goto("about:blank").await?;
+        let frame = self
+            .mainframe()
+            .await?
+            .ok_or("Web2Pdf set content: page has no main frame")?;
+        self.execute(SetDocumentContentParams::new(frame, html))
+            .await?;
+        Ok(())
+    }
+
+    /// Renders a CSV/TSV file as table, whose header is repeated on every page
+    ///
+    /// # Arguments
+    /// * `url` - The URL of the file
+    /// * `delimiter` - The delimiter, see `table::delimiter_for_url`; `,` also accepts `;` separated files
+    ///
+    /// # Returns
+    /// A `Result` containing an empty `()` value or an error.
+    async fn web2pdf_goto_table(&self, url: &str, delimiter: char) -> Result<()> {
+        let body = self.web2pdf_fetch(url).await?;
+        let text = String::from_utf8_lossy(&body);
+        let rows = table::parse_delimited(&text, table::sniff_delimiter(&text, delimiter));
+        let title = util::url_file_name(url).unwrap_or_else(|| url.to_string());
+
+        self.web2pdf_set_content(table::render_table(&title, &rows))
+            .await?;
+        tracing::debug!("Web2Pdf rendered {} rows of {}", rows.len(), url);
+        Ok(())
+    }
+
+    /// Checks whether the table of `web2pdf_goto_table` is too wide for the page without wrapping its cells
+    ///
+    /// # Returns
+    /// A `Result` containing whether the table is wider than the page or an error.
+    async fn web2pdf_table_overflows(&self) -> Result<bool> {
+        Ok(self
+            .evaluate(table::TABLE_OVERFLOWS_JS)
+            .await?
+            .into_value()?)
+    }
+
+    /// The metadata declared by the page, from its `<title>` and meta tags (description, author, keywords)
+    ///
+    /// # Returns
+    /// A `Result` containing the metadata or an error.
+    async fn web2pdf_metadata(&self) -> Result<Metadata> {
+        let metadata: Metadata = self
+            .evaluate(metadata::PAGE_METADATA_JS)
+            .await?
+            .into_value()?;
+        tracing::debug!("Web2Pdf page metadata: {:?}", metadata);
+        Ok(metadata)
+    }
+
+    /// Replaces a JSON or XML document with a pretty-printed, syntax highlighted listing
+    /// Other documents are left unchanged.
+    ///
+    /// # Arguments
+    /// * `theme` - The color theme
+    ///
+    /// # Returns
+    /// A `Result` containing whether the document was JSON or XML or an error.
+    async fn web2pdf_pretty_data(&self, theme: CodeTheme) -> Result<bool> {
+        let (format, data): (Option<DataFormat>, String) = self
+            .evaluate(code_view::PRETTY_DATA_JS)
+            .await?
+            .into_value()?;
+        let Some(format) = format else {
+            return Ok(false);
+        };
+
+        let url = self.url().await?.unwrap_or_default();
+        let title = util::url_file_name(&url).unwrap_or(url);
+        self.web2pdf_set_content(code_view::render_data(&title, &data, format, theme))
+            .await?;
+        tracing::debug!("Web2Pdf pretty-printed {:?} data of {}", format, title);
+        Ok(true)
+    }
+
+    /// Opens a standalone SVG file, sized to its intrinsic dimensions
+    /// Print it with `web2pdf_pdf_mono` and no margins to get a page of exactly that size.
+    ///
+    /// # Arguments
+    /// * `url` - The URL of the SVG file
+    ///
+    /// # Returns
+    /// A `Result` containing an empty `()` value or an error.
+    async fn web2pdf_goto_svg(&self, url: &str) -> Result<()> {
+        let body = self.web2pdf_fetch(url).await?;
+        let svg = String::from_utf8_lossy(&body);
+        self.web2pdf_set_content(svg::render_svg_page(url, &svg))
+            .await?;
+
+        let size: Option<(f64, f64)> = self
+            .evaluate(svg::SVG_INTRINSIC_SIZE_JS)
+            .await?
+            .into_value()?;
+        tracing::debug!("Web2Pdf SVG size of {}: {:?}", url, size);
+        Ok(())
+    }
+
+    /// Steps through a slide deck and renders every slide as one page of the size of the viewport
+    /// reveal.js decks are controlled through their API, other decks with the right arrow key.
+    /// The deck ends when advancing does not change the URL and the rendered slide anymore.
+    ///
+    /// # Note emulate the screen media type beforehand, to capture the slides as shown on screen
+    /// # Arguments
+    /// * `opts` - The `PrintToPdfParams` to use for rendering the slides, size and margins are overridden
+    ///
+    /// # Returns
+    /// A `Result` containing a `Vec<u8>` containing the PDF data or an error.
+    async fn web2pdf_pdf_slides(&self, mut opts: PrintToPdfParams) -> Result<Vec<u8>> {
+        let reveal: bool = self
+            .evaluate(slides::PREPARE_SLIDES_JS)
+            .await?
+            .into_value()?;
+        tokio::time::sleep(slides::SLIDE_TRANSITION_DELAY).await;
+
+        let viewport = self.layout_metrics().await?.css_layout_viewport;
+        opts.scale = None;
+        opts.landscape = Some(false);
+        opts.prefer_css_page_size = Some(false);
+        opts.paper_width = Some(viewport.client_width as f64 / 96.0);
+        opts.paper_height = Some(viewport.client_height as f64 / 96.0);
+        opts.margin_top = Some(0.0);
+        opts.margin_bottom = Some(0.0);
+        opts.margin_left = Some(0.0);
+        opts.margin_right = Some(0.0);
+        opts.page_ranges = Some("1".to_string());
+
+        let mut pages = Vec::new();
+        let mut previous = None;
+        while pages.len() < slides::MAX_SLIDES {
+            pages.push(self.pdf(opts.clone()).await?);
+
+            if reveal {
+                let advanced: bool = self
+                    .evaluate(slides::REVEAL_NEXT_SLIDE_JS)
+                    .await?
+                    .into_value()?;
+                if !advanced {
+                    break;
+                }
+                tokio::time::sleep(slides::SLIDE_TRANSITION_DELAY).await;
+                continue;
+            }
+
+            let position = || async {
+                let url: String = self
+                    .evaluate(slides::SLIDE_POSITION_JS)
+                    .await?
+                    .into_value()?;
+                let screenshot = self
+                    .execute(CaptureScreenshotParams::default())
+                    .await?
+                    .result
+                    .data
+                    .clone();
+                Result::Ok((url, screenshot))
+            };
+            if previous.is_none() {
+                previous = Some(position().await?);
+            }
+            self.web2pdf_press_key("ArrowRight", 39).await?;
+            tokio::time::sleep(slides::SLIDE_TRANSITION_DELAY).await;
+            let current = Some(position().await?);
+            if current == previous {
+                break;
+            }
+            previous = current;
+        }
+
+        tracing::debug!("Web2Pdf captured {} slides", pages.len());
+        pdf::merge(&pages)
+    }
+
+    /// Presses and releases a key, as if typed by the user
+    ///
+    /// # Arguments
+    /// * `key` - The DOM key value, e.g. `ArrowRight`
+    /// * `windows_virtual_key_code` - The Windows virtual key code, e.g. 39 for the right arrow key
+    ///
+    /// # Returns
+    /// A `Result` containing an empty `()` value or an error.
+    async fn web2pdf_press_key(&self, key: &str, windows_virtual_key_code: i64) -> Result<()> {
+        for event_type in [
+            DispatchKeyEventType::RawKeyDown,
+            DispatchKeyEventType::KeyUp,
+        ] {
+            self.execute(
+                DispatchKeyEventParams::builder()
+                    .r#type(event_type)
+                    .key(key)
+                    .code(key)
+                    .windows_virtual_key_code(windows_virtual_key_code)
+                    .native_virtual_key_code(windows_virtual_key_code)
+                    .build()?,
+            )
+            .await?;
+        }
+        Ok(())
+    }
+
+    /// Renders the page as a single PDF page with a minimal or snapped height, without saving it
+    /// See `web2pdf_pdf_mono`, the content is placed at the top of the page.
+    /// With re-measurement passes the viewport is left at the size of the page.
+    ///
+    /// # Note use web2pdf_launch or web2pdf_launch_from_executable_path for correct results
+    /// # Arguments
+    /// * `opts` - The `PrintToPdfParams` to use for rendering the PDF.
+    /// * `mono` - The sizing of the page
+    ///
+    /// # Returns
+    /// A `Result` containing a `Vec<u8>` containing the PDF data or an error.
+    async fn web2pdf_pdf_mono_with(
+        &self,
+        mut opts: PrintToPdfParams,
+        mono: MonoOptions,
+    ) -> chromiumoxide::Result<Vec<u8>> {
+        let mut layout = self.layout_metrics().await?;
+        for pass in 0..mono.passes {
+            let size = &layout.css_content_size;
+            // A device scale factor of 0 keeps the one of the browser
+            self.execute(SetDeviceMetricsOverrideParams::new(
+                size.width.ceil() as i64,
+                size.height.ceil() as i64,
+                0.0,
+                false,
+            ))
+            .await?;
+            tokio::time::sleep(mono::RESIZE_DELAY).await;
+
+            let previous = layout;
+            layout = self.layout_metrics().await?;
+            let (width, height) = (
+                layout.css_content_size.width,
+                layout.css_content_size.height,
+            );
+            tracing::debug!(
+                "Web2Pdf mono pass {} measured {}x{} (was {}x{})",
+                pass + 1,
+                width,
+                height,
+                previous.css_content_size.width,
+                previous.css_content_size.height
+            );
+            if (width - previous.css_content_size.width).abs() < 1.0
+                && (height - previous.css_content_size.height).abs() < 1.0
+            {
+                break;
+            }
+        }
+
+        let mut height = layout.css_content_size.height;
+        if mono.trim {
+            let bottom: Option<f64> = self.evaluate(mono::CONTENT_BOTTOM_JS).await?.into_value()?;
+            if let Some(bottom) = bottom.filter(|bottom| *bottom < height) {
+                tracing::debug!("Web2Pdf mono trimming height {} to {}", height, bottom);
+                height = bottom;
+            }
+        }
+
+        opts.scale = None;
+        opts.landscape = Some(false);
+
+        // See: https://developer.mozilla.org/en-US/docs/Web/CSS/length#absolute_length_units
+        opts.paper_height = Some(mono.paper_height(
+            (height / 96.0) + opts.margin_top.unwrap_or(0.4) + opts.margin_bottom.unwrap_or(0.4),
+        ));
+        opts.paper_width = Some(
+            (layout.css_content_size.width / 96.0)
+                + opts.margin_left.unwrap_or(0.4)
+                + opts.margin_right.unwrap_or(0.4),
+        );
+
+        // Some websites force a second (empty) page due to their CSS
+        opts.page_ranges = Some("1".to_string());
+
+        tracing::trace!("Web2Pdf mono page layout: {:?}", layout);
+
+        self.pdf(opts).await
+    }
+
+    /// Hashes the text content of the page, see `util::content_hash`
+    ///
+    /// # Returns
+    /// A `Result` containing the hex encoded hash or an error.
+    async fn web2pdf_content_hash(&self) -> Result<String> {
+        let text: String = self.evaluate(util::CONTENT_TEXT_JS).await?.into_value()?;
+        Ok(util::content_hash(&text))
+    }
+
+    /// Limits the download and upload throughput of the page
+    /// Call this before navigating, so the whole load is throttled.
+    ///
+    /// # Arguments
+    /// * `bytes_per_second` - The maximum throughput in each direction
+    ///
+    /// # Returns
+    /// A `Result` containing an empty `()` value or an error.
+    async fn web2pdf_limit_bandwidth(&self, bytes_per_second: f64) -> Result<()> {
+        tracing::debug!(
+            "Web2Pdf limiting bandwidth to {:.0} bytes/s",
+            bytes_per_second
+        );
+        self.execute(EmulateNetworkConditionsParams::new(
+            false,
+            0.0,
+            bytes_per_second,
+            bytes_per_second,
+        ))
+        .await?;
+        Ok(())
+    }
+
+    /// Blocks the third-party cookies of the page, without exceptions
+    /// Call this before navigating, as the page has to be loaded again to see the change.
+    ///
+    /// # Returns
+    /// A `Result` containing whether the browser supports it (Chrome 132+) or an error.
+    async fn web2pdf_block_third_party_cookies(&self) -> Result<bool> {
+        tracing::debug!("Web2Pdf blocking third-party cookies");
+        let params = SetCookieControlsParams {
+            enable_third_party_cookie_restriction: true,
+            disable_third_party_cookie_metadata: true,
+            disable_third_party_cookie_heuristics: true,
+        };
+        match self.execute(params).await {
+            Ok(_) => Ok(true),
+            // Older browsers don't know the command ("method not found")
+            Err(chromiumoxide::error::CdpError::Chrome(error)) if error.code == -32601 => Ok(false),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Emulates the language, number and date formats and timezone of a locale
+    /// Sets the Accept-Language header, `navigator.language`, the locale of `Intl` and the timezone.
+    /// Call this before navigating and after `web2pdf_set_user_agent`, as the User-Agent is kept.
+    ///
+    /// # Arguments
+    /// * `profile` - The locale settings, see `LangProfile::resolve`
+    ///
+    /// # Returns
+    /// A `Result` containing an empty `()` value or an error.
+    async fn web2pdf_set_lang_profile(&self, profile: &LangProfile) -> Result<()> {
+        tracing::debug!("Web2Pdf setting language profile {:?}", profile);
+        self.web2pdf_set_accept_language(&profile.accept_language)
+            .await?;
+        self.execute(
+            SetLocaleOverrideParams::builder()
+                .locale(&profile.locale)
+                .build(),
+        )
+        .await?;
+        self.execute(SetTimezoneOverrideParams::new(&profile.timezone))
+            .await?;
+        Ok(())
+    }
+
+    /// Sets the Accept-Language header and `navigator.languages`, e.g. "de-DE,de;q=0.9"
+    /// Call this before navigating and after `web2pdf_set_user_agent`, as the User-Agent is kept.
+    ///
+    /// # Arguments
+    /// * `accept_language` - The value of the Accept-Language header
+    ///
+    /// # Returns
+    /// A `Result` containing an empty `()` value or an error.
+    async fn web2pdf_set_accept_language(
+        &self,
+        accept_language: impl Into<String> + Send,
+    ) -> Result<()> {
+        let accept_language = accept_language.into();
+        tracing::debug!("Web2Pdf setting Accept-Language to {:?}", accept_language);
+        // The Accept-Language can only be overridden together with the User-Agent
+        let user_agent: String = self.evaluate("navigator.userAgent").await?.into_value()?;
+        self.execute(
+            SetUserAgentOverrideParams::builder()
+                .user_agent(user_agent)
+                .accept_language(accept_language)
+                .build()?,
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Collects the absolute URLs of all links of the page, without their fragments
+    ///
+    /// # Returns
+    /// A `Result` containing the distinct http(s) and file URLs in document order or an error.
+    async fn web2pdf_links(&self) -> Result<Vec<String>> {
+        let links: Vec<String> = self
+            .evaluate(
+                "(() => { const links = new Set(); for (const link of document.querySelectorAll('a[href], area[href]')) { try { const url = new URL(link.href, document.baseURI); if (['http:', 'https:', 'file:'].includes(url.protocol)) { url.hash = ''; links.add(url.href); } } catch (e) {} } return [...links]; })()",
+            )
+            .await?
+            .into_value()?;
+        tracing::debug!("Web2Pdf collected {} links", links.len());
+        Ok(links)
     }
 }
 
@@ -261,9 +1620,25 @@ impl ViewportWeb2Pdf for Viewport {
     // Use standard a4 paper size as page size minus default border (8.268-2*0.4 x 11.693-2*0.4 (inches) * 96 (dpi))
     // See: https://developer.mozilla.org/en-US/docs/Web/CSS/length#absolute_length_units
     fn web2pdf_viewport() -> Viewport {
+        let (width, height) = PaperSize::A4.inches();
+        Self::web2pdf_paper_viewport(
+            width - 2.0 * paper::DEFAULT_MARGIN,
+            height - 2.0 * paper::DEFAULT_MARGIN,
+        )
+    }
+
+    /// Creates a viewport with the size of the printable area of a page
+    ///
+    /// # Arguments
+    /// * `width` - The width of the paper minus the left and right margin, in inches
+    /// * `height` - The height of the paper minus the top and bottom margin, in inches
+    ///
+    /// # Returns
+    /// The viewport, at 96 CSS pixels per inch
+    fn web2pdf_paper_viewport(width: f64, height: f64) -> Viewport {
         Viewport {
-            width: 717,
-            height: 1046,
+            width: (width * 96.0).round() as u32,
+            height: (height * 96.0).round() as u32,
             device_scale_factor: Some(1.0),
             emulating_mobile: false,
             is_landscape: false,
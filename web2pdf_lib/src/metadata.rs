@@ -0,0 +1,51 @@
+use serde::Deserialize;
+
+/// Document metadata written into the Info dictionary and XMP metadata of a PDF
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+pub struct Metadata {
+    pub title: Option<String>,
+    pub author: Option<String>,
+    pub subject: Option<String>,
+    pub keywords: Option<String>,
+}
+
+impl Metadata {
+    /// Whether no field is set
+    pub fn is_empty(&self) -> bool {
+        self == &Metadata::default()
+    }
+
+    /// Fills all unset fields from another metadata, e.g. explicit values with those of the page
+    ///
+    /// # Arguments
+    /// * `other` - The metadata used for unset fields
+    pub fn or(self, other: Metadata) -> Metadata {
+        Metadata {
+            title: self.title.or(other.title),
+            author: self.author.or(other.author),
+            subject: self.subject.or(other.subject),
+            keywords: self.keywords.or(other.keywords),
+        }
+    }
+}
+
+/// Javascript returning the metadata declared by the page (`<title>` and meta tags)
+pub const PAGE_METADATA_JS: &str = r#"(() => {
+    const meta = (...selectors) => {
+        for (const selector of selectors) {
+            const element = document.querySelector(selector);
+            const content = element && element.getAttribute('content');
+            if (content && content.trim()) return content.trim();
+        }
+        return null;
+    };
+    const tags = Array.from(document.querySelectorAll('meta[property="article:tag"]'))
+        .map(tag => (tag.getAttribute('content') || '').trim())
+        .filter(tag => tag);
+    return {
+        title: document.title.trim() || meta('meta[property="og:title"]', 'meta[name="twitter:title"]'),
+        author: meta('meta[name="author" i]', 'meta[property="article:author"]', 'meta[name="dc.creator" i]'),
+        subject: meta('meta[name="description" i]', 'meta[property="og:description"]', 'meta[name="dc.description" i]'),
+        keywords: meta('meta[name="keywords" i]') || (tags.length ? tags.join(', ') : null),
+    };
+})()"#;
@@ -0,0 +1,85 @@
+use std::time::Duration;
+
+/// Height of an A4 page in inches (297 mm)
+pub const A4_HEIGHT: f64 = 297.0 / 25.4;
+
+/// Time given to a page to react to a resized viewport before it is measured again
+pub const RESIZE_DELAY: Duration = Duration::from_millis(100);
+
+/// Sizing of single page (mono) PDFs, see `PageWeb2Pdf::web2pdf_pdf_mono_with`
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MonoOptions {
+    /// Minimal paper height in inches, so very short pages don't produce tiny PDFs
+    pub min_height: Option<f64>,
+    /// Rounds the paper height up to a whole multiple of the A4 height
+    pub snap_a4: bool,
+    /// Maximal number of re-measurements after resizing the viewport to the measured size
+    /// The printed page may reflow (e.g. units relative to the viewport, media queries), so the
+    /// viewport is resized to the page until its size is stable. 0 measures once.
+    pub passes: usize,
+    /// Clamps the height to the bottom of the visible content, see `CONTENT_BOTTOM_JS`
+    pub trim: bool,
+}
+
+impl MonoOptions {
+    /// The paper height for content of a given height
+    ///
+    /// # Arguments
+    /// * `height` - The height of the content including the margins, in inches
+    ///
+    /// # Returns
+    /// * The paper height in inches
+    pub fn paper_height(&self, height: f64) -> f64 {
+        let height = self
+            .min_height
+            .map_or(height, |min_height| height.max(min_height));
+        if self.snap_a4 {
+            // Tolerates rounding errors of heights that already are a multiple
+            (height / A4_HEIGHT - 1e-6).ceil().max(1.0) * A4_HEIGHT
+        } else {
+            height
+        }
+    }
+}
+
+/// Javascript returning the bottom of the visible content in CSS pixels, relative to the document
+///
+/// Only text, replaced elements (images, videos, canvases, ...) and elements with a background or
+/// border count as content, so empty spacers (e.g. of 100vh) and positioned wrappers that make the
+/// document taller than its content are left out. Returns null for pages without such content.
+pub const CONTENT_BOTTOM_JS: &str = r#"(() => {
+    const replaced = new Set(['IMG', 'SVG', 'VIDEO', 'CANVAS', 'IFRAME', 'EMBED', 'OBJECT', 'INPUT', 'TEXTAREA', 'SELECT', 'BUTTON', 'PICTURE', 'HR']);
+    let bottom = null;
+    const extend = (rect) => {
+        if (rect.width > 0 && rect.height > 0) {
+            bottom = Math.max(bottom ?? 0, rect.bottom + window.scrollY);
+        }
+    };
+    const visible = (element) => {
+        const style = getComputedStyle(element);
+        return style.visibility !== 'hidden' && style.opacity !== '0';
+    };
+    const walker = document.createTreeWalker(document.body ?? document.documentElement, NodeFilter.SHOW_ELEMENT | NodeFilter.SHOW_TEXT);
+    const range = document.createRange();
+    for (let node = walker.currentNode; node; node = walker.nextNode()) {
+        if (node.nodeType === Node.TEXT_NODE) {
+            if (node.textContent.trim() && node.parentElement && visible(node.parentElement)) {
+                range.selectNodeContents(node);
+                extend(range.getBoundingClientRect());
+            }
+            continue;
+        }
+        if (node === document.body || node === document.documentElement || !visible(node)) {
+            continue;
+        }
+        const style = getComputedStyle(node);
+        const background = style.backgroundImage !== 'none'
+            || !/^(transparent|rgba\(.*,\s*0\))$/.test(style.backgroundColor);
+        const border = ['Top', 'Right', 'Bottom', 'Left']
+            .some((side) => style[`border${side}Style`] !== 'none' && parseFloat(style[`border${side}Width`]) > 0);
+        if (replaced.has(node.tagName.toUpperCase()) || background || border) {
+            extend(node.getBoundingClientRect());
+        }
+    }
+    return bottom === null ? null : Math.ceil(bottom);
+})()"#;
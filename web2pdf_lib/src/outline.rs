@@ -0,0 +1,37 @@
+use serde::Deserialize;
+
+/// A heading of a page, turned into a bookmark of the PDF outline
+#[derive(Debug, Clone, Deserialize)]
+pub struct Heading {
+    /// The heading level, 1 for `<h1>`
+    pub level: u8,
+    pub title: String,
+    /// Name of the destination Chrome creates in the PDF for the heading
+    pub destination: String,
+}
+
+/// Javascript collecting the visible h1–h3 headings of the page
+///
+/// Chrome only writes the position of an element into the PDF (as named destination) if a link
+/// on the page points to it, so a hidden link to every heading is added. Headings without an id
+/// get a generated one.
+pub const COLLECT_HEADINGS_JS: &str = r#"(() => {
+    const headings = [];
+    const links = document.createElement('div');
+    links.id = 'web2pdf-outline-links';
+    links.style.display = 'none';
+    document.querySelectorAll('h1, h2, h3').forEach((heading, index) => {
+        const title = heading.innerText.replace(/\s+/g, ' ').trim();
+        if (!title || heading.getClientRects().length === 0) return;
+        if (!heading.id) heading.id = `web2pdf-heading-${index}`;
+        // Duplicate ids would point to the first element with that id
+        if (document.getElementById(heading.id) !== heading) return;
+        const destination = encodeURIComponent(heading.id);
+        const link = document.createElement('a');
+        link.setAttribute('href', '#' + destination);
+        links.appendChild(link);
+        headings.push({ level: Number(heading.tagName[1]), title, destination });
+    });
+    if (document.body) document.body.appendChild(links);
+    return headings;
+})()"#;
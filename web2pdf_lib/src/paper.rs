@@ -0,0 +1,72 @@
+//! Standard paper sizes, see `ViewportWeb2Pdf::web2pdf_paper_viewport` for a matching viewport
+
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+/// The default margin of the browser on each side in inches, used for the default viewport
+pub const DEFAULT_MARGIN: f64 = 0.4;
+
+/// A standard paper size
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PaperSize {
+    A3,
+    A4,
+    A5,
+    Letter,
+    Legal,
+}
+impl PaperSize {
+    /// All paper sizes
+    pub const ALL: [PaperSize; 5] = [
+        PaperSize::A3,
+        PaperSize::A4,
+        PaperSize::A5,
+        PaperSize::Letter,
+        PaperSize::Legal,
+    ];
+
+    /// Width and height in portrait orientation, in inches
+    pub fn inches(&self) -> (f64, f64) {
+        const MM_PER_INCH: f64 = 25.4;
+        match self {
+            PaperSize::A3 => (297.0 / MM_PER_INCH, 420.0 / MM_PER_INCH),
+            PaperSize::A4 => (210.0 / MM_PER_INCH, 297.0 / MM_PER_INCH),
+            PaperSize::A5 => (148.0 / MM_PER_INCH, 210.0 / MM_PER_INCH),
+            PaperSize::Letter => (8.5, 11.0),
+            PaperSize::Legal => (8.5, 14.0),
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PaperSize::A3 => "a3",
+            PaperSize::A4 => "a4",
+            PaperSize::A5 => "a5",
+            PaperSize::Letter => "letter",
+            PaperSize::Legal => "legal",
+        }
+    }
+}
+impl fmt::Display for PaperSize {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+impl FromStr for PaperSize {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        PaperSize::ALL
+            .into_iter()
+            .find(|paper| paper.as_str().eq_ignore_ascii_case(s))
+            .ok_or_else(|| {
+                format!(
+                    "invalid paper size '{}', expected a3, a4, a5, letter or legal",
+                    s
+                )
+            })
+    }
+}
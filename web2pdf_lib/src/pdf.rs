@@ -0,0 +1,947 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write;
+
+use lopdf::{
+    decode_text_string, dictionary, text_string, Dictionary, Document, Object, ObjectId, Stream,
+};
+
+use crate::code_view::escape_html;
+use crate::metadata::Metadata;
+use crate::outline::Heading;
+use crate::Result;
+
+/// A bookmark of the PDF outline
+struct OutlineItem {
+    title: Object,
+    /// Explicit destination, e.g. `[page /XYZ left top zoom]`
+    destination: Object,
+    children: Vec<OutlineItem>,
+}
+
+/// Sets the document language (`/Lang` entry of the catalog) of a PDF
+/// Used by screen readers and search indexes
+///
+/// # Arguments
+/// * `pdf` - The PDF data
+/// * `lang` - The BCP 47 language tag, e.g. "en-US"
+///
+/// # Returns
+/// * The modified PDF data
+pub fn set_language(pdf: &[u8], lang: &str) -> Result<Vec<u8>> {
+    let mut document = Document::load_mem(pdf)?;
+
+    document
+        .catalog_mut()?
+        .set("Lang", Object::string_literal(lang));
+
+    save_to_vec(&mut document)
+}
+
+/// Reads the document language (`/Lang` entry of the catalog) of a PDF
+///
+/// # Arguments
+/// * `pdf` - The PDF data
+pub fn language(pdf: &[u8]) -> Result<Option<String>> {
+    let document = Document::load_mem(pdf)?;
+    let lang = document
+        .catalog()?
+        .get(b"Lang")
+        .ok()
+        .and_then(|lang| decode_text_string(lang).ok())
+        .filter(|lang| !lang.is_empty());
+    Ok(lang)
+}
+
+/// Sets the document metadata of a PDF
+/// Writes the Info dictionary and an XMP metadata stream (as used by indexers and archives),
+/// fields that are not set keep the values Chrome wrote (e.g. the title).
+///
+/// # Arguments
+/// * `pdf` - The PDF data
+/// * `metadata` - The metadata
+///
+/// # Returns
+/// * The modified PDF data
+pub fn set_metadata(pdf: &[u8], metadata: &Metadata) -> Result<Vec<u8>> {
+    let mut document = Document::load_mem(pdf)?;
+
+    let info_id = match document.trailer.get(b"Info").and_then(Object::as_reference) {
+        Ok(id) => id,
+        Err(_) => {
+            let id = document.add_object(Dictionary::new());
+            document.trailer.set("Info", id);
+            id
+        }
+    };
+    let info = document.get_dictionary_mut(info_id)?;
+    for (key, value) in [
+        ("Title", &metadata.title),
+        ("Author", &metadata.author),
+        ("Subject", &metadata.subject),
+        ("Keywords", &metadata.keywords),
+    ] {
+        if let Some(value) = value {
+            info.set(key, text_string(value));
+        }
+    }
+    write_xmp(&mut document, "")?;
+
+    save_to_vec(&mut document)
+}
+
+/// Writes an XMP metadata stream matching the Info dictionary of a PDF
+///
+/// # Arguments
+/// * `document` - The PDF document
+/// * `extra_properties` - Additional XMP properties, e.g. the PDF/A identification
+pub(crate) fn write_xmp(document: &mut Document, extra_properties: &str) -> Result<()> {
+    let info = document
+        .trailer
+        .get(b"Info")
+        .ok()
+        .and_then(|info| resolve_dictionary(document, info));
+    let field = |key: &[u8]| {
+        info.and_then(|info| info.get(key).ok())
+            .and_then(|value| decode_text_string(value).ok())
+    };
+
+    // Control characters are not allowed in XML
+    let xml = |value: &str| {
+        escape_html(
+            &value
+                .chars()
+                .filter(|c| !c.is_control() || matches!(c, '\t' | '\n' | '\r'))
+                .collect::<String>(),
+        )
+    };
+
+    let mut properties = String::from("<dc:format>application/pdf</dc:format>");
+    if let Some(title) = field(b"Title") {
+        let _ = write!(
+            properties,
+            "<dc:title><rdf:Alt><rdf:li xml:lang=\"x-default\">{}</rdf:li></rdf:Alt></dc:title>",
+            xml(&title)
+        );
+    }
+    if let Some(author) = field(b"Author") {
+        let _ = write!(
+            properties,
+            "<dc:creator><rdf:Seq><rdf:li>{}</rdf:li></rdf:Seq></dc:creator>",
+            xml(&author)
+        );
+    }
+    if let Some(subject) = field(b"Subject") {
+        let _ = write!(
+            properties,
+            "<dc:description><rdf:Alt><rdf:li xml:lang=\"x-default\">{}</rdf:li></rdf:Alt></dc:description>",
+            xml(&subject)
+        );
+    }
+    if let Some(keywords) = field(b"Keywords") {
+        let list: String = keywords
+            .split([',', ';'])
+            .map(str::trim)
+            .filter(|keyword| !keyword.is_empty())
+            .map(|keyword| format!("<rdf:li>{}</rdf:li>", xml(keyword)))
+            .collect();
+        let _ = write!(
+            properties,
+            "<pdf:Keywords>{}</pdf:Keywords><dc:subject><rdf:Bag>{}</rdf:Bag></dc:subject>",
+            xml(&keywords),
+            list
+        );
+    }
+    if let Some(producer) = field(b"Producer") {
+        let _ = write!(
+            properties,
+            "<pdf:Producer>{}</pdf:Producer>",
+            xml(&producer)
+        );
+    }
+    if let Some(creator) = field(b"Creator") {
+        let _ = write!(
+            properties,
+            "<xmp:CreatorTool>{}</xmp:CreatorTool>",
+            xml(&creator)
+        );
+    }
+    if let Some(date) = field(b"CreationDate").as_deref().and_then(xmp_date) {
+        let _ = write!(properties, "<xmp:CreateDate>{}</xmp:CreateDate>", date);
+    }
+    if let Some(date) = field(b"ModDate").as_deref().and_then(xmp_date) {
+        let _ = write!(properties, "<xmp:ModifyDate>{}</xmp:ModifyDate>", date);
+    }
+    properties.push_str(extra_properties);
+
+    let xmp = format!(
+        "<?xpacket begin=\"\u{feff}\" id=\"W5M0MpCehiHzreSzNTczkc9d\"?>\n\
+         <x:xmpmeta xmlns:x=\"adobe:ns:meta/\">\n\
+         <rdf:RDF xmlns:rdf=\"http://www.w3.org/1999/02/22-rdf-syntax-ns#\">\n\
+         <rdf:Description rdf:about=\"\" xmlns:dc=\"http://purl.org/dc/elements/1.1/\" \
+         xmlns:pdf=\"http://ns.adobe.com/pdf/1.3/\" xmlns:xmp=\"http://ns.adobe.com/xap/1.0/\" \
+         xmlns:pdfaid=\"http://www.aiim.org/pdfa/ns/id/\">\n\
+         {}\n\
+         </rdf:Description>\n\
+         </rdf:RDF>\n\
+         </x:xmpmeta>\n\
+         <?xpacket end=\"w\"?>",
+        properties
+    );
+
+    // Metadata streams stay uncompressed, so tools without PDF parser can read them
+    let mut stream = Stream::new(
+        dictionary! { "Type" => "Metadata", "Subtype" => "XML" },
+        xmp.into_bytes(),
+    );
+    stream.allows_compression = false;
+    let metadata_id = document.add_object(stream);
+    document.catalog_mut()?.set("Metadata", metadata_id);
+    Ok(())
+}
+
+/// Converts a PDF date ("D:YYYYMMDDHHmmSSOHH'mm'") into an XMP date ("YYYY-MM-DDTHH:mm:SS+HH:mm")
+fn xmp_date(date: &str) -> Option<String> {
+    let date = date.strip_prefix("D:").unwrap_or(date);
+    let digits = date
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(date.len());
+    let (timestamp, zone) = date.split_at(digits);
+    if timestamp.len() < 4 || timestamp.len() % 2 != 0 {
+        return None;
+    }
+    let part =
+        |start: usize, default: &'static str| timestamp.get(start..start + 2).unwrap_or(default);
+    let mut xmp = format!(
+        "{}-{}-{}T{}:{}:{}",
+        &timestamp[..4],
+        part(4, "01"),
+        part(6, "01"),
+        part(8, "00"),
+        part(10, "00"),
+        part(12, "00")
+    );
+
+    let zone: String = zone.chars().filter(|c| *c != '\'').collect();
+    match zone.as_bytes().first() {
+        Some(b'Z') | None => xmp.push('Z'),
+        Some(b'+' | b'-') if zone.len() >= 3 => {
+            let _ = write!(xmp, "{}:{}", &zone[..3], zone.get(3..5).unwrap_or("00"));
+        }
+        _ => return None,
+    }
+    Some(xmp)
+}
+
+/// Counts the pages of a PDF
+///
+/// # Arguments
+/// * `pdf` - The PDF data
+pub fn page_count(pdf: &[u8]) -> Result<usize> {
+    Ok(Document::load_mem(pdf)?.get_pages().len())
+}
+
+/// Reads the title of a PDF from its Info dictionary
+///
+/// # Arguments
+/// * `pdf` - The PDF data
+pub fn title(pdf: &[u8]) -> Result<Option<String>> {
+    let document = Document::load_mem(pdf)?;
+    let title = document
+        .trailer
+        .get(b"Info")
+        .ok()
+        .and_then(|info| resolve_dictionary(&document, info))
+        .and_then(|info| info.get(b"Title").ok())
+        .and_then(|title| decode_text_string(title).ok())
+        .map(|title| title.trim().to_string())
+        .filter(|title| !title.is_empty());
+    Ok(title)
+}
+
+/// Embeds the source of a PDF (e.g. the MHTML snapshot of the page) as a file attachment
+/// The file is listed in the `EmbeddedFiles` name tree, so PDF viewers show it as attachment,
+/// and associated with the document (`/AF`) with the relationship `Source`.
+/// An existing attachment with the same name is kept, the new one gets a numbered name.
+///
+/// # Arguments
+/// * `pdf` - The PDF data
+/// * `name` - The file name of the attachment, e.g. "example.com.mhtml"
+/// * `mime_type` - The MIME type of the attachment, e.g. "multipart/related"
+/// * `data` - The contents of the attachment
+///
+/// # Returns
+/// * The modified PDF data
+pub fn attach_source(pdf: &[u8], name: &str, mime_type: &str, data: &[u8]) -> Result<Vec<u8>> {
+    let mut document = Document::load_mem(pdf)?;
+
+    let mut file = Stream::new(
+        dictionary! {
+            "Type" => "EmbeddedFile",
+            "Subtype" => Object::Name(mime_type.as_bytes().to_vec()),
+            "Params" => dictionary! {
+                "Size" => data.len() as i64,
+            },
+        },
+        data.to_vec(),
+    );
+    let _ = file.compress();
+    let file_id = document.add_object(file);
+    let file_spec_id = document.add_object(dictionary! {
+        "Type" => "Filespec",
+        "F" => text_string(name),
+        "UF" => text_string(name),
+        "Desc" => text_string("Source of the document"),
+        "AFRelationship" => "Source",
+        "EF" => dictionary! {
+            "F" => file_id,
+            "UF" => file_id,
+        },
+    });
+
+    let mut files = read_embedded_files(&document);
+    files.push((name.to_string(), Object::Reference(file_spec_id)));
+    let mut associated = read_associated_files(&document);
+    associated.push(Object::Reference(file_spec_id));
+    write_embedded_files(&mut document, files, associated)?;
+
+    save_to_vec(&mut document)
+}
+
+/// Page attributes that may be inherited from the page tree instead of being set on the page
+const INHERITABLE_PAGE_KEYS: [&[u8]; 4] = [b"Resources", b"MediaBox", b"CropBox", b"Rotate"];
+
+/// Adds an outline (bookmarks) of the headings of a page to a PDF
+/// Headings are located through the named destinations Chrome creates for link targets,
+/// headings that are not part of the PDF (e.g. excluded by page ranges) are left out.
+///
+/// # Arguments
+/// * `pdf` - The PDF data
+/// * `headings` - The headings of the page, see `PageWeb2Pdf::web2pdf_collect_headings`
+///
+/// # Returns
+/// * The modified PDF data
+pub fn add_outline(pdf: &[u8], headings: &[Heading]) -> Result<Vec<u8>> {
+    let mut document = Document::load_mem(pdf)?;
+    let destinations = named_destinations(&document);
+
+    // Every heading is nested below the last heading with a lower level
+    let mut roots = Vec::new();
+    let mut open: Vec<(u8, OutlineItem)> = Vec::new();
+    for heading in headings {
+        let Some(destination) = destinations.get(heading.destination.as_bytes()) else {
+            continue;
+        };
+        while open
+            .last()
+            .is_some_and(|(level, _)| *level >= heading.level)
+        {
+            let (_, item) = open.pop().unwrap();
+            attach_outline_item(&mut open, &mut roots, item);
+        }
+        open.push((
+            heading.level,
+            OutlineItem {
+                title: text_string(&heading.title),
+                destination: destination.clone(),
+                children: Vec::new(),
+            },
+        ));
+    }
+    while let Some((_, item)) = open.pop() {
+        attach_outline_item(&mut open, &mut roots, item);
+    }
+
+    if roots.is_empty() {
+        return Ok(pdf.to_vec());
+    }
+    write_outline(&mut document, roots)?;
+    save_to_vec(&mut document)
+}
+
+fn attach_outline_item(
+    open: &mut [(u8, OutlineItem)],
+    roots: &mut Vec<OutlineItem>,
+    item: OutlineItem,
+) {
+    match open.last_mut() {
+        Some((_, parent)) => parent.children.push(item),
+        None => roots.push(item),
+    }
+}
+
+/// Concatenates PDFs into a single PDF
+/// The pages keep their order, the document language is taken from the first PDF that has one.
+/// Outlines and named destinations of the source PDFs are dropped, attached files are kept.
+///
+/// # Arguments
+/// * `pdfs` - The PDF data of all documents, in order
+///
+/// # Returns
+/// * The merged PDF data
+pub fn merge(pdfs: &[Vec<u8>]) -> Result<Vec<u8>> {
+    merge_documents(pdfs.iter().map(|pdf| (None, pdf.as_slice())))
+}
+
+/// Concatenates PDFs into a single PDF with one bookmark per document
+/// The outline of every document is nested below its bookmark, otherwise like `merge`.
+///
+/// # Arguments
+/// * `documents` - The bookmark titles and PDF data of all documents, in order
+///
+/// # Returns
+/// * The merged PDF data
+pub fn merge_with_outline(documents: &[(String, Vec<u8>)]) -> Result<Vec<u8>> {
+    merge_documents(
+        documents
+            .iter()
+            .map(|(title, pdf)| (Some(title.as_str()), pdf.as_slice())),
+    )
+}
+
+fn merge_documents<'a>(
+    documents: impl Iterator<Item = (Option<&'a str>, &'a [u8])>,
+) -> Result<Vec<u8>> {
+    let mut merged = Document::with_version("1.7");
+    let mut pages: Vec<(ObjectId, Dictionary)> = Vec::new();
+    let mut outline = Vec::new();
+    let mut lang = None;
+    let mut files = Vec::new();
+    let mut associated = Vec::new();
+
+    for (title, pdf) in documents {
+        let mut document = Document::load_mem(pdf)?;
+        document.renumber_objects_with(merged.max_id + 1);
+        merged.max_id = document.max_id;
+
+        if lang.is_none() {
+            lang = document.catalog()?.get(b"Lang").ok().cloned();
+        }
+        files.extend(read_embedded_files(&document));
+        associated.extend(read_associated_files(&document));
+        let page_ids: Vec<ObjectId> = document.get_pages().into_values().collect();
+        if let (Some(title), Some(first_page)) = (title, page_ids.first()) {
+            outline.push(OutlineItem {
+                title: text_string(title),
+                destination: Object::Array(vec![
+                    Object::Reference(*first_page),
+                    Object::Name(b"Fit".to_vec()),
+                ]),
+                children: read_outline(&document),
+            });
+        }
+        for page_id in page_ids {
+            let mut page = document.get_dictionary(page_id)?.clone();
+            // The page tree of the source document is replaced, so inherited attributes move to the page
+            for key in INHERITABLE_PAGE_KEYS {
+                if !page.has(key) {
+                    if let Some(value) = inherited_attribute(&document, page_id, key) {
+                        page.set(key, value);
+                    }
+                }
+            }
+            pages.push((page_id, page));
+        }
+
+        for (id, object) in document.objects {
+            match object.type_name().unwrap_or(b"") {
+                b"Catalog" | b"Pages" | b"Page" | b"Outlines" | b"Outline" => {}
+                _ => {
+                    merged.objects.insert(id, object);
+                }
+            }
+        }
+    }
+
+    let pages_id = merged.new_object_id();
+    let mut kids = Vec::with_capacity(pages.len());
+    for (page_id, mut page) in pages {
+        page.set("Parent", pages_id);
+        merged.objects.insert(page_id, Object::Dictionary(page));
+        kids.push(Object::Reference(page_id));
+    }
+    let mut page_tree = Dictionary::new();
+    page_tree.set("Type", "Pages");
+    page_tree.set("Count", kids.len() as i64);
+    page_tree.set("Kids", kids);
+    merged
+        .objects
+        .insert(pages_id, Object::Dictionary(page_tree));
+
+    let mut catalog = Dictionary::new();
+    catalog.set("Type", "Catalog");
+    catalog.set("Pages", pages_id);
+    if let Some(lang) = lang {
+        catalog.set("Lang", lang);
+    }
+    let catalog_id = merged.add_object(catalog);
+    merged.trailer.set("Root", catalog_id);
+    if !outline.is_empty() {
+        write_outline(&mut merged, outline)?;
+    }
+    if !files.is_empty() || !associated.is_empty() {
+        write_embedded_files(&mut merged, files, associated)?;
+    }
+
+    // Drops objects only referenced by the outlines of the source documents
+    merged.prune_objects();
+    merged.renumber_objects();
+    merged.compress();
+    save_to_vec(&mut merged)
+}
+
+/// Where a link to a web page is pointed to instead
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LinkTarget {
+    /// A page of the same PDF, 0 for the first page
+    Page(usize),
+    /// Another PDF file, as path relative to the linking PDF
+    File(String),
+}
+
+/// Rewrites links to web pages that are part of the same PDF or were converted to other PDF files
+/// Links are matched without their fragment, e.g. `https://example.com/page#section` matches
+/// `https://example.com/page`.
+///
+/// # Arguments
+/// * `pdf` - The PDF data
+/// * `targets` - The URLs of the web pages with their new targets
+///
+/// # Returns
+/// * The modified PDF data and the number of rewritten links
+pub fn rewrite_links(pdf: &[u8], targets: &[(String, LinkTarget)]) -> Result<(Vec<u8>, usize)> {
+    let mut document = Document::load_mem(pdf)?;
+    let page_ids: Vec<ObjectId> = document.get_pages().into_values().collect();
+    let mut actions_by_url: HashMap<String, Object> = HashMap::new();
+    let actions = targets.iter().filter_map(|(url, target)| {
+        let action = match target {
+            LinkTarget::Page(page) => dictionary! {
+                "S" => "GoTo",
+                "D" => vec![Object::Reference(*page_ids.get(*page)?), "Fit".into()],
+            },
+            LinkTarget::File(path) => dictionary! {
+                "S" => "GoToR",
+                "F" => Object::string_literal(path.as_str()),
+                "D" => vec![0.into(), "Fit".into()],
+            },
+        };
+        Some((link_key(url), Object::Dictionary(action)))
+    });
+    for (url, action) in actions {
+        // The first target of a URL is used
+        actions_by_url.entry(url).or_insert(action);
+    }
+
+    // Links are either indirect objects or inline in the annotations of a page
+    let mut rewrites = Vec::new();
+    for (&id, object) in &document.objects {
+        let Object::Dictionary(dictionary) = object else {
+            continue;
+        };
+        if let Some(action) = link_action(&document, dictionary, &actions_by_url) {
+            rewrites.push((id, None, action));
+        }
+        if let Ok(Object::Array(annotations)) = dictionary.get(b"Annots") {
+            for (index, annotation) in annotations.iter().enumerate() {
+                if let Object::Dictionary(annotation) = annotation {
+                    if let Some(action) = link_action(&document, annotation, &actions_by_url) {
+                        rewrites.push((id, Some(index), action));
+                    }
+                }
+            }
+        }
+    }
+
+    let count = rewrites.len();
+    for (id, index, action) in rewrites {
+        let mut dictionary = document.get_dictionary_mut(id)?;
+        if let Some(index) = index {
+            dictionary = dictionary
+                .get_mut(b"Annots")?
+                .as_array_mut()?
+                .get_mut(index)
+                .ok_or("annotation not found")?
+                .as_dict_mut()?;
+        }
+        dictionary.set("A", action);
+    }
+    Ok((save_to_vec(&mut document)?, count))
+}
+
+/// The new action of a link annotation pointing to one of `targets`
+fn link_action(
+    document: &Document,
+    annotation: &Dictionary,
+    targets: &HashMap<String, Object>,
+) -> Option<Object> {
+    if annotation.get(b"Subtype").and_then(Object::as_name).ok()? != b"Link" {
+        return None;
+    }
+    let action = resolve_dictionary(document, annotation.get(b"A").ok()?)?;
+    if action.get(b"S").and_then(Object::as_name).ok()? != b"URI" {
+        return None;
+    }
+    let uri = action.get(b"URI").and_then(Object::as_str).ok()?;
+    targets
+        .get(&link_key(&String::from_utf8_lossy(uri)))
+        .cloned()
+}
+
+/// Normalizes a URL for matching links, the fragment and a trailing slash are ignored
+fn link_key(url: &str) -> String {
+    let key = match url::Url::parse(url) {
+        Ok(mut url) => {
+            url.set_fragment(None);
+            url.to_string()
+        }
+        Err(_) => url.split('#').next().unwrap_or_default().to_string(),
+    };
+    key.trim_end_matches('/').to_string()
+}
+
+/// Maps the names of all named destinations of a PDF to their explicit destinations
+/// Supports both the `/Dests` dictionary (PDF 1.1) and the `/Dests` name tree (PDF 1.2)
+fn named_destinations(document: &Document) -> HashMap<Vec<u8>, Object> {
+    let mut destinations = HashMap::new();
+    let Ok(catalog) = document.catalog() else {
+        return destinations;
+    };
+
+    if let Some(dests) = catalog
+        .get(b"Dests")
+        .ok()
+        .and_then(|dests| resolve_dictionary(document, dests))
+    {
+        for (name, destination) in dests.iter() {
+            if let Some(destination) = explicit_destination(document, destination) {
+                destinations.insert(name.clone(), destination);
+            }
+        }
+    }
+
+    let tree = catalog
+        .get(b"Names")
+        .ok()
+        .and_then(|names| resolve_dictionary(document, names))
+        .and_then(|names| names.get(b"Dests").ok())
+        .and_then(|tree| resolve_dictionary(document, tree));
+    let mut nodes: Vec<&Dictionary> = tree.into_iter().collect();
+    let mut visited = HashSet::new();
+    while let Some(node) = nodes.pop() {
+        if let Ok(names) = node.get(b"Names").and_then(Object::as_array) {
+            for pair in names.chunks_exact(2) {
+                if let (Ok(name), Some(destination)) =
+                    (pair[0].as_str(), explicit_destination(document, &pair[1]))
+                {
+                    destinations.insert(name.to_vec(), destination);
+                }
+            }
+        }
+        if let Ok(kids) = node.get(b"Kids").and_then(Object::as_array) {
+            for kid in kids {
+                if let Ok(id) = kid.as_reference() {
+                    if visited.insert(id) {
+                        nodes.extend(document.get_dictionary(id).ok());
+                    }
+                }
+            }
+        }
+    }
+
+    destinations
+}
+
+/// Resolves a destination, which may be wrapped in a dictionary (`<< /D [...] >>`)
+fn explicit_destination(document: &Document, destination: &Object) -> Option<Object> {
+    let (_, destination) = document.dereference(destination).ok()?;
+    match destination {
+        Object::Array(_) => Some(destination.clone()),
+        Object::Dictionary(dictionary) => {
+            let (_, destination) = document.dereference(dictionary.get(b"D").ok()?).ok()?;
+            matches!(destination, Object::Array(_)).then(|| destination.clone())
+        }
+        _ => None,
+    }
+}
+
+pub(crate) fn resolve_dictionary<'a>(
+    document: &'a Document,
+    object: &'a Object,
+) -> Option<&'a Dictionary> {
+    document.dereference(object).ok()?.1.as_dict().ok()
+}
+
+/// Reads the outline of a PDF, bookmarks without a resolvable destination are left out
+fn read_outline(document: &Document) -> Vec<OutlineItem> {
+    let destinations = named_destinations(document);
+    let first = document
+        .catalog()
+        .ok()
+        .and_then(|catalog| catalog.get(b"Outlines").ok())
+        .and_then(|outlines| resolve_dictionary(document, outlines))
+        .and_then(|outlines| outlines.get(b"First").ok());
+    read_outline_items(document, &destinations, first, &mut HashSet::new())
+}
+
+fn read_outline_items(
+    document: &Document,
+    destinations: &HashMap<Vec<u8>, Object>,
+    first: Option<&Object>,
+    visited: &mut HashSet<ObjectId>,
+) -> Vec<OutlineItem> {
+    let mut items = Vec::new();
+    let mut next = first.and_then(|first| first.as_reference().ok());
+    while let Some(id) = next.filter(|id| visited.insert(*id)) {
+        let Ok(item) = document.get_dictionary(id) else {
+            break;
+        };
+        next = item.get(b"Next").and_then(Object::as_reference).ok();
+
+        // The destination is either given directly or as GoTo action
+        let destination = item.get(b"Dest").ok().or_else(|| {
+            resolve_dictionary(document, item.get(b"A").ok()?)?
+                .get(b"D")
+                .ok()
+        });
+        let destination = destination.and_then(|destination| match destination {
+            Object::String(name, _) | Object::Name(name) => destinations.get(name).cloned(),
+            destination => explicit_destination(document, destination),
+        });
+        let (Some(destination), Ok(title)) = (destination, item.get(b"Title")) else {
+            continue;
+        };
+        items.push(OutlineItem {
+            title: title.clone(),
+            destination,
+            children: read_outline_items(document, destinations, item.get(b"First").ok(), visited),
+        });
+    }
+    items
+}
+
+/// Writes an outline into a PDF, replacing any existing outline
+/// All bookmarks are expanded, readers are asked to show the outline when opening the PDF.
+fn write_outline(document: &mut Document, items: Vec<OutlineItem>) -> Result<()> {
+    let outlines_id = document.new_object_id();
+    let (ids, count) = write_outline_items(document, outlines_id, items);
+
+    let mut outlines = Dictionary::new();
+    outlines.set("Type", "Outlines");
+    if let (Some(first), Some(last)) = (ids.first(), ids.last()) {
+        outlines.set("First", *first);
+        outlines.set("Last", *last);
+    }
+    outlines.set("Count", count);
+    document
+        .objects
+        .insert(outlines_id, Object::Dictionary(outlines));
+
+    let catalog = document.catalog_mut()?;
+    catalog.set("Outlines", outlines_id);
+    catalog.set("PageMode", "UseOutlines");
+    Ok(())
+}
+
+/// Writes a level of the outline
+///
+/// # Returns
+/// * The ids of the written items and the number of visible items including all descendants
+fn write_outline_items(
+    document: &mut Document,
+    parent: ObjectId,
+    items: Vec<OutlineItem>,
+) -> (Vec<ObjectId>, i64) {
+    let ids: Vec<ObjectId> = items.iter().map(|_| document.new_object_id()).collect();
+    let mut count = 0;
+    for (index, item) in items.into_iter().enumerate() {
+        let mut dictionary = Dictionary::new();
+        dictionary.set("Title", item.title);
+        dictionary.set("Parent", parent);
+        dictionary.set("Dest", item.destination);
+        if index > 0 {
+            dictionary.set("Prev", ids[index - 1]);
+        }
+        if let Some(next) = ids.get(index + 1) {
+            dictionary.set("Next", *next);
+        }
+        let (children, descendants) = write_outline_items(document, ids[index], item.children);
+        if let (Some(first), Some(last)) = (children.first(), children.last()) {
+            dictionary.set("First", *first);
+            dictionary.set("Last", *last);
+            dictionary.set("Count", descendants);
+        }
+        document
+            .objects
+            .insert(ids[index], Object::Dictionary(dictionary));
+        count += 1 + descendants;
+    }
+    (ids, count)
+}
+
+/// Reads the names and file specifications of the `EmbeddedFiles` name tree
+fn read_embedded_files(document: &Document) -> Vec<(String, Object)> {
+    let mut files = Vec::new();
+    let root = document
+        .catalog()
+        .ok()
+        .and_then(|catalog| catalog.get(b"Names").ok())
+        .and_then(|names| resolve_dictionary(document, names))
+        .and_then(|names| names.get(b"EmbeddedFiles").ok());
+    if let Some(root) = root {
+        read_name_tree(document, root, &mut files, 0);
+    }
+    files
+}
+
+fn read_name_tree(
+    document: &Document,
+    node: &Object,
+    files: &mut Vec<(String, Object)>,
+    depth: usize,
+) {
+    // Guards against cyclic name trees
+    let Some(node) = resolve_dictionary(document, node).filter(|_| depth < 64) else {
+        return;
+    };
+    if let Ok(names) = node.get(b"Names").and_then(Object::as_array) {
+        for pair in names.chunks_exact(2) {
+            if let Ok(name) = decode_text_string(&pair[0]) {
+                files.push((name, pair[1].clone()));
+            }
+        }
+    }
+    if let Ok(kids) = node.get(b"Kids").and_then(Object::as_array) {
+        for kid in kids {
+            read_name_tree(document, kid, files, depth + 1);
+        }
+    }
+}
+
+/// Reads the associated files (`/AF`) of the document
+fn read_associated_files(document: &Document) -> Vec<Object> {
+    document
+        .catalog()
+        .ok()
+        .and_then(|catalog| catalog.get(b"AF").ok())
+        .and_then(|files| document.dereference(files).ok())
+        .and_then(|(_, files)| files.as_array().ok())
+        .cloned()
+        .unwrap_or_default()
+}
+
+/// Replaces the `EmbeddedFiles` name tree and associated files of the document
+/// Duplicate names get a numbered suffix, as names of a name tree are unique.
+fn write_embedded_files(
+    document: &mut Document,
+    files: Vec<(String, Object)>,
+    associated: Vec<Object>,
+) -> Result<()> {
+    let mut used = HashSet::new();
+    let mut files: Vec<(String, Object)> = files
+        .into_iter()
+        .map(|(name, file_spec)| {
+            let mut unique = name.clone();
+            let mut number = 1;
+            while !used.insert(unique.clone()) {
+                number += 1;
+                unique = match name.rsplit_once('.') {
+                    Some((stem, extension)) => format!("{} ({}).{}", stem, number, extension),
+                    None => format!("{} ({})", name, number),
+                };
+            }
+            (unique, file_spec)
+        })
+        .collect();
+    // Keys of a name tree are sorted
+    files.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+    let mut names = document
+        .catalog()?
+        .get(b"Names")
+        .ok()
+        .and_then(|names| resolve_dictionary(document, names))
+        .cloned()
+        .unwrap_or_default();
+    if files.is_empty() {
+        names.remove(b"EmbeddedFiles");
+    } else {
+        let entries: Vec<Object> = files
+            .into_iter()
+            .flat_map(|(name, file_spec)| [text_string(&name), file_spec])
+            .collect();
+        names.set("EmbeddedFiles", dictionary! { "Names" => entries });
+    }
+
+    let catalog = document.catalog_mut()?;
+    if names.is_empty() {
+        catalog.remove(b"Names");
+    } else {
+        catalog.set("Names", names);
+    }
+    if associated.is_empty() {
+        catalog.remove(b"AF");
+    } else {
+        catalog.set("AF", associated);
+    }
+    Ok(())
+}
+
+/// Looks up an attribute in the ancestors of a page
+pub(crate) fn inherited_attribute(
+    document: &Document,
+    page_id: ObjectId,
+    key: &[u8],
+) -> Option<Object> {
+    let mut node = document.get_dictionary(page_id).ok()?;
+    // Guards against cyclic page trees
+    for _ in 0..64 {
+        let parent = node.get(b"Parent").and_then(Object::as_reference).ok()?;
+        node = document.get_dictionary(parent).ok()?;
+        if let Ok(value) = node.get(key) {
+            return Some(value.clone());
+        }
+    }
+    None
+}
+
+/// Encodes text for the standard fonts (WinAnsiEncoding), characters outside of Latin-1 become `?`
+pub(crate) fn encode_win_ansi(text: &str) -> Vec<u8> {
+    // WinAnsiEncoding matches Latin-1 for printable characters above 0xA0
+    text.chars()
+        .map(|c| match c as u32 {
+            0x20..=0x7E | 0xA0..=0xFF => c as u8,
+            _ => b'?',
+        })
+        .collect()
+}
+
+/// Glyph widths of Helvetica in thousandths of the font size, see `encode_win_ansi`
+pub(crate) fn helvetica_width(byte: u8) -> u16 {
+    const ASCII_WIDTHS: [u16; 95] = [
+        // space to /
+        278, 278, 355, 556, 556, 889, 667, 191, 333, 333, 389, 584, 278, 333, 278, 278,
+        // 0 to ?
+        556, 556, 556, 556, 556, 556, 556, 556, 556, 556, 278, 278, 584, 584, 584, 556,
+        // @ to O
+        1015, 667, 667, 722, 722, 667, 611, 778, 722, 278, 500, 667, 556, 833, 722, 778,
+        // P to _
+        667, 778, 722, 667, 611, 722, 667, 944, 667, 667, 611, 278, 278, 278, 469, 556,
+        // ` to o
+        333, 556, 556, 500, 556, 556, 278, 556, 556, 222, 222, 500, 222, 833, 556, 556,
+        // p to ~
+        556, 556, 333, 500, 278, 556, 500, 722, 500, 500, 500, 334, 260, 334, 584,
+    ];
+    match byte {
+        0x20..=0x7E => ASCII_WIDTHS[usize::from(byte - 0x20)],
+        // Ellipsis
+        0x85 => 1000,
+        // Most Latin-1 letters have the width of their base letter, which is mostly 556
+        _ => 556,
+    }
+}
+
+/// Serializes a PDF document
+pub(crate) fn save_to_vec(document: &mut Document) -> Result<Vec<u8>> {
+    let mut pdf = Vec::new();
+    document.save_to(&mut pdf)?;
+    Ok(pdf)
+}
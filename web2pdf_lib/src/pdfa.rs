@@ -0,0 +1,260 @@
+use std::collections::hash_map::DefaultHasher;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+
+use lopdf::{dictionary, Document, Object, Stream, StringFormat};
+
+use crate::pdf::{save_to_vec, write_xmp};
+use crate::Result;
+
+/// Error for when a PDF cannot be converted to PDF/A
+#[derive(Debug, Clone)]
+struct PdfAConversionError {
+    error_message: String,
+}
+impl fmt::Display for PdfAConversionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Error converting to PDF/A: {}", self.error_message)
+    }
+}
+impl std::error::Error for PdfAConversionError {}
+
+/// PDF/A conformance levels
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PdfA {
+    /// PDF/A-2b (ISO 19005-2, basic conformance): reliable visual reproduction
+    PdfA2b,
+}
+
+impl PdfA {
+    fn part(self) -> u8 {
+        match self {
+            PdfA::PdfA2b => 2,
+        }
+    }
+
+    fn conformance(self) -> &'static str {
+        match self {
+            PdfA::PdfA2b => "B",
+        }
+    }
+}
+
+/// Name of the output condition of the embedded ICC profile
+const SRGB_OUTPUT_CONDITION: &str = "sRGB IEC61966-2.1";
+
+/// Converts a PDF created by Chrome into a PDF/A file
+///
+/// 1. checks that all fonts are embedded (Chrome embeds all fonts it renders)
+/// 2. adds an sRGB output intent, as Chrome uses device dependent RGB colors
+/// 3. makes all annotations printable
+/// 4. writes XMP metadata with the PDF/A identification, matching the Info dictionary
+///
+/// # Arguments
+/// * `pdf` - The PDF data
+/// * `level` - The PDF/A conformance level
+///
+/// # Returns
+/// * The PDF/A data or an error if the PDF cannot be made compliant
+pub fn convert(pdf: &[u8], level: PdfA) -> Result<Vec<u8>> {
+    let mut document = Document::load_mem(pdf)?;
+    if document.trailer.has(b"Encrypt") {
+        return Err(conversion_error("encrypted PDFs are not allowed"));
+    }
+
+    let missing_fonts = fonts_without_file(&document);
+    if !missing_fonts.is_empty() {
+        return Err(conversion_error(&format!(
+            "fonts are not embedded: {}",
+            missing_fonts.join(", ")
+        )));
+    }
+
+    // PDF/A-2 is based on PDF 1.7
+    document.version = String::from("1.7");
+
+    for object in document.objects.values_mut() {
+        let Object::Dictionary(dictionary) = object else {
+            continue;
+        };
+        if dictionary.has_type(b"Annot") {
+            // Print flag set, Invisible, Hidden and NoView flags cleared
+            let flags = dictionary.get(b"F").and_then(Object::as_i64).unwrap_or(0);
+            dictionary.set("F", (flags | 4) & !(1 | 2 | 32));
+        }
+    }
+
+    let mut profile = Stream::new(dictionary! { "N" => 3 }, srgb_icc_profile());
+    let _ = profile.compress();
+    let profile_id = document.add_object(profile);
+    let output_intent = dictionary! {
+        "Type" => "OutputIntent",
+        "S" => "GTS_PDFA1",
+        "OutputConditionIdentifier" => Object::string_literal(SRGB_OUTPUT_CONDITION),
+        "Info" => Object::string_literal(SRGB_OUTPUT_CONDITION),
+        "RegistryName" => Object::string_literal("http://www.color.org"),
+        "DestOutputProfile" => profile_id,
+    };
+    document
+        .catalog_mut()?
+        .set("OutputIntents", vec![Object::Dictionary(output_intent)]);
+
+    write_xmp(
+        &mut document,
+        &format!(
+            "<pdfaid:part>{}</pdfaid:part><pdfaid:conformance>{}</pdfaid:conformance>",
+            level.part(),
+            level.conformance()
+        ),
+    )?;
+
+    // A file identifier is required
+    if !document.trailer.has(b"ID") {
+        let id = Object::String(file_identifier(pdf), StringFormat::Hexadecimal);
+        document.trailer.set("ID", vec![id.clone(), id]);
+    }
+
+    save_to_vec(&mut document)
+}
+
+fn conversion_error(message: &str) -> Box<dyn std::error::Error> {
+    Box::new(PdfAConversionError {
+        error_message: message.to_string(),
+    })
+}
+
+/// Names of all fonts of a PDF without embedded font program
+fn fonts_without_file(document: &Document) -> Vec<String> {
+    let mut fonts = Vec::new();
+    for object in document.objects.values() {
+        let Ok(font) = object.as_dict() else {
+            continue;
+        };
+        if !font.has_type(b"Font") {
+            continue;
+        }
+        // Composite fonts are checked through their descendant, Type3 glyphs are content streams
+        let subtype = font
+            .get(b"Subtype")
+            .and_then(Object::as_name)
+            .unwrap_or(b"");
+        if subtype == b"Type0" || subtype == b"Type3" {
+            continue;
+        }
+        let embedded = font
+            .get(b"FontDescriptor")
+            .ok()
+            .and_then(|descriptor| document.dereference(descriptor).ok())
+            .and_then(|(_, descriptor)| descriptor.as_dict().ok())
+            .is_some_and(|descriptor| {
+                descriptor.has(b"FontFile")
+                    || descriptor.has(b"FontFile2")
+                    || descriptor.has(b"FontFile3")
+            });
+        if !embedded {
+            let name = font
+                .get(b"BaseFont")
+                .and_then(Object::as_name)
+                .map(|name| String::from_utf8_lossy(name).into_owned())
+                .unwrap_or_else(|_| String::from("unnamed font"));
+            fonts.push(name);
+        }
+    }
+    fonts.sort();
+    fonts.dedup();
+    fonts
+}
+
+/// A 16 byte file identifier derived from the PDF data
+fn file_identifier(pdf: &[u8]) -> Vec<u8> {
+    let mut id = Vec::with_capacity(16);
+    for seed in 0u8..2 {
+        let mut hasher = DefaultHasher::new();
+        seed.hash(&mut hasher);
+        pdf.hash(&mut hasher);
+        id.extend(hasher.finish().to_be_bytes());
+    }
+    id
+}
+
+/// Creates an ICC (version 2) display profile of the sRGB color space
+fn srgb_icc_profile() -> Vec<u8> {
+    fn s15_fixed16(value: f64) -> [u8; 4] {
+        ((value * 65536.0).round() as i32).to_be_bytes()
+    }
+    fn xyz(x: f64, y: f64, z: f64) -> Vec<u8> {
+        let mut tag = b"XYZ \0\0\0\0".to_vec();
+        for value in [x, y, z] {
+            tag.extend(s15_fixed16(value));
+        }
+        tag
+    }
+
+    let mut description = b"desc\0\0\0\0".to_vec();
+    description.extend((SRGB_OUTPUT_CONDITION.len() as u32 + 1).to_be_bytes());
+    description.extend(SRGB_OUTPUT_CONDITION.as_bytes());
+    // Terminator, empty Unicode and ScriptCode descriptions
+    description.extend([0u8; 1 + 4 + 4 + 2 + 1 + 67]);
+
+    let mut copyright = b"text\0\0\0\0".to_vec();
+    copyright.extend(b"No copyright, use freely\0");
+
+    // sRGB transfer function, sampled
+    const SAMPLES: u32 = 1024;
+    let mut curve = b"curv\0\0\0\0".to_vec();
+    curve.extend(SAMPLES.to_be_bytes());
+    for sample in 0..SAMPLES {
+        let value = f64::from(sample) / f64::from(SAMPLES - 1);
+        let linear = if value <= 0.04045 {
+            value / 12.92
+        } else {
+            ((value + 0.055) / 1.055).powf(2.4)
+        };
+        curve.extend(((linear * 65535.0).round() as u16).to_be_bytes());
+    }
+
+    // Primaries adapted to the D50 illuminant of the profile connection space
+    let tags: [(&[u8; 4], Vec<u8>); 9] = [
+        (b"desc", description),
+        (b"cprt", copyright),
+        (b"wtpt", xyz(0.9642, 1.0, 0.8249)),
+        (b"rXYZ", xyz(0.4361, 0.2225, 0.0139)),
+        (b"gXYZ", xyz(0.3851, 0.7169, 0.0971)),
+        (b"bXYZ", xyz(0.1431, 0.0606, 0.7141)),
+        (b"rTRC", curve.clone()),
+        (b"gTRC", curve.clone()),
+        (b"bTRC", curve),
+    ];
+
+    let mut table = (tags.len() as u32).to_be_bytes().to_vec();
+    let mut data = Vec::new();
+    let data_start = 128 + 4 + 12 * tags.len();
+    for (signature, tag) in &tags {
+        table.extend(*signature);
+        table.extend(((data_start + data.len()) as u32).to_be_bytes());
+        table.extend((tag.len() as u32).to_be_bytes());
+        data.extend(tag);
+        // Tags are 4 byte aligned
+        data.resize(data.len().next_multiple_of(4), 0);
+    }
+
+    let size = data_start + data.len();
+    let mut profile = Vec::with_capacity(size);
+    profile.extend((size as u32).to_be_bytes());
+    profile.extend([0; 4]); // Preferred CMM
+    profile.extend([0x02, 0x10, 0, 0]); // Version 2.1
+    profile.extend(b"mntrRGB XYZ ");
+    for value in [2024u16, 1, 1, 0, 0, 0] {
+        profile.extend(value.to_be_bytes());
+    }
+    profile.extend(b"acsp");
+    profile.extend([0; 24]); // Platform, flags, manufacturer, model, attributes
+    profile.extend([0; 4]); // Perceptual rendering intent
+    for value in [0.9642, 1.0, 0.8249] {
+        profile.extend(s15_fixed16(value));
+    }
+    profile.resize(128, 0);
+    profile.extend(table);
+    profile.extend(data);
+    profile
+}
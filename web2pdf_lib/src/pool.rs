@@ -0,0 +1,319 @@
+//! A pool of browser processes for large batches
+//! A single browser degrades badly with many concurrent tabs, so pages are distributed
+//! among several processes. Crashed processes are replaced with a new launch.
+
+use std::fmt;
+use std::ops::Deref;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use chromiumoxide::cdp::browser_protocol::target::CreateTargetParams;
+use chromiumoxide::{Browser, BrowserConfig, Page};
+
+use crate::{BrowserWeb2Pdf, Result};
+
+/// How long a browser may take to answer a health check
+pub const HEALTH_CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Error for when managing the browser pool
+#[derive(Debug, Clone)]
+struct PoolError {
+    error_message: String,
+}
+impl fmt::Display for PoolError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Error in browser pool: {}", self.error_message)
+    }
+}
+impl std::error::Error for PoolError {
+    fn description(&self) -> &str {
+        &self.error_message
+    }
+}
+
+fn pool_error(message: impl Into<String>) -> Box<dyn std::error::Error> {
+    Box::new(PoolError {
+        error_message: message.into(),
+    })
+}
+
+/// One browser process of the pool
+struct Slot {
+    /// The current browser and how often it has been replaced
+    browser: RwLock<(Arc<Browser>, usize)>,
+    /// Serializes replacing the browser
+    relaunch: tokio::sync::Mutex<()>,
+    /// Jobs currently using the browser
+    active: Arc<AtomicUsize>,
+    config: BrowserConfig,
+    /// The profile directory of the first browser, see `profile_dir`
+    profile: PathBuf,
+}
+
+impl Slot {
+    fn current(&self) -> (Arc<Browser>, usize) {
+        let browser = self.browser.read().unwrap_or_else(|e| e.into_inner());
+        (Arc::clone(&browser.0), browser.1)
+    }
+
+    /// The profile directory of a generation of the browser, e.g. `pool-0` and then `pool-0-1`
+    fn profile_dir(&self, generation: usize) -> PathBuf {
+        match generation {
+            0 => self.profile.clone(),
+            generation => {
+                let mut dir = self.profile.clone().into_os_string();
+                dir.push(format!("-{}", generation));
+                dir.into()
+            }
+        }
+    }
+
+    /// Replaces the browser, unless it has already been replaced since `generation`
+    async fn replace(&self, generation: usize) -> Result<()> {
+        let _relaunch = self.relaunch.lock().await;
+        if self.current().1 != generation {
+            return Ok(());
+        }
+        // The unresponsive browser keeps running (and locking its profile) while it is in use,
+        // so the new one gets a fresh profile
+        let mut config = self.config.clone();
+        config.user_data_dir = Some(self.profile_dir(generation + 1));
+        let browser = Browser::web2pdf_launch_from_config(config).await?;
+        // The old browser is killed once its last user is dropped
+        *self.browser.write().unwrap_or_else(|e| e.into_inner()) =
+            (Arc::new(browser), generation + 1);
+        tracing::warn!("Web2Pdf replaced an unresponsive browser of the pool");
+        Ok(())
+    }
+}
+
+/// Checks that a browser still answers
+async fn is_healthy(browser: &Browser) -> bool {
+    matches!(
+        tokio::time::timeout(HEALTH_CHECK_TIMEOUT, browser.version()).await,
+        Ok(Ok(_))
+    )
+}
+
+/// Manages several browser processes and distributes pages among them
+pub struct BrowserPool {
+    slots: Vec<Slot>,
+    /// Whether the profiles are temporary directories, removed on close
+    temporary_profiles: bool,
+}
+
+impl BrowserPool {
+    /// Launches `size` browsers from a given `BrowserConfig`.
+    /// Please remeber to use Viewport::web2pdf_viewport() for building the config
+    ///
+    /// # Arguments
+    /// * `browser_config` - The `BrowserConfig` to use for launching every browser.
+    ///   Each browser gets its own profile, inside the configured user data directory if set.
+    ///   Replaced browsers start with a fresh profile next to it.
+    /// * `size` - The number of browsers, at least one
+    ///
+    /// # Returns
+    /// A `Result` containing the `BrowserPool` or an error.
+    pub async fn launch(browser_config: BrowserConfig, size: usize) -> Result<BrowserPool> {
+        if size == 0 {
+            return Err(pool_error("the pool needs at least one browser"));
+        }
+        let temporary_profiles = browser_config.user_data_dir.is_none();
+        let configs = (0..size)
+            .map(|index| {
+                let mut config = browser_config.clone();
+                // Browsers sharing a profile would hand their pages to the first one
+                config.user_data_dir = Some(match &browser_config.user_data_dir {
+                    Some(dir) => dir.join(format!("pool-{}", index)),
+                    None => std::env::temp_dir().join(format!(
+                        "web2pdf-pool-{}-{}",
+                        std::process::id(),
+                        index
+                    )),
+                });
+                config
+            })
+            .collect::<Vec<_>>();
+
+        // Launched browsers are killed on drop if another one fails.
+        // Errors are converted to messages, as boxed errors cannot be sent between threads
+        let browsers = futures::future::try_join_all(configs.iter().map(|config| async move {
+            Browser::web2pdf_launch_from_config(config.clone())
+                .await
+                .map_err(|e| e.to_string())
+        }))
+        .await
+        .map_err(pool_error)?;
+        let slots = browsers
+            .into_iter()
+            .zip(configs)
+            .map(|(browser, config)| Slot {
+                browser: RwLock::new((Arc::new(browser), 0)),
+                relaunch: tokio::sync::Mutex::new(()),
+                active: Arc::new(AtomicUsize::new(0)),
+                profile: config.user_data_dir.clone().unwrap_or_default(),
+                config,
+            })
+            .collect();
+
+        tracing::debug!("Web2Pdf browser pool of {} launched", size);
+        Ok(BrowserPool {
+            slots,
+            temporary_profiles,
+        })
+    }
+
+    /// The number of browsers in the pool
+    pub fn size(&self) -> usize {
+        self.slots.len()
+    }
+
+    /// The number of jobs currently using each browser
+    pub fn load(&self) -> Vec<usize> {
+        self.slots
+            .iter()
+            .map(|slot| slot.active.load(Ordering::Relaxed))
+            .collect()
+    }
+
+    /// Takes the least busy browser, it counts as busy until the returned value is dropped
+    ///
+    /// # Returns
+    /// The browser of the pool
+    pub fn browser(&self) -> PooledBrowser {
+        let (index, slot) = self
+            .slots
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, slot)| slot.active.load(Ordering::Relaxed))
+            .expect("the pool is never empty");
+        slot.active.fetch_add(1, Ordering::Relaxed);
+        let (browser, generation) = slot.current();
+        PooledBrowser {
+            browser,
+            index,
+            generation,
+            active: Arc::clone(&slot.active),
+        }
+    }
+
+    /// Create a new page on the least busy browser.
+    /// If the browser does not respond, it is replaced and the page is created on the new one.
+    ///
+    /// # Arguments
+    /// * `params` - The `CreateTargetParams` to use for creating the page.
+    ///
+    /// # Returns
+    /// A `Result` containing the new `PooledPage` or an error.
+    pub async fn web2pdf_new_page(
+        &self,
+        params: impl Into<CreateTargetParams> + Send,
+    ) -> Result<PooledPage> {
+        let params = params.into();
+        let browser = self.browser();
+        let error = match browser.web2pdf_new_page(params.clone()).await {
+            Ok(page) => return Ok(PooledPage { page, browser }),
+            Err(e) => e.to_string(),
+        };
+        if is_healthy(&browser).await {
+            return Err(error.into());
+        }
+        self.slots[browser.index]
+            .replace(browser.generation)
+            .await?;
+        drop(browser);
+
+        let browser = self.browser();
+        let page = browser.web2pdf_new_page(params).await?;
+        Ok(PooledPage { page, browser })
+    }
+
+    /// Checks every browser and replaces the ones that do not respond
+    ///
+    /// # Returns
+    /// A `Result` containing the number of replaced browsers or an error.
+    pub async fn health_check(&self) -> Result<usize> {
+        let replaced = futures::future::try_join_all(self.slots.iter().map(|slot| async move {
+            let (browser, generation) = slot.current();
+            if is_healthy(&browser).await {
+                return Ok(false);
+            }
+            drop(browser);
+            slot.replace(generation)
+                .await
+                .map(|_| true)
+                .map_err(|e| e.to_string())
+        }))
+        .await
+        .map_err(pool_error)?;
+        Ok(replaced.into_iter().filter(|replaced| *replaced).count())
+    }
+
+    /// Closes all browsers and waits for them to terminate
+    ///
+    /// # Returns
+    /// A `Result` containing an empty `()` value or an error, e.g. if a browser is still in use.
+    pub async fn close(self) -> Result<()> {
+        let mut profiles = Vec::new();
+        for slot in self.slots {
+            let (browser, generation) = slot.current();
+            if self.temporary_profiles {
+                profiles.extend((0..=generation).map(|generation| slot.profile_dir(generation)));
+            }
+            drop(slot);
+            let browser = Arc::try_unwrap(browser)
+                .map_err(|_| pool_error("a browser of the pool is still in use"))?;
+            browser.close_and_wait().await?;
+        }
+        for dir in &profiles {
+            let _ = tokio::fs::remove_dir_all(dir).await;
+        }
+        tracing::debug!("Web2Pdf browser pool closed");
+        Ok(())
+    }
+}
+
+/// A browser taken from a `BrowserPool`
+pub struct PooledBrowser {
+    browser: Arc<Browser>,
+    index: usize,
+    generation: usize,
+    active: Arc<AtomicUsize>,
+}
+
+impl Deref for PooledBrowser {
+    type Target = Browser;
+
+    fn deref(&self) -> &Browser {
+        &self.browser
+    }
+}
+
+impl Drop for PooledBrowser {
+    fn drop(&mut self) {
+        self.active.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// A page of a `BrowserPool`, keeps its browser busy until dropped
+pub struct PooledPage {
+    page: Page,
+    browser: PooledBrowser,
+}
+
+impl PooledPage {
+    /// The browser the page belongs to
+    pub fn browser(&self) -> &Browser {
+        &self.browser
+    }
+}
+
+impl Deref for PooledPage {
+    type Target = Page;
+
+    fn deref(&self) -> &Page {
+        &self.page
+    }
+}
@@ -0,0 +1,148 @@
+//! Detecting addresses of private networks, to keep pages from reaching the network of the host
+//! (server-side request forgery), see `InterceptConfig::deny_private_networks`
+
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+use url::{Host, Url};
+
+/// Whether an address is not publicly routable, e.g. loopback, a private range or link-local
+pub fn is_private(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => is_private_v4(ip),
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ip) => is_private_v4(ip),
+            None => is_private_v6(ip),
+        },
+    }
+}
+
+fn is_private_v4(ip: Ipv4Addr) -> bool {
+    let [first, second, ..] = ip.octets();
+    ip.is_unspecified()
+        || ip.is_loopback()
+        || ip.is_private()
+        || ip.is_link_local()
+        || ip.is_broadcast()
+        || ip.is_multicast()
+        // "This network" 0.0.0.0/8, carrier-grade NAT 100.64.0.0/10 and reserved 240.0.0.0/4
+        || first == 0
+        || (first == 100 && (64..128).contains(&second))
+        || first >= 240
+}
+
+fn is_private_v6(ip: Ipv6Addr) -> bool {
+    let first = ip.segments()[0];
+    ip.is_unspecified()
+        || ip.is_loopback()
+        || ip.is_multicast()
+        // Unique local fc00::/7 and link-local fe80::/10
+        || (first & 0xfe00) == 0xfc00
+        || (first & 0xffc0) == 0xfe80
+}
+
+/// Whether the host of a URL is or resolves to a private address
+/// URLs without a host, e.g. `data:` URLs, are not private.
+///
+/// # Arguments
+/// * `url` - The URL
+///
+/// # Returns
+/// Whether any address of the host is private, or the error of resolving the host
+pub async fn is_private_url(url: &str) -> std::io::Result<bool> {
+    let Ok(url) = Url::parse(url) else {
+        return Ok(false);
+    };
+    let port = url.port_or_known_default().unwrap_or(80);
+    match url.host() {
+        Some(Host::Ipv4(ip)) => Ok(is_private(ip.into())),
+        Some(Host::Ipv6(ip)) => Ok(is_private(ip.into())),
+        Some(Host::Domain(domain)) => Ok(tokio::net::lookup_host((domain, port))
+            .await?
+            .any(|address| is_private(address.ip()))),
+        None => Ok(false),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn private(ip: &str) -> bool {
+        is_private(ip.parse().unwrap())
+    }
+
+    #[test]
+    fn ipv4_ranges() {
+        for ip in [
+            "0.0.0.0",
+            "0.1.2.3",
+            "127.0.0.1",
+            "10.0.0.1",
+            "172.16.0.1",
+            "172.31.255.255",
+            "192.168.1.1",
+            "169.254.169.254",
+            "100.64.0.1",
+            "100.127.255.255",
+            "224.0.0.1",
+            "240.0.0.1",
+            "255.255.255.255",
+        ] {
+            assert!(private(ip), "{} should be private", ip);
+        }
+        for ip in [
+            "8.8.8.8",
+            "1.1.1.1",
+            "172.32.0.1",
+            "100.128.0.1",
+            "93.184.215.14",
+        ] {
+            assert!(!private(ip), "{} should be public", ip);
+        }
+    }
+
+    #[test]
+    fn ipv6_ranges() {
+        for ip in ["::", "::1", "fc00::1", "fd12:3456::1", "fe80::1", "ff02::1"] {
+            assert!(private(ip), "{} should be private", ip);
+        }
+        for ip in ["2001:4860:4860::8888", "2606:4700::1111"] {
+            assert!(!private(ip), "{} should be public", ip);
+        }
+    }
+
+    #[test]
+    fn ipv4_mapped_ipv6_is_checked_as_ipv4() {
+        assert!(private("::ffff:127.0.0.1"));
+        assert!(private("::ffff:192.168.1.1"));
+        assert!(private("::ffff:169.254.169.254"));
+        assert!(!private("::ffff:8.8.8.8"));
+    }
+
+    #[tokio::test]
+    async fn urls() {
+        for url in [
+            "http://localhost/",
+            "http://127.0.0.1:8080/",
+            "http://[::1]/",
+            "http://0.0.0.0/",
+            "http://[::ffff:10.0.0.1]/",
+            // Numeric host forms are normalized by the URL parser
+            "http://0x7f000001/",
+            "http://2130706433/",
+        ] {
+            assert!(
+                is_private_url(url).await.unwrap(),
+                "{} should be private",
+                url
+            );
+        }
+        for url in ["https://8.8.8.8/", "data:text/html,hi", "about:blank"] {
+            assert!(
+                !is_private_url(url).await.unwrap(),
+                "{} should be public",
+                url
+            );
+        }
+    }
+}
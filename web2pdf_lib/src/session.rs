@@ -0,0 +1,123 @@
+//! A single page reused for converting many URLs one after the other
+//! Creating a page per URL is the main memory cost of large batches, a session navigates the
+//! same page through all URLs and resets it in between.
+
+use std::ops::Deref;
+use std::sync::Mutex;
+
+use chromiumoxide::cdp::browser_protocol::emulation::SetDeviceMetricsOverrideParams;
+use chromiumoxide::cdp::browser_protocol::page::{
+    AddScriptToEvaluateOnNewDocumentParams, RemoveScriptToEvaluateOnNewDocumentParams,
+    ScriptIdentifier,
+};
+use chromiumoxide::cdp::browser_protocol::target::CreateTargetParams;
+use chromiumoxide::{Browser, Page};
+
+use crate::{BrowserWeb2Pdf, Result};
+
+/// A page reused for several URLs
+/// Settings of the page (e.g. emulated media, headers or request interception) are kept between
+/// URLs, init scripts added with `add_init_script` and viewport changes are undone by `reset`.
+pub struct PageSession {
+    page: Page,
+    /// Size of the viewport of the new page
+    viewport: (i64, i64),
+    /// Init scripts of the current URL
+    init_scripts: Mutex<Vec<ScriptIdentifier>>,
+    reuses: usize,
+}
+
+impl PageSession {
+    /// Creates the page of the session
+    ///
+    /// # Arguments
+    /// * `browser` - The browser to create the page in
+    /// * `params` - The `CreateTargetParams` to use for creating the page.
+    ///
+    /// # Returns
+    /// A `Result` containing the `PageSession` or an error.
+    pub async fn new(
+        browser: &Browser,
+        params: impl Into<CreateTargetParams> + Send,
+    ) -> Result<PageSession> {
+        let page = browser.web2pdf_new_page(params).await?;
+        let viewport = page.layout_metrics().await?.css_layout_viewport;
+        Ok(PageSession {
+            page,
+            viewport: (viewport.client_width, viewport.client_height),
+            init_scripts: Mutex::new(Vec::new()),
+            reuses: 0,
+        })
+    }
+
+    /// How often the page has been reset for another URL
+    pub fn reuses(&self) -> usize {
+        self.reuses
+    }
+
+    /// Adds a script that runs before the scripts of every document, until the next `reset`
+    ///
+    /// # Arguments
+    /// * `script` - The javascript source
+    ///
+    /// # Returns
+    /// A `Result` containing an empty `()` value or an error.
+    pub async fn add_init_script(&self, script: impl Into<String>) -> Result<()> {
+        let identifier = self
+            .page
+            .execute(AddScriptToEvaluateOnNewDocumentParams::new(script))
+            .await?
+            .result
+            .identifier;
+        self.init_scripts
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(identifier);
+        Ok(())
+    }
+
+    /// Prepares the page for the next URL: leaves the current document, removes the init scripts
+    /// and restores the viewport of the new page
+    ///
+    /// # Returns
+    /// A `Result` containing an empty `()` value or an error, e.g. if the page crashed.
+    pub async fn reset(&mut self) -> Result<()> {
+        self.page.goto("about:blank").await?;
+        let init_scripts =
+            std::mem::take(&mut *self.init_scripts.lock().unwrap_or_else(|e| e.into_inner()));
+        for identifier in init_scripts {
+            self.page
+                .execute(RemoveScriptToEvaluateOnNewDocumentParams::new(identifier))
+                .await?;
+        }
+        // A device scale factor of 0 keeps the one of the browser
+        self.page
+            .execute(SetDeviceMetricsOverrideParams::new(
+                self.viewport.0,
+                self.viewport.1,
+                0.0,
+                false,
+            ))
+            .await?;
+        self.reuses += 1;
+        tracing::debug!("Web2Pdf reset page for reuse {}", self.reuses);
+        Ok(())
+    }
+
+    /// Closes the page
+    ///
+    /// # Returns
+    /// A `Result` containing an empty `()` value or an error.
+    pub async fn close(self) -> Result<()> {
+        self.page.close().await?;
+        Ok(())
+    }
+}
+
+impl Deref for PageSession {
+    type Target = Page;
+
+    fn deref(&self) -> &Page {
+        &self.page
+    }
+}
@@ -0,0 +1,288 @@
+//! A blocking interface for embedding, e.g. in desktop apps or FFI layers
+//! Neither async traits nor chromiumoxide types are exposed, the options are plain
+//! (de)serializable values, so they can be passed as JSON.
+//! Use `convert` for a single page or a `Converter` to keep the browser running between pages.
+
+use std::path::Path;
+use std::time::Duration;
+
+use chromiumoxide::cdp::browser_protocol::page::PrintToPdfParams;
+use chromiumoxide::page::MediaTypeParams;
+use chromiumoxide::{Browser, Page};
+use serde::{Deserialize, Serialize};
+
+use crate::paper::{self, PaperSize};
+use crate::{BrowserWeb2Pdf, PageWeb2Pdf, Result};
+
+/// How long `wait_for_selector` waits by default
+const DEFAULT_WAIT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Options of a conversion, every field is optional
+/// Lengths are in inches, like the print options of the browser.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ConvertOptions {
+    /// Print a single page that fits the content instead of standard pages
+    pub mono: bool,
+    pub landscape: bool,
+    /// One of a3, a4, a5, letter or legal (default: the page's CSS page size or letter)
+    pub paper: Option<String>,
+    /// Margin on all sides (default: 0.4)
+    pub margin: Option<f64>,
+    /// Scale of the rendering, between 0.1 and 2
+    pub scale: Option<f64>,
+    /// Pages to print, e.g. "1-5, 8"
+    pub page_ranges: Option<String>,
+    /// Omit the background colors and images of the page
+    pub no_background: bool,
+    /// Render with the screen stylesheets instead of the print stylesheets
+    pub screen: bool,
+    /// HTML template of the page header, see `PrintToPdfParams::header_template`
+    pub header_template: Option<String>,
+    /// HTML template of the page footer, see `PrintToPdfParams::footer_template`
+    pub footer_template: Option<String>,
+    /// CSS selector of an element to wait for before printing
+    pub wait_for_selector: Option<String>,
+    pub user_agent: Option<String>,
+    /// Value of the Accept-Language header, e.g. "de-DE,de;q=0.9"
+    pub accept_language: Option<String>,
+    /// Time limit of the whole conversion in seconds
+    pub timeout_secs: Option<u64>,
+}
+
+impl ConvertOptions {
+    /// Parses options from a JSON object, e.g. `{ "mono": true }`
+    /// Unknown fields are rejected, so misspelled options are not ignored silently.
+    pub fn from_json(json: &str) -> Result<ConvertOptions> {
+        if json.trim().is_empty() {
+            return Ok(ConvertOptions::default());
+        }
+        Ok(serde_json::from_str(json)?)
+    }
+
+    fn paper_size(&self) -> Result<Option<PaperSize>> {
+        Ok(self.paper.as_deref().map(str::parse).transpose()?)
+    }
+
+    fn pdf_params(&self) -> Result<PrintToPdfParams> {
+        let paper = self.paper_size()?;
+        let mut params = PrintToPdfParams::builder()
+            .print_background(!self.no_background)
+            .landscape(self.landscape)
+            .prefer_css_page_size(paper.is_none())
+            .build();
+        if let Some((width, height)) = paper.map(|paper| paper.inches()) {
+            params.paper_width = Some(width);
+            params.paper_height = Some(height);
+        }
+        if let Some(margin) = self.margin {
+            params.margin_top = Some(margin);
+            params.margin_bottom = Some(margin);
+            params.margin_left = Some(margin);
+            params.margin_right = Some(margin);
+        }
+        params.scale = self.scale;
+        params.page_ranges = self.page_ranges.clone();
+        if self.header_template.is_some() || self.footer_template.is_some() {
+            params.display_header_footer = Some(true);
+            // The browser prints its own header or footer if only one is given
+            params.header_template = Some(
+                self.header_template
+                    .clone()
+                    .unwrap_or_else(|| "<span></span>".into()),
+            );
+            params.footer_template = Some(
+                self.footer_template
+                    .clone()
+                    .unwrap_or_else(|| "<span></span>".into()),
+            );
+        }
+        Ok(params)
+    }
+
+    /// Width of the viewport so the page is laid out for the printable width of the paper
+    fn viewport_width(&self) -> Result<Option<u32>> {
+        let Some(paper) = self.paper_size()? else {
+            return Ok(None);
+        };
+        let (width, height) = paper.inches();
+        let width = if self.landscape { height } else { width };
+        let margin = self.margin.unwrap_or(paper::DEFAULT_MARGIN);
+        Ok(Some(((width - 2.0 * margin).max(1.0) * 96.0).round() as u32))
+    }
+}
+
+/// A browser for blocking conversions, launch once and convert many pages
+pub struct Converter {
+    runtime: tokio::runtime::Runtime,
+    browser: Browser,
+}
+
+impl Converter {
+    /// Launches the Chromium browser installed on the system
+    ///
+    /// # Returns
+    /// A `Result` containing the converter or an error if no browser could be launched.
+    pub fn new() -> Result<Converter> {
+        Self::launch(None)
+    }
+
+    /// Launches a specific Chromium executable
+    ///
+    /// # Arguments
+    /// * `path` - The path of the executable
+    ///
+    /// # Returns
+    /// A `Result` containing the converter or an error if the browser could not be launched.
+    pub fn with_browser_path(path: impl AsRef<Path>) -> Result<Converter> {
+        Self::launch(Some(path.as_ref()))
+    }
+
+    fn launch(path: Option<&Path>) -> Result<Converter> {
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .enable_all()
+            .build()?;
+        let browser = runtime.block_on(async {
+            match path {
+                Some(path) => Browser::web2pdf_launch_from_executable_path(path).await,
+                None => Browser::web2pdf_launch().await,
+            }
+        })?;
+        Ok(Converter { runtime, browser })
+    }
+
+    /// Converts a page to a PDF, without saving it
+    ///
+    /// # Arguments
+    /// * `url` - The URL or the path of a local file
+    /// * `options` - The options of the conversion
+    ///
+    /// # Returns
+    /// A `Result` containing the PDF data or an error.
+    pub fn convert_to_bytes(&self, url: &str, options: &ConvertOptions) -> Result<Vec<u8>> {
+        let url = resolve_url(url)?;
+        self.runtime.block_on(async {
+            let page = self.browser.web2pdf_new_page("about:blank").await?;
+            let rendered = match options.timeout_secs {
+                Some(timeout) => {
+                    tokio::time::timeout(Duration::from_secs(timeout), render(&page, &url, options))
+                        .await
+                        .unwrap_or_else(|_| {
+                            Err(format!("the conversion took longer than {}s", timeout).into())
+                        })
+                }
+                None => render(&page, &url, options).await,
+            };
+            // A failed close only leaks the tab, the PDF is still valid
+            if let Err(e) = page.close().await {
+                tracing::debug!("Web2Pdf failed to close the tab of {}: {}", url, e);
+            }
+            rendered
+        })
+    }
+
+    /// Converts a page to a PDF file
+    ///
+    /// # Arguments
+    /// * `url` - The URL or the path of a local file
+    /// * `output` - The path to save the PDF file to
+    /// * `options` - The options of the conversion
+    ///
+    /// # Returns
+    /// A `Result` containing an empty `()` value or an error.
+    pub fn convert(
+        &self,
+        url: &str,
+        output: impl AsRef<Path>,
+        options: &ConvertOptions,
+    ) -> Result<()> {
+        let pdf = self.convert_to_bytes(url, options)?;
+        std::fs::write(output, pdf)?;
+        Ok(())
+    }
+
+    /// Closes the browser and waits for it to exit
+    pub fn close(self) -> Result<()> {
+        let Converter { runtime, browser } = self;
+        runtime.block_on(browser.close_and_wait())?;
+        Ok(())
+    }
+}
+
+/// Converts a single page to a PDF file with the Chromium browser installed on the system
+/// Launching the browser takes the most time, use a `Converter` for several pages.
+///
+/// # Arguments
+/// * `url` - The URL or the path of a local file
+/// * `output` - The path to save the PDF file to
+/// * `options` - The options of the conversion
+///
+/// # Returns
+/// A `Result` containing an empty `()` value or an error.
+pub fn convert(url: &str, output: impl AsRef<Path>, options: &ConvertOptions) -> Result<()> {
+    let converter = Converter::new()?;
+    let converted = converter.convert(url, output, options);
+    converter.close()?;
+    converted
+}
+
+/// Converts a single page to a PDF with the Chromium browser installed on the system
+///
+/// # Arguments
+/// * `url` - The URL or the path of a local file
+/// * `options` - The options of the conversion
+///
+/// # Returns
+/// A `Result` containing the PDF data or an error.
+pub fn convert_to_bytes(url: &str, options: &ConvertOptions) -> Result<Vec<u8>> {
+    let converter = Converter::new()?;
+    let converted = converter.convert_to_bytes(url, options);
+    converter.close()?;
+    converted
+}
+
+/// Turns paths of existing local files into `file://` URLs
+fn resolve_url(url: &str) -> Result<String> {
+    let path = Path::new(url);
+    if !path.is_file() {
+        return Ok(url.to_string());
+    }
+    let path = std::path::absolute(path)?;
+    Ok(url::Url::from_file_path(&path)
+        .map_err(|_| format!("invalid file path {:?}", path))?
+        .to_string())
+}
+
+/// Loads the page in the tab and prints it
+async fn render(page: &Page, url: &str, options: &ConvertOptions) -> Result<Vec<u8>> {
+    let params = options.pdf_params()?;
+    if let Some(user_agent) = &options.user_agent {
+        page.web2pdf_set_user_agent(user_agent.as_str()).await?;
+    }
+    if let Some(accept_language) = &options.accept_language {
+        page.web2pdf_set_accept_language(accept_language.as_str())
+            .await?;
+    }
+    if options.screen {
+        page.emulate_media_type(MediaTypeParams::Screen).await?;
+    }
+    if let Some(width) = options.viewport_width()? {
+        page.web2pdf_set_viewport_width(width).await?;
+    }
+    page.goto(url).await?;
+    if let Some(selector) = &options.wait_for_selector {
+        if !page
+            .web2pdf_wait_for_selector(selector, DEFAULT_WAIT_TIMEOUT)
+            .await?
+        {
+            return Err(format!("{:?} did not appear on {}", selector, url).into());
+        }
+    }
+    let pdf = if options.mono {
+        page.web2pdf_pdf_mono(params).await?
+    } else {
+        page.pdf(params).await?
+    };
+    tracing::debug!("Web2Pdf converted {} ({} bytes)", url, pdf.len());
+    Ok(pdf)
+}
@@ -0,0 +1,212 @@
+use std::collections::HashSet;
+
+use base64::prelude::*;
+use chromiumoxide::cdp::browser_protocol::network::ResourceType;
+use chromiumoxide::cdp::browser_protocol::page::{
+    FrameResourceTree, GetResourceContentParams, GetResourceTreeParams,
+};
+use chromiumoxide::Page;
+
+use crate::Result;
+
+/// Javascript serializing the document into a self-contained HTML file
+///
+/// Takes a map of resource URLs to data URLs and returns the HTML of the document, where
+/// 1. stylesheets are inlined as `<style>` elements (including `@import`s)
+/// 2. images, fonts and other `url(...)` references are replaced by their data URLs
+/// 3. canvases are replaced by images of their current content
+/// 4. scripts are removed, remaining links are made absolute
+pub const SINGLE_FILE_JS: &str = r#"(async (resources) => {
+    const absolute = (url, base) => {
+        try { return new URL(url, base).href; } catch (e) { return url; }
+    };
+    const inline = (url, base) => {
+        const href = absolute(url.trim(), base);
+        return resources[href] || resources[href.split('#')[0]] || href;
+    };
+    const inlineCss = (css, base, depth = 0) => {
+        css = css.replace(/@import\s+(?:url\()?\s*(['"]?)([^'")\s]+)\1\s*\)?([^;]*);/g, (rule, quote, url, media) => {
+            const href = absolute(url, base);
+            const imported = stylesheets[href];
+            if (imported === undefined || depth > 8) return rule;
+            const inner = inlineCss(imported, href, depth + 1);
+            return media.trim() ? `@media ${media.trim()} {\n${inner}\n}` : inner;
+        });
+        return css.replace(/url\(\s*(['"]?)([^'")]+)\1\s*\)/g, (match, quote, url) =>
+            url.startsWith('data:') ? match : `url("${inline(url, base)}")`);
+    };
+    const inlineSrcset = (srcset, base) => srcset.split(',').map(candidate => {
+        const [url, ...descriptor] = candidate.trim().split(/\s+/);
+        return [inline(url, base), ...descriptor].join(' ');
+    }).join(', ');
+
+    // Stylesheets are passed as text, all other resources as data URLs
+    const stylesheets = resources.__stylesheets || {};
+    delete resources.__stylesheets;
+
+    // Canvases are not cloned with their content
+    const canvases = Array.from(document.querySelectorAll('canvas')).map(canvas => {
+        try { return canvas.toDataURL(); } catch (e) { return null; }
+    });
+
+    const root = document.documentElement.cloneNode(true);
+    const base = document.baseURI;
+
+    root.querySelectorAll('canvas').forEach((canvas, i) => {
+        if (!canvases[i]) return;
+        const img = document.createElement('img');
+        img.src = canvases[i];
+        img.width = canvas.width;
+        img.height = canvas.height;
+        img.setAttribute('style', canvas.getAttribute('style') || '');
+        img.className = canvas.className;
+        canvas.replaceWith(img);
+    });
+
+    root.querySelectorAll('link[rel~="stylesheet"]').forEach(link => {
+        const href = absolute(link.getAttribute('href') || '', base);
+        const css = stylesheets[href];
+        if (css === undefined) {
+            link.href = href;
+            return;
+        }
+        const style = document.createElement('style');
+        if (link.media) style.media = link.media;
+        style.textContent = inlineCss(css, href);
+        link.replaceWith(style);
+    });
+    root.querySelectorAll('style').forEach(style => {
+        style.textContent = inlineCss(style.textContent, base);
+    });
+    root.querySelectorAll('[style]').forEach(element => {
+        element.setAttribute('style', inlineCss(element.getAttribute('style'), base));
+    });
+
+    root.querySelectorAll('img[src], input[type="image"][src], video[poster], source[src], audio[src], video[src]').forEach(element => {
+        const attribute = element.hasAttribute('poster') ? 'poster' : 'src';
+        const value = element.getAttribute(attribute);
+        if (!value.startsWith('data:')) element.setAttribute(attribute, inline(value, base));
+    });
+    root.querySelectorAll('img[srcset], source[srcset]').forEach(element => {
+        element.setAttribute('srcset', inlineSrcset(element.getAttribute('srcset'), base));
+    });
+    root.querySelectorAll('svg image').forEach(image => {
+        for (const attribute of ['href', 'xlink:href']) {
+            const value = image.getAttribute(attribute);
+            if (value && !value.startsWith('data:')) image.setAttribute(attribute, inline(value, base));
+        }
+    });
+    root.querySelectorAll('link[rel~="icon"]').forEach(link => {
+        link.href = inline(link.getAttribute('href') || '', base);
+    });
+
+    // The archive is static, scripts could only break it
+    root.querySelectorAll('script, noscript, link[rel~="preload"], link[rel~="modulepreload"], link[rel~="prefetch"], base')
+        .forEach(element => element.remove());
+    root.querySelectorAll('*').forEach(element => {
+        for (const attribute of Array.from(element.attributes)) {
+            if (attribute.name.startsWith('on')) element.removeAttribute(attribute.name);
+        }
+    });
+    root.querySelectorAll('a[href], area[href], form[action], iframe[src]').forEach(element => {
+        const attribute = element.hasAttribute('action') ? 'action' : (element.hasAttribute('src') ? 'src' : 'href');
+        const value = element.getAttribute(attribute);
+        if (!value.startsWith('#') && !value.startsWith('javascript:')) {
+            element.setAttribute(attribute, absolute(value, base));
+        }
+    });
+
+    // Keep the current state of form fields
+    const fields = document.querySelectorAll('input, textarea, select');
+    root.querySelectorAll('input, textarea, select').forEach((field, i) => {
+        const live = fields[i];
+        if (!live) return;
+        if (field.tagName === 'TEXTAREA') field.textContent = live.value;
+        else if (field.tagName === 'SELECT') Array.from(field.options).forEach((option, j) => {
+            if (live.options[j] && live.options[j].selected) option.setAttribute('selected', '');
+            else option.removeAttribute('selected');
+        });
+        else if (live.type === 'checkbox' || live.type === 'radio') {
+            if (live.checked) field.setAttribute('checked', ''); else field.removeAttribute('checked');
+        } else if (live.type !== 'password' && live.type !== 'file') field.setAttribute('value', live.value);
+    });
+
+    const head = root.querySelector('head');
+    if (head && !head.querySelector('meta[charset]')) {
+        const charset = document.createElement('meta');
+        charset.setAttribute('charset', 'utf-8');
+        head.prepend(charset);
+    }
+
+    const doctype = document.doctype ? new XMLSerializer().serializeToString(document.doctype) : '<!DOCTYPE html>';
+    return `${doctype}\n${root.outerHTML}`;
+})"#;
+
+/// Collects the resources loaded by the main frame of a page from the browser cache
+///
+/// # Returns
+/// A JSON object mapping resource URLs to data URLs, stylesheets are mapped to their text
+/// in the `__stylesheets` key
+pub(crate) async fn collect_resources(page: &Page) -> Result<serde_json::Value> {
+    let tree = page.execute(GetResourceTreeParams::default()).await?;
+    let FrameResourceTree {
+        frame, resources, ..
+    } = &tree.result.frame_tree;
+
+    let mut data_urls = serde_json::Map::new();
+    let mut stylesheets = serde_json::Map::new();
+    let mut seen = HashSet::new();
+    for resource in resources {
+        if resource.failed == Some(true) || resource.canceled == Some(true) {
+            continue;
+        }
+        if !matches!(
+            resource.r#type,
+            ResourceType::Stylesheet | ResourceType::Image | ResourceType::Font
+        ) || !seen.insert(resource.url.clone())
+        {
+            continue;
+        }
+
+        let content = match page
+            .execute(GetResourceContentParams::new(
+                frame.id.clone(),
+                resource.url.clone(),
+            ))
+            .await
+        {
+            Ok(content) => content.result,
+            Err(e) => {
+                tracing::debug!("Web2Pdf could not inline resource {}: {}", resource.url, e);
+                continue;
+            }
+        };
+
+        if resource.r#type == ResourceType::Stylesheet {
+            let css = if content.base64_encoded {
+                String::from_utf8_lossy(&BASE64_STANDARD.decode(&content.content)?).into_owned()
+            } else {
+                content.content
+            };
+            stylesheets.insert(resource.url.clone(), css.into());
+        } else {
+            let data = if content.base64_encoded {
+                content.content
+            } else {
+                BASE64_STANDARD.encode(content.content)
+            };
+            data_urls.insert(
+                resource.url.clone(),
+                format!("data:{};base64,{}", resource.mime_type, data).into(),
+            );
+        }
+    }
+
+    tracing::debug!(
+        "Web2Pdf collected {} resources and {} stylesheets to inline",
+        data_urls.len(),
+        stylesheets.len()
+    );
+    data_urls.insert(String::from("__stylesheets"), stylesheets.into());
+    Ok(data_urls.into())
+}
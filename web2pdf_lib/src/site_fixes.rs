@@ -0,0 +1,129 @@
+use std::{fmt, path::Path, sync::OnceLock, time::Duration};
+
+use serde::Deserialize;
+use url::Url;
+
+use crate::Result;
+
+/// The built-in site fixes, see `site_fixes.json`
+const BUILTIN_SITE_FIXES: &str = include_str!("site_fixes.json");
+
+/// Error for when a site fixes file is invalid
+#[derive(Debug, Clone)]
+struct SiteFixesParseError {
+    error_message: String,
+}
+impl fmt::Display for SiteFixesParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Error parsing site fixes: {}", self.error_message)
+    }
+}
+impl std::error::Error for SiteFixesParseError {}
+
+/// A versioned set of site fixes
+///
+/// Site fixes adjust popular sites that do not print well, e.g. by removing sign up overlays
+/// or waiting for content that is rendered late. They are stored as JSON:
+/// `{"version": 1, "fixes": [{"name": "...", "hosts": ["example.com"], "remove": [".overlay"]}]}`
+#[derive(Debug, Clone, Deserialize)]
+pub struct SiteFixes {
+    pub version: u32,
+    pub fixes: Vec<SiteFix>,
+}
+
+/// Fixes applied to all pages of some hosts
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct SiteFix {
+    pub name: String,
+    /// Hosts the fix applies to, including their subdomains
+    pub hosts: Vec<String>,
+    /// Script evaluated in every document before any of its own scripts
+    pub init_script: Option<String>,
+    /// CSS selector of an element to wait for after loading the page
+    pub wait_for: Option<String>,
+    /// Additional time to wait after loading the page, in milliseconds
+    pub delay_ms: Option<u64>,
+    /// CSS selectors of elements to remove
+    pub remove: Vec<String>,
+    /// CSS to add to the page
+    pub css: Option<String>,
+}
+
+impl SiteFixes {
+    /// The latest supported version of the site fixes format
+    pub const VERSION: u32 = 1;
+
+    /// The site fixes shipped with web2pdf
+    pub fn builtin() -> &'static SiteFixes {
+        static BUILTIN: OnceLock<SiteFixes> = OnceLock::new();
+        BUILTIN.get_or_init(|| {
+            SiteFixes::parse(BUILTIN_SITE_FIXES).expect("Built-in site fixes are invalid")
+        })
+    }
+
+    /// Parses site fixes from JSON
+    ///
+    /// # Arguments
+    /// * `json` - The site fixes as JSON
+    ///
+    /// # Returns
+    /// A `Result` containing the site fixes or an error.
+    pub fn parse(json: &str) -> Result<SiteFixes> {
+        let site_fixes: SiteFixes = serde_json::from_str(json)?;
+        if site_fixes.version > Self::VERSION {
+            return Err(Box::new(SiteFixesParseError {
+                error_message: format!(
+                    "Unsupported version {}, the latest supported version is {}",
+                    site_fixes.version,
+                    Self::VERSION
+                ),
+            }));
+        }
+        Ok(site_fixes)
+    }
+
+    /// Reads and parses a site fixes file
+    ///
+    /// # Arguments
+    /// * `path` - The path of the site fixes file
+    ///
+    /// # Returns
+    /// A `Result` containing the site fixes or an error.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<SiteFixes> {
+        SiteFixes::parse(&std::fs::read_to_string(path)?)
+    }
+
+    /// All fixes applying to a URL
+    ///
+    /// # Arguments
+    /// * `url` - The URL of the page
+    pub fn matching(&self, url: &str) -> impl Iterator<Item = &SiteFix> {
+        let host = Url::parse(url)
+            .ok()
+            .and_then(|url| url.host_str().map(str::to_lowercase));
+        self.fixes.iter().filter(move |fix| {
+            host.as_deref().is_some_and(|host| {
+                fix.hosts
+                    .iter()
+                    .any(|fix_host| host_matches(host, fix_host))
+            })
+        })
+    }
+}
+
+impl SiteFix {
+    /// The time to wait after loading the page
+    pub fn delay(&self) -> Option<Duration> {
+        self.delay_ms.map(Duration::from_millis)
+    }
+}
+
+/// Whether `host` is `domain` or one of its subdomains
+fn host_matches(host: &str, domain: &str) -> bool {
+    let domain = domain.trim_start_matches("*.").to_lowercase();
+    host == domain
+        || host
+            .strip_suffix(&domain)
+            .is_some_and(|prefix| prefix.ends_with('.'))
+}
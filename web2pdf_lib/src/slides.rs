@@ -0,0 +1,35 @@
+use std::time::Duration;
+
+/// Upper bound of captured slides, in case a deck never reports its end
+pub const MAX_SLIDES: usize = 1000;
+
+/// Time given to a deck to finish its transition after advancing
+pub const SLIDE_TRANSITION_DELAY: Duration = Duration::from_millis(500);
+
+/// Javascript preparing a slide deck for capturing and jumping to the first slide
+///
+/// reveal.js decks are controlled through their API, with fragments and transitions disabled so
+/// every slide is captured once and complete. Returns whether the page is a reveal.js deck, other
+/// decks are stepped through with keyboard events.
+pub const PREPARE_SLIDES_JS: &str = r#"(() => {
+    const reveal = window.Reveal;
+    if (reveal && typeof reveal.next === 'function' && typeof reveal.isLastSlide === 'function') {
+        reveal.configure({ fragments: false, transition: 'none', backgroundTransition: 'none', controls: false, progress: false });
+        reveal.slide(0, 0);
+        return true;
+    }
+    if (document.activeElement && document.activeElement !== document.body) {
+        document.activeElement.blur();
+    }
+    return false;
+})()"#;
+
+/// Javascript advancing a reveal.js deck, returns false on the last slide
+pub const REVEAL_NEXT_SLIDE_JS: &str = r#"(() => {
+    if (window.Reveal.isLastSlide()) return false;
+    window.Reveal.next();
+    return true;
+})()"#;
+
+/// Javascript returning the position in the deck, slide decks keep it in the URL fragment
+pub const SLIDE_POSITION_JS: &str = r#"(() => location.href)()"#;
@@ -0,0 +1,70 @@
+use crate::code_view::escape_html;
+use crate::util;
+
+/// Whether a URL points to a standalone SVG file, detected from its file extension
+///
+/// # Arguments
+/// * `url` - The URL of the file
+pub fn is_svg_url(url: &str) -> bool {
+    util::url_file_name(url)
+        .and_then(|name| {
+            name.rsplit_once('.')
+                .map(|(_, extension)| extension.eq_ignore_ascii_case("svg"))
+        })
+        .unwrap_or(false)
+}
+
+/// Embeds an SVG file into an otherwise empty HTML page, relative references are resolved against `url`
+///
+/// # Arguments
+/// * `url` - The URL of the SVG file
+/// * `svg` - The contents of the SVG file
+pub fn render_svg_page(url: &str, svg: &str) -> String {
+    // The XML declaration and doctype are not valid in HTML
+    let svg = svg.trim_start_matches('\u{feff}').trim_start();
+    let svg = match svg.find("<svg") {
+        Some(start) => &svg[start..],
+        None => svg,
+    };
+    format!(
+        "<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\"><base href=\"{}\"><title>{}</title><style>html, body {{ margin: 0; padding: 0; }} body > svg {{ display: block; }}</style></head><body>{}</body></html>",
+        escape_html(url),
+        escape_html(&util::url_file_name(url).unwrap_or_else(|| url.to_string())),
+        svg
+    )
+}
+
+/// Javascript fixing the size of the embedded SVG to its intrinsic dimensions in CSS pixels
+///
+/// The size is taken from the `width`/`height` attributes, missing ones are derived from the
+/// aspect ratio of the `viewBox`. Returns the size as `[width, height]` or null without SVG.
+pub const SVG_INTRINSIC_SIZE_JS: &str = r#"(() => {
+    const svg = document.querySelector('body > svg');
+    if (!svg) return null;
+    // Percentages (and missing attributes) depend on the page size, so they are not intrinsic
+    const length = (animated) => {
+        const value = animated.baseVal;
+        return value.unitType === SVGLength.SVG_LENGTHTYPE_PERCENTAGE || !(value.value > 0) ? null : value.value;
+    };
+    let width = svg.hasAttribute('width') ? length(svg.width) : null;
+    let height = svg.hasAttribute('height') ? length(svg.height) : null;
+    const viewBox = svg.viewBox.baseVal;
+    if (viewBox && viewBox.width > 0 && viewBox.height > 0) {
+        if (width === null && height === null) {
+            width = viewBox.width;
+            height = viewBox.height;
+        } else if (width === null) {
+            width = height * viewBox.width / viewBox.height;
+        } else if (height === null) {
+            height = width * viewBox.height / viewBox.width;
+        }
+    }
+    if (width === null || height === null) {
+        const box = svg.getBBox();
+        width = width ?? (box.x + box.width || 300);
+        height = height ?? (box.y + box.height || 150);
+    }
+    svg.setAttribute('width', `${width}px`);
+    svg.setAttribute('height', `${height}px`);
+    return [width, height];
+})()"#;
@@ -0,0 +1,163 @@
+use std::fmt::Write;
+
+use crate::code_view::escape_html;
+use crate::util;
+
+/// The delimiter of a CSV/TSV file, detected from the file extension of its URL
+///
+/// # Arguments
+/// * `url` - The URL of the file
+///
+/// # Returns
+/// `,` for `.csv` and `\t` for `.tsv`/`.tab` files, None for other files
+pub fn delimiter_for_url(url: &str) -> Option<char> {
+    let name = util::url_file_name(url)?;
+    let (_, extension) = name.rsplit_once('.')?;
+    match extension.to_lowercase().as_str() {
+        "csv" => Some(','),
+        "tsv" | "tab" => Some('\t'),
+        _ => None,
+    }
+}
+
+/// Guesses the delimiter of a CSV file from its first line
+/// Spreadsheet exports in many locales use `;` as delimiter, as `,` is the decimal separator.
+///
+/// # Arguments
+/// * `text` - The contents of the file
+/// * `default` - The delimiter used if the first line gives no hint
+pub fn sniff_delimiter(text: &str, default: char) -> char {
+    let first_line = text.lines().next().unwrap_or_default();
+    let count = |delimiter: char| first_line.matches(delimiter).count();
+    if default == ',' && count(';') > count(',') {
+        ';'
+    } else {
+        default
+    }
+}
+
+/// Parses delimiter separated values (RFC 4180)
+/// Fields may be quoted with `"`, quoted fields may contain delimiters, line breaks and `""`.
+///
+/// # Arguments
+/// * `text` - The contents of the file
+/// * `delimiter` - The field delimiter, e.g. `,`
+///
+/// # Returns
+/// The rows of the file, rows may have different lengths
+pub fn parse_delimited(text: &str, delimiter: char) -> Vec<Vec<String>> {
+    let text = text.strip_prefix('\u{feff}').unwrap_or(text);
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if quoted {
+            match c {
+                '"' if chars.peek() == Some(&'"') => {
+                    field.push('"');
+                    chars.next();
+                }
+                '"' => quoted = false,
+                c => field.push(c),
+            }
+            continue;
+        }
+        match c {
+            '"' if field.is_empty() => quoted = true,
+            c if c == delimiter => row.push(std::mem::take(&mut field)),
+            '\r' if chars.peek() == Some(&'\n') => {}
+            '\n' | '\r' => {
+                row.push(std::mem::take(&mut field));
+                rows.push(std::mem::take(&mut row));
+            }
+            c => field.push(c),
+        }
+    }
+    if !field.is_empty() || !row.is_empty() {
+        row.push(field);
+        rows.push(row);
+    }
+    rows
+}
+
+const TABLE_CSS: &str = "
+* { box-sizing: border-box; }
+html, body { margin: 0; -webkit-print-color-adjust: exact; print-color-adjust: exact; }
+body { font: 10px/1.35 system-ui, -apple-system, 'Segoe UI', Roboto, 'Helvetica Neue', Arial, sans-serif; color: #1f2328; }
+h1 { font-size: 14px; margin: 0 0 8px; }
+table { width: 100%; border-collapse: collapse; }
+thead { display: table-header-group; }
+th, td { padding: 3px 6px; border: 1px solid #d0d7de; text-align: left; vertical-align: top; overflow-wrap: anywhere; white-space: pre-line; }
+th { background: #eaeef2; font-weight: 600; }
+tbody tr:nth-child(even) { background: #f6f8fa; }
+tr { break-inside: avoid; }
+td.num { text-align: right; font-variant-numeric: tabular-nums; }
+";
+
+/// Renders rows as HTML table, the first row is used as header which is repeated on every page
+///
+/// # Arguments
+/// * `title` - The title shown above the table, e.g. the file name
+/// * `rows` - The rows, see `parse_delimited`
+pub fn render_table(title: &str, rows: &[Vec<String>]) -> String {
+    let columns = rows.iter().map(Vec::len).max().unwrap_or(0);
+
+    let mut body = format!("<h1>{}</h1><table>", escape_html(title));
+    if let Some((header, rows)) = rows.split_first() {
+        body.push_str("<thead><tr>");
+        for column in 0..columns {
+            let _ = write!(body, "<th>{}</th>", escape_html(cell(header, column)));
+        }
+        body.push_str("</tr></thead><tbody>");
+        for row in rows {
+            body.push_str("<tr>");
+            for column in 0..columns {
+                let value = cell(row, column);
+                let class = if is_number(value) {
+                    " class=\"num\""
+                } else {
+                    ""
+                };
+                let _ = write!(body, "<td{}>{}</td>", class, escape_html(value));
+            }
+            body.push_str("</tr>");
+        }
+        body.push_str("</tbody>");
+    }
+    body.push_str("</table>");
+
+    format!(
+        "<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\"><title>{}</title><style>{}</style></head><body>{}</body></html>",
+        escape_html(title),
+        TABLE_CSS,
+        body
+    )
+}
+
+fn cell(row: &[String], column: usize) -> &str {
+    row.get(column).map_or("", String::as_str)
+}
+
+/// Whether a cell looks like a number (e.g. "-1,234.50", "3,5", "12%"), numbers are aligned right
+fn is_number(value: &str) -> bool {
+    let value = value.trim();
+    let value = value.strip_suffix('%').unwrap_or(value);
+    let value = value.strip_prefix(['-', '+']).unwrap_or(value);
+    value.starts_with(|c: char| c.is_ascii_digit())
+        && value
+            .chars()
+            .all(|c| c.is_ascii_digit() || matches!(c, '.' | ',' | '\'' | ' '))
+}
+
+/// Javascript returning whether the table is wider than the page when its cells are not wrapped
+pub const TABLE_OVERFLOWS_JS: &str = r#"(() => {
+    const table = document.querySelector('table');
+    if (!table) return false;
+    table.style.width = 'max-content';
+    const width = table.getBoundingClientRect().width;
+    table.style.width = '';
+    return width > document.documentElement.clientWidth;
+})()"#;
@@ -0,0 +1,233 @@
+use lopdf::content::{Content, Operation};
+use lopdf::{dictionary, Document, Object, ObjectId, Stream, StringFormat};
+
+use crate::pdf::{encode_win_ansi, helvetica_width, inherited_attribute, save_to_vec};
+use crate::Result;
+
+/// Margin of the table of contents pages, in points (1 inch)
+const MARGIN: f32 = 72.0;
+const HEADING_SIZE: f32 = 20.0;
+const FONT_SIZE: f32 = 11.0;
+const LINE_HEIGHT: f32 = 20.0;
+/// Space between the title, the dot leaders and the page number
+const GAP: f32 = 6.0;
+
+/// Used if the first page has no media box (US Letter, Chrome's default)
+const DEFAULT_PAGE_SIZE: (f32, f32) = (612.0, 792.0);
+
+/// An entry of a table of contents
+#[derive(Debug, Clone)]
+pub struct TocEntry {
+    pub title: String,
+    /// Index of the first page of the entry, 0 for the first page of the PDF
+    pub page: usize,
+}
+
+/// Prepends table of contents pages to a PDF
+///
+/// Every entry lists its title and page number (counting the added pages) and links to its page.
+/// The text is set in Helvetica, characters outside of Latin-1 are replaced by `?`.
+///
+/// # Arguments
+/// * `pdf` - The PDF data
+/// * `heading` - The heading of the first table of contents page, e.g. "Contents"
+/// * `entries` - The entries, entries pointing to missing pages are left out
+///
+/// # Returns
+/// * The modified PDF data
+pub fn prepend_toc(pdf: &[u8], heading: &str, entries: &[TocEntry]) -> Result<Vec<u8>> {
+    let mut document = Document::load_mem(pdf)?;
+    let page_ids: Vec<ObjectId> = document.get_pages().into_values().collect();
+    let entries: Vec<&TocEntry> = entries
+        .iter()
+        .filter(|entry| entry.page < page_ids.len())
+        .collect();
+
+    let (width, height) = page_ids
+        .first()
+        .and_then(|&page_id| page_size(&document, page_id))
+        .unwrap_or(DEFAULT_PAGE_SIZE);
+    let first_page_lines = ((height - 2.0 * MARGIN - 2.0 * HEADING_SIZE) / LINE_HEIGHT).max(1.0);
+    let lines = ((height - 2.0 * MARGIN) / LINE_HEIGHT).max(1.0);
+    let mut chunks = Vec::new();
+    let mut remaining = entries.as_slice();
+    loop {
+        let capacity = if chunks.is_empty() {
+            first_page_lines
+        } else {
+            lines
+        } as usize;
+        let (chunk, rest) = remaining.split_at(capacity.min(remaining.len()));
+        chunks.push(chunk);
+        remaining = rest;
+        if remaining.is_empty() {
+            break;
+        }
+    }
+    let toc_pages = chunks.len();
+
+    let font_id = document.add_object(dictionary! {
+        "Type" => "Font",
+        "Subtype" => "Type1",
+        "BaseFont" => "Helvetica",
+        "Encoding" => "WinAnsiEncoding",
+    });
+    let bold_font_id = document.add_object(dictionary! {
+        "Type" => "Font",
+        "Subtype" => "Type1",
+        "BaseFont" => "Helvetica-Bold",
+        "Encoding" => "WinAnsiEncoding",
+    });
+    let pages_id = document
+        .catalog()?
+        .get(b"Pages")
+        .and_then(Object::as_reference)?;
+
+    let mut toc_page_ids = Vec::with_capacity(toc_pages);
+    for (index, chunk) in chunks.into_iter().enumerate() {
+        let mut operations = Vec::new();
+        let mut annotations = Vec::new();
+        let mut y = height - MARGIN;
+        if index == 0 {
+            y -= HEADING_SIZE;
+            operations.extend(text(
+                b"F2",
+                HEADING_SIZE,
+                MARGIN,
+                y,
+                encode_win_ansi(heading),
+            ));
+            y -= HEADING_SIZE;
+        }
+
+        for entry in chunk {
+            y -= LINE_HEIGHT;
+            let number = encode_win_ansi(&(entry.page + toc_pages + 1).to_string());
+            let number_width = text_width(&number, FONT_SIZE);
+            let title_width = width - 2.0 * MARGIN - number_width - 2.0 * GAP;
+            let title = truncate(encode_win_ansi(&entry.title), title_width, FONT_SIZE);
+
+            let leader_start = MARGIN + text_width(&title, FONT_SIZE) + GAP;
+            let leader_end = width - MARGIN - number_width - GAP;
+            let dot_width = text_width(b".", FONT_SIZE);
+            let dots = ((leader_end - leader_start) / dot_width).max(0.0) as usize;
+
+            operations.extend(text(b"F1", FONT_SIZE, MARGIN, y, title));
+            if dots > 0 {
+                operations.extend(text(
+                    b"F1",
+                    FONT_SIZE,
+                    leader_end - dots as f32 * dot_width,
+                    y,
+                    vec![b'.'; dots],
+                ));
+            }
+            operations.extend(text(
+                b"F1",
+                FONT_SIZE,
+                width - MARGIN - number_width,
+                y,
+                number,
+            ));
+
+            annotations.push(Object::Dictionary(dictionary! {
+                "Type" => "Annot",
+                "Subtype" => "Link",
+                "Rect" => vec![
+                    MARGIN.into(),
+                    (y - 5.0).into(),
+                    (width - MARGIN).into(),
+                    (y + FONT_SIZE + 3.0).into(),
+                ],
+                "Border" => vec![0.into(), 0.into(), 0.into()],
+                "Dest" => vec![
+                    Object::Reference(page_ids[entry.page]),
+                    Object::Name(b"Fit".to_vec()),
+                ],
+            }));
+        }
+
+        let content = Content { operations }.encode()?;
+        let mut content = Stream::new(dictionary! {}, content);
+        let _ = content.compress();
+        let content_id = document.add_object(content);
+        toc_page_ids.push(document.add_object(dictionary! {
+            "Type" => "Page",
+            "Parent" => pages_id,
+            "MediaBox" => vec![0.into(), 0.into(), width.into(), height.into()],
+            "Resources" => dictionary! {
+                "Font" => dictionary! {
+                    "F1" => font_id,
+                    "F2" => bold_font_id,
+                },
+            },
+            "Contents" => content_id,
+            "Annots" => annotations,
+        }));
+    }
+
+    let pages = document.get_dictionary_mut(pages_id)?;
+    let mut kids: Vec<Object> = toc_page_ids.into_iter().map(Object::Reference).collect();
+    kids.extend(
+        pages
+            .get(b"Kids")
+            .and_then(Object::as_array)?
+            .iter()
+            .cloned(),
+    );
+    let count = pages.get(b"Count").and_then(Object::as_i64).unwrap_or(0);
+    pages.set("Kids", kids);
+    pages.set("Count", count + toc_pages as i64);
+
+    save_to_vec(&mut document)
+}
+
+/// Operations showing a line of (encoded) text with its baseline at `x`, `y`
+fn text(font: &[u8], size: f32, x: f32, y: f32, text: Vec<u8>) -> Vec<Operation> {
+    vec![
+        Operation::new("BT", vec![]),
+        Operation::new("Tf", vec![Object::Name(font.to_vec()), size.into()]),
+        Operation::new("Td", vec![x.into(), y.into()]),
+        Operation::new("Tj", vec![Object::String(text, StringFormat::Literal)]),
+        Operation::new("ET", vec![]),
+    ]
+}
+
+/// Width of encoded text in Helvetica
+fn text_width(text: &[u8], size: f32) -> f32 {
+    text.iter()
+        .map(|&byte| f32::from(helvetica_width(byte)) * size / 1000.0)
+        .sum()
+}
+
+/// Shortens encoded text to a maximal width, ending it with an ellipsis
+fn truncate(mut text: Vec<u8>, max_width: f32, size: f32) -> Vec<u8> {
+    if text_width(&text, size) <= max_width {
+        return text;
+    }
+    // The ellipsis of WinAnsiEncoding is 1000 units wide
+    let ellipsis_width = size;
+    while !text.is_empty() && text_width(&text, size) + ellipsis_width > max_width {
+        text.pop();
+    }
+    while text.last() == Some(&b' ') {
+        text.pop();
+    }
+    text.push(0x85);
+    text
+}
+
+/// The size of a page, from its (possibly inherited) media box
+fn page_size(document: &Document, page_id: ObjectId) -> Option<(f32, f32)> {
+    let media_box = document
+        .get_dictionary(page_id)
+        .ok()?
+        .get(b"MediaBox")
+        .ok()
+        .cloned()
+        .or_else(|| inherited_attribute(document, page_id, b"MediaBox"))?;
+    let (_, media_box) = document.dereference(&media_box).ok()?;
+    let values = media_box.as_array().ok()?;
+    let value = |index: usize| values.get(index)?.as_float().ok();
+    Some(((value(2)? - value(0)?).abs(), (value(3)? - value(1)?).abs()))
+}
@@ -0,0 +1,139 @@
+//! Trusting additional CA certificates, e.g. of a corporate TLS-intercepting proxy
+//! The browser is told to accept certificate chains containing the public key of a trusted
+//! certificate (`--ignore-certificate-errors-spki-list`), without changing the trust store of
+//! the system. Only certificates sent in the chain of the server are matched, which is the case
+//! for intercepting proxies and self-signed servers.
+
+use std::fmt;
+use std::path::Path;
+
+use base64::prelude::*;
+use sha2::{Digest, Sha256};
+
+use crate::Result;
+
+/// Error for when reading a certificate
+#[derive(Debug, Clone)]
+struct CertificateError {
+    error_message: String,
+}
+impl fmt::Display for CertificateError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Error reading certificate: {}", self.error_message)
+    }
+}
+impl std::error::Error for CertificateError {
+    fn description(&self) -> &str {
+        &self.error_message
+    }
+}
+
+fn certificate_error(message: impl Into<String>) -> Box<dyn std::error::Error> {
+    Box::new(CertificateError {
+        error_message: message.into(),
+    })
+}
+
+/// Builds the browser flag trusting the certificates of the given files
+///
+/// # Arguments
+/// * `paths` - Certificate files in PEM (may contain several certificates) or DER format
+///
+/// # Returns
+/// A `Result` containing the browser flag or an error.
+pub fn trust_flag(paths: &[impl AsRef<Path>]) -> Result<String> {
+    let mut hashes = Vec::new();
+    for path in paths {
+        let path = path.as_ref();
+        let certificates = read_certificates(&std::fs::read(path)?)
+            .map_err(|e| certificate_error(format!("{}: {}", path.display(), e)))?;
+        for certificate in certificates {
+            hashes.push(spki_hash(&certificate)?);
+        }
+    }
+    tracing::debug!("Web2Pdf trusting public keys {:?}", hashes);
+    Ok(format!(
+        "--ignore-certificate-errors-spki-list={}",
+        hashes.join(",")
+    ))
+}
+
+/// Splits a PEM file into DER certificates, other files are returned as a single DER certificate
+fn read_certificates(contents: &[u8]) -> std::result::Result<Vec<Vec<u8>>, String> {
+    const BEGIN: &str = "-----BEGIN CERTIFICATE-----";
+    const END: &str = "-----END CERTIFICATE-----";
+
+    let Ok(text) = std::str::from_utf8(contents) else {
+        return Ok(vec![contents.to_vec()]);
+    };
+    if !text.contains(BEGIN) {
+        return Err("no PEM certificate found".to_string());
+    }
+    let mut certificates = Vec::new();
+    for block in text.split(BEGIN).skip(1) {
+        let body = block
+            .split(END)
+            .next()
+            .filter(|_| block.contains(END))
+            .ok_or("unterminated PEM certificate")?;
+        let body: String = body.chars().filter(|c| !c.is_whitespace()).collect();
+        certificates.push(BASE64_STANDARD.decode(body).map_err(|e| e.to_string())?);
+    }
+    Ok(certificates)
+}
+
+/// The base64 encoded SHA-256 hash of the SubjectPublicKeyInfo of a DER certificate
+fn spki_hash(certificate: &[u8]) -> Result<String> {
+    let invalid = || certificate_error("invalid DER certificate");
+    // Certificate ::= SEQUENCE { tbsCertificate, signatureAlgorithm, signature }
+    let (certificate, _) = der_element(certificate, 0x30).ok_or_else(invalid)?;
+    let (tbs, _) = der_element(certificate.content, 0x30).ok_or_else(invalid)?;
+    // TBSCertificate ::= SEQUENCE { [0] version OPTIONAL, serialNumber, signature, issuer,
+    //                               validity, subject, subjectPublicKeyInfo, ... }
+    let mut rest = tbs.content;
+    if rest.first() == Some(&0xa0) {
+        rest = der_element(rest, 0xa0).ok_or_else(invalid)?.1;
+    }
+    for tag in [0x02, 0x30, 0x30, 0x30, 0x30] {
+        rest = der_element(rest, tag).ok_or_else(invalid)?.1;
+    }
+    let (spki, _) = der_element(rest, 0x30).ok_or_else(invalid)?;
+    Ok(BASE64_STANDARD.encode(Sha256::digest(spki.encoded)))
+}
+
+/// A DER element, with and without its tag and length
+struct DerElement<'a> {
+    encoded: &'a [u8],
+    content: &'a [u8],
+}
+
+/// Reads the DER element at the start of `data` if it has the given tag
+///
+/// # Returns
+/// The element and the data after it
+fn der_element(data: &[u8], tag: u8) -> Option<(DerElement<'_>, &[u8])> {
+    if *data.first()? != tag {
+        return None;
+    }
+    let (length, header) = match *data.get(1)? {
+        length @ 0..=0x7f => (length as usize, 2),
+        0x81..=0x84 => {
+            let bytes = (data[1] & 0x7f) as usize;
+            let length = data
+                .get(2..2 + bytes)?
+                .iter()
+                .fold(0usize, |length, byte| length << 8 | *byte as usize);
+            (length, 2 + bytes)
+        }
+        _ => return None,
+    };
+    let end = header.checked_add(length)?;
+    let encoded = data.get(..end)?;
+    Some((
+        DerElement {
+            encoded,
+            content: &encoded[header..],
+        },
+        &data[end..],
+    ))
+}
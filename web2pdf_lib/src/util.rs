@@ -1,6 +1,11 @@
 use std::fmt;
 
-use chromiumoxide::cdp::browser_protocol::network::{CookieParam, CookieSameSite, TimeSinceEpoch};
+use serde::Deserialize;
+use sha1::{Digest, Sha1};
+
+use chromiumoxide::cdp::browser_protocol::network::{
+    Cookie, CookieParam, CookieSameSite, TimeSinceEpoch,
+};
 
 use crate::Result;
 
@@ -11,9 +16,7 @@ struct CookieFileParseError {
 }
 impl CookieFileParseError {
     fn new(error_message: String) -> CookieFileParseError {
-        CookieFileParseError {
-            error_message: error_message,
-        }
+        CookieFileParseError { error_message }
     }
 }
 impl fmt::Display for CookieFileParseError {
@@ -27,8 +30,46 @@ impl std::error::Error for CookieFileParseError {
     }
 }
 
-/// Parse a cookie file
-/// As specified in https://curl.se/docs/http-cookies.html
+/// Format of a cookie file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CookieFormat {
+    /// The Netscape format used by curl and wget, see https://curl.se/docs/http-cookies.html
+    Netscape,
+    /// A JSON array of cookies, as exported by Chrome DevTools, Puppeteer or EditThisCookie
+    Json,
+}
+
+impl CookieFormat {
+    /// Detects the format of a cookie file from its contents
+    /// JSON files start with `[` or `{`, everything else is read as Netscape format.
+    ///
+    /// # Arguments
+    /// * `file_contents` - The contents of the cookie file
+    pub fn detect(file_contents: &str) -> CookieFormat {
+        match file_contents
+            .trim_start_matches('\u{feff}')
+            .trim_start()
+            .chars()
+            .next()
+        {
+            Some('[' | '{') => CookieFormat::Json,
+            _ => CookieFormat::Netscape,
+        }
+    }
+}
+
+/// How malformed entries of a cookie file are handled
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CookieParsing {
+    /// Fails on the first malformed entry
+    #[default]
+    Strict,
+    /// Skips malformed entries with a warning, exported jars often contain a few broken lines
+    Lenient,
+}
+
+/// Parse a cookie file, detecting its format
+/// See `CookieFormat::detect`, malformed entries are errors.
 ///
 /// # Arguments
 /// * `file_contents` - The contents of the cookie file
@@ -36,57 +77,392 @@ impl std::error::Error for CookieFileParseError {
 /// # Returns
 /// * A vector of CookieParam structs
 pub fn parse_cookie_file(file_contents: &str) -> Result<Vec<CookieParam>> {
-    let mut cookies: Vec<CookieParam> = Vec::new();
+    parse_cookie_file_as(file_contents, None, CookieParsing::Strict)
+}
+
+/// Parse a cookie file of a given format
+///
+/// # Arguments
+/// * `file_contents` - The contents of the cookie file
+/// * `format` - The format of the cookie file, None to detect it (see `CookieFormat::detect`)
+/// * `parsing` - Whether malformed entries are errors or skipped
+///
+/// # Returns
+/// * A vector of CookieParam structs
+pub fn parse_cookie_file_as(
+    file_contents: &str,
+    format: Option<CookieFormat>,
+    parsing: CookieParsing,
+) -> Result<Vec<CookieParam>> {
+    let entries = match format.unwrap_or_else(|| CookieFormat::detect(file_contents)) {
+        CookieFormat::Netscape => parse_netscape_cookie_file(file_contents),
+        CookieFormat::Json => parse_json_cookie_file(file_contents)?,
+    };
+
+    let mut cookies = Vec::with_capacity(entries.len());
+    for entry in entries {
+        match entry {
+            Ok(cookie) => cookies.push(cookie),
+            Err(err) if parsing == CookieParsing::Lenient => {
+                tracing::warn!("Skipping cookie: {}", err);
+            }
+            Err(err) => {
+                tracing::error!("{}", err);
+                return Err(Box::new(err));
+            }
+        }
+    }
+    Ok(cookies)
+}
+
+/// Parse a cookie file
+/// As specified in https://curl.se/docs/http-cookies.html
+///
+/// # Arguments
+/// * `file_contents` - The contents of the cookie file
+///
+/// # Returns
+/// * The parsed cookie (or error) of every line that is not empty or a comment
+fn parse_netscape_cookie_file(
+    file_contents: &str,
+) -> Vec<std::result::Result<CookieParam, CookieFileParseError>> {
+    let mut cookies = Vec::new();
     // https://curl.se/docs/http-cookies.html
-    for line_unchanged in file_contents.lines() {
-        let mut cookie_builder = CookieParam::builder().source_port(-1);
-        let mut line = line_unchanged;
-
-        if line.starts_with("#HttpOnly_") {
-            line = &line[10..];
-            cookie_builder = cookie_builder.http_only(true);
-        } else if line.starts_with("#") {
+    for (number, line_unchanged) in file_contents.lines().enumerate() {
+        let mut line = line_unchanged.trim_end_matches('\r');
+        let mut http_only = false;
+
+        if let Some(rest) = line.strip_prefix("#HttpOnly_") {
+            line = rest;
+            http_only = true;
+        } else if line.starts_with('#') || line.trim().is_empty() {
             continue;
         }
 
-        let cookie_args: Vec<&str> = line.split('\t').collect();
-        if cookie_args.len() != 7 {
-            tracing::error!(
-                "Error parsing cookie line (Wrong number of arguments): '{}'",
-                line
-            );
-            return Err(Box::new(CookieFileParseError::new(format!(
-                "Error parsing cookie line (Wrong number of arguments): '{}'",
+        let cookie = parse_netscape_cookie_line(line, http_only).map_err(|message| {
+            CookieFileParseError::new(format!(
+                "Error parsing cookie line {} ({}): '{}'",
+                number + 1,
+                message,
                 line
-            ))));
+            ))
+        });
+        if let Ok(cookie) = &cookie {
+            tracing::trace!("Parsed cookie line: {:?} to {:?}", line_unchanged, cookie);
+        }
+        cookies.push(cookie);
+    }
+    cookies
+}
+
+/// Parse a line of a cookie file, the `#HttpOnly_` prefix is already removed
+fn parse_netscape_cookie_line(
+    line: &str,
+    http_only: bool,
+) -> std::result::Result<CookieParam, String> {
+    let cookie_args: Vec<&str> = line.split('\t').collect();
+    let [domain, include_subdomains, path, secure, expires, name, value] = cookie_args[..] else {
+        return Err("Wrong number of arguments".to_string());
+    };
+    let flag = |value: &str| match value {
+        value if value.eq_ignore_ascii_case("TRUE") => Ok(true),
+        value if value.eq_ignore_ascii_case("FALSE") => Ok(false),
+        value => Err(format!("Invalid flag '{}'", value)),
+    };
+    let secure = flag(secure)?;
+    let expires = expires
+        .parse::<f64>()
+        .map_err(|err| format!("Could not convert time: '{}'", err))?;
+    if domain.is_empty() || name.is_empty() {
+        return Err("Missing domain or name".to_string());
+    }
+
+    let mut cookie_builder = CookieParam::builder()
+        .source_port(-1)
+        .path(path)
+        .secure(secure)
+        .http_only(http_only)
+        .name(name)
+        .value(value);
+    // Cookies that don't include subdomains are only sent to their host
+    cookie_builder = if flag(include_subdomains)? {
+        cookie_builder.domain(domain)
+    } else {
+        cookie_builder.url(host_only_url(domain, path, secure))
+    };
+    // Session cookies have the expiry 0
+    if expires > 0.0 {
+        cookie_builder = cookie_builder.expires(TimeSinceEpoch::new(expires));
+    }
+    cookie_builder.build()
+}
+
+/// A cookie of a JSON cookie file
+/// Covers the fields of Chrome DevTools / Puppeteer (`expires`) and EditThisCookie (`expirationDate`).
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct JsonCookie {
+    name: String,
+    value: String,
+    domain: Option<String>,
+    path: Option<String>,
+    url: Option<String>,
+    #[serde(alias = "expirationDate")]
+    expires: Option<f64>,
+    #[serde(default)]
+    http_only: bool,
+    #[serde(default)]
+    secure: bool,
+    #[serde(default)]
+    session: bool,
+    same_site: Option<String>,
+    #[serde(default)]
+    host_only: bool,
+}
+
+/// JSON cookie files are either an array of cookies or an object with a `cookies` array
+/// (e.g. the storage state of Playwright)
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum JsonCookieFile {
+    Cookies(Vec<serde_json::Value>),
+    Object { cookies: Vec<serde_json::Value> },
+}
+
+/// Parse a JSON cookie file
+///
+/// # Arguments
+/// * `file_contents` - The contents of the cookie file
+///
+/// # Returns
+/// * The parsed cookie (or error) of every entry, or an error if the file is no JSON cookie file
+fn parse_json_cookie_file(
+    file_contents: &str,
+) -> Result<Vec<std::result::Result<CookieParam, CookieFileParseError>>> {
+    let file: JsonCookieFile = serde_json::from_str(file_contents.trim_start_matches('\u{feff}'))
+        .map_err(|err| {
+        CookieFileParseError::new(format!("Could not parse JSON cookie file: {}", err))
+    })?;
+    let (JsonCookieFile::Cookies(entries) | JsonCookieFile::Object { cookies: entries }) = file;
+
+    Ok(entries
+        .into_iter()
+        .enumerate()
+        .map(|(index, entry)| {
+            serde_json::from_value::<JsonCookie>(entry)
+                .map_err(|err| err.to_string())
+                .and_then(json_cookie_param)
+                .map_err(|message| {
+                    CookieFileParseError::new(format!(
+                        "Error parsing cookie {}: {}",
+                        index + 1,
+                        message
+                    ))
+                })
+        })
+        .collect())
+}
+
+/// Converts a cookie of a JSON cookie file
+fn json_cookie_param(json_cookie: JsonCookie) -> std::result::Result<CookieParam, String> {
+    let mut cookie_builder = CookieParam::builder()
+        .name(json_cookie.name.clone())
+        .value(json_cookie.value.clone())
+        .http_only(json_cookie.http_only)
+        .secure(json_cookie.secure);
+
+    let path = json_cookie.path.clone().unwrap_or_else(|| "/".to_string());
+    match (&json_cookie.domain, &json_cookie.url) {
+        // Cookies set through a URL without domain are only sent to that host
+        (Some(domain), _) if json_cookie.host_only => {
+            cookie_builder = cookie_builder.url(host_only_url(domain, &path, json_cookie.secure));
+        }
+        (Some(domain), _) => cookie_builder = cookie_builder.domain(domain.clone()),
+        (None, Some(url)) => cookie_builder = cookie_builder.url(url.clone()),
+        (None, None) => {
+            return Err(format!(
+                "Cookie {:?} has neither a domain nor a url",
+                json_cookie.name
+            ));
         }
+    }
+    cookie_builder = cookie_builder.path(path);
+
+    // Session cookies have no (or a negative) expiry
+    if let Some(expires) = json_cookie
+        .expires
+        .filter(|expires| !json_cookie.session && *expires > 0.0)
+    {
+        cookie_builder = cookie_builder.expires(TimeSinceEpoch::new(expires));
+    }
+    let same_site = match json_cookie
+        .same_site
+        .as_deref()
+        .map(str::to_ascii_lowercase)
+    {
+        Some(same_site) if same_site == "strict" => Some(CookieSameSite::Strict),
+        Some(same_site) if same_site == "lax" => Some(CookieSameSite::Lax),
+        Some(same_site) if same_site == "none" || same_site == "no_restriction" => {
+            Some(CookieSameSite::None)
+        }
+        _ => None,
+    };
+    if let Some(same_site) = same_site {
+        cookie_builder = cookie_builder.same_site(same_site);
+    }
+
+    let cookie = cookie_builder.build()?;
 
-        cookie_builder = cookie_builder
-            .domain(cookie_args[0].to_string())
-            .same_site(if cookie_args[1].eq("TRUE") {
-                CookieSameSite::Strict
+    tracing::trace!("Parsed JSON cookie {:?} to {:?}", json_cookie, cookie);
+
+    Ok(cookie)
+}
+
+/// The URL a host-only cookie is set through, as cookies set with a domain also apply to subdomains
+pub(crate) fn host_only_url(host: &str, path: &str, secure: bool) -> String {
+    format!(
+        "{}://{}{}",
+        if secure { "https" } else { "http" },
+        host.trim_start_matches('.'),
+        path
+    )
+}
+
+/// Serialize cookies into a cookie file
+/// As specified in https://curl.se/docs/http-cookies.html, session cookies get the expiry 0
+///
+/// # Arguments
+/// * `cookies` - The cookies, e.g. from `Browser::get_cookies`
+///
+/// # Returns
+/// * The contents of the cookie file
+pub fn format_cookie_file(cookies: &[Cookie]) -> String {
+    let mut file_contents = String::from("# Netscape HTTP Cookie File\n");
+    for cookie in cookies {
+        let flag = |value: bool| if value { "TRUE" } else { "FALSE" };
+        let expires = if cookie.session || cookie.expires < 0.0 {
+            0
+        } else {
+            cookie.expires as i64
+        };
+        file_contents.push_str(&format!(
+            "{}{}\t{}\t{}\t{}\t{}\t{}\t{}\n",
+            if cookie.http_only { "#HttpOnly_" } else { "" },
+            cookie.domain,
+            flag(cookie.domain.starts_with('.')),
+            cookie.path,
+            flag(cookie.secure),
+            expires,
+            cookie.name,
+            cookie.value
+        ));
+    }
+    file_contents
+}
+
+/// Convert cookies of a browser back into cookies that can be set,
+/// e.g. to copy them into another browser context
+///
+/// # Arguments
+/// * `cookies` - The cookies, e.g. from `Browser::get_cookies`
+///
+/// # Returns
+/// * The cookies to set
+pub fn cookie_params(cookies: Vec<Cookie>) -> Vec<CookieParam> {
+    cookies
+        .into_iter()
+        .map(|cookie| {
+            // Host-only cookies are stored without a leading dot and have to be set through a URL
+            let (url, domain) = if cookie.domain.starts_with('.') {
+                (None, Some(cookie.domain))
             } else {
-                CookieSameSite::Lax
-            })
-            .path(cookie_args[2].to_string())
-            .http_only(cookie_args[3].eq("TRUE"))
-            .expires(TimeSinceEpoch::new(match cookie_args[4].parse::<f64>() {
-                Ok(value) => value,
-                Err(err) => {
-                    return Err(Box::new(CookieFileParseError::new(format!(
-                        "Error parsing cookie line: '{}' Could not convert time: '{}'",
-                        line, err
-                    ))));
-                }
-            }))
-            .name(cookie_args[5].to_string())
-            .value(cookie_args[6].to_string());
-
-        let cookie = cookie_builder.build()?;
-
-        tracing::trace!("Parsed cookie line: {:?} to {:?}", line_unchanged, cookie);
+                (
+                    Some(host_only_url(&cookie.domain, &cookie.path, cookie.secure)),
+                    None,
+                )
+            };
+            CookieParam {
+                name: cookie.name,
+                value: cookie.value,
+                url,
+                domain,
+                path: Some(cookie.path),
+                secure: Some(cookie.secure),
+                http_only: Some(cookie.http_only),
+                same_site: cookie.same_site,
+                expires: (!cookie.session && cookie.expires > 0.0)
+                    .then(|| TimeSinceEpoch::new(cookie.expires)),
+                priority: Some(cookie.priority),
+                same_party: None,
+                source_scheme: Some(cookie.source_scheme),
+                source_port: Some(cookie.source_port),
+                partition_key: cookie.partition_key,
+            }
+        })
+        .collect()
+}
 
-        cookies.push(cookie);
+/// The file name of a URL, i.e. its last non-empty path segment (percent-decoded)
+///
+/// # Arguments
+/// * `url` - The URL, e.g. "https://example.com/data/report%202024.csv"
+///
+/// # Returns
+/// * The file name, e.g. "report 2024.csv", or None if the URL has no path
+pub fn url_file_name(url: &str) -> Option<String> {
+    let url = url::Url::parse(url).ok()?;
+    let name = url.path_segments()?.rfind(|segment| !segment.is_empty())?;
+    Some(
+        percent_encoding::percent_decode_str(name)
+            .decode_utf8_lossy()
+            .into_owned(),
+    )
+}
+
+/// Derives a short name for the document at a URL, e.g. for files created from it
+///
+/// # Arguments
+/// * `url` - The URL, e.g. "https://example.com/blog/post.html"
+///
+/// # Returns
+/// * The file name without extension, e.g. "post", the host for URLs without path or "page"
+pub fn url_base_name(url: &str) -> String {
+    if let Some(name) = url_file_name(url) {
+        let stem = name
+            .rsplit_once('.')
+            .map_or(name.as_str(), |(stem, _)| stem);
+        if !stem.is_empty() {
+            return stem.to_string();
+        }
     }
-    Ok(cookies)
+    url::Url::parse(url)
+        .ok()
+        .and_then(|url| url.host_str().map(str::to_string))
+        .unwrap_or_else(|| "page".to_string())
+}
+
+/// Javascript returning the rendered text of the page, see `content_hash`
+pub const CONTENT_TEXT_JS: &str = "document.body ? document.body.innerText : ''";
+
+/// A hash of the text content of a page, to detect changed pages.
+/// Whitespace is normalized, so changes of the layout alone don't change the hash.
+///
+/// # Arguments
+/// * `text` - The rendered text, e.g. from `CONTENT_TEXT_JS`
+///
+/// # Returns
+/// * The hex encoded SHA-1 hash
+pub fn content_hash(text: &str) -> String {
+    let mut hasher = Sha1::new();
+    for (index, word) in text.split_whitespace().enumerate() {
+        if index > 0 {
+            hasher.update(b" ");
+        }
+        hasher.update(word.as_bytes());
+    }
+    hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
 }
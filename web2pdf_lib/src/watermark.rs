@@ -0,0 +1,349 @@
+use std::fmt;
+
+use lopdf::{dictionary, Dictionary, Document, Object, ObjectId, Stream};
+
+use crate::pdf::{
+    encode_win_ansi, helvetica_width, inherited_attribute, resolve_dictionary, save_to_vec,
+};
+use crate::Result;
+
+/// Error for when a watermark cannot be applied
+#[derive(Debug, Clone)]
+struct WatermarkError {
+    error_message: String,
+}
+impl fmt::Display for WatermarkError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Error applying watermark: {}", self.error_message)
+    }
+}
+impl std::error::Error for WatermarkError {}
+
+/// Distance of watermarks placed at an edge of the page to the edge, in points (0.5 inch)
+const EDGE_DISTANCE: f64 = 36.0;
+
+/// What is stamped onto the pages
+#[derive(Debug, Clone)]
+pub enum WatermarkContent {
+    /// A single line of text in Helvetica, characters outside of Latin-1 are replaced by `?`
+    Text { text: String, font_size: f64 },
+    /// The first page of a PDF, e.g. a letterhead or a stamp
+    Pdf(Vec<u8>),
+}
+
+/// Position of a watermark on the page
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WatermarkPosition {
+    #[default]
+    Center,
+    Top,
+    Bottom,
+    Left,
+    Right,
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+impl WatermarkPosition {
+    /// Horizontal and vertical alignment, -1 for left/bottom, 0 for center, 1 for right/top
+    fn alignment(self) -> (f64, f64) {
+        match self {
+            WatermarkPosition::Center => (0.0, 0.0),
+            WatermarkPosition::Top => (0.0, 1.0),
+            WatermarkPosition::Bottom => (0.0, -1.0),
+            WatermarkPosition::Left => (-1.0, 0.0),
+            WatermarkPosition::Right => (1.0, 0.0),
+            WatermarkPosition::TopLeft => (-1.0, 1.0),
+            WatermarkPosition::TopRight => (1.0, 1.0),
+            WatermarkPosition::BottomLeft => (-1.0, -1.0),
+            WatermarkPosition::BottomRight => (1.0, -1.0),
+        }
+    }
+}
+
+/// A watermark stamped on top of every page
+#[derive(Debug, Clone)]
+pub struct Watermark {
+    pub content: WatermarkContent,
+    pub position: WatermarkPosition,
+    /// Counterclockwise rotation in degrees
+    pub rotation: f64,
+    /// Opacity from 0 (invisible) to 1 (opaque)
+    pub opacity: f64,
+}
+
+/// Stamps a watermark onto every page of a PDF
+///
+/// The watermark is added as form XObject drawn after the page content, so it covers the page.
+///
+/// # Arguments
+/// * `pdf` - The PDF data
+/// * `watermark` - The watermark
+///
+/// # Returns
+/// * The watermarked PDF data
+pub fn apply(pdf: &[u8], watermark: &Watermark) -> Result<Vec<u8>> {
+    let mut document = Document::load_mem(pdf)?;
+
+    let form = match &watermark.content {
+        WatermarkContent::Text { text, font_size } => text_form(text, *font_size),
+        WatermarkContent::Pdf(overlay) => pdf_form(&mut document, overlay)?,
+    };
+    let bbox =
+        rectangle(&form.dict, b"BBox").ok_or_else(|| watermark_error("invalid bounding box"))?;
+    let form_id = document.add_object(form);
+    let ext_g_state_id = document.add_object(dictionary! {
+        "Type" => "ExtGState",
+        "ca" => watermark.opacity.clamp(0.0, 1.0) as f32,
+        "CA" => watermark.opacity.clamp(0.0, 1.0) as f32,
+    });
+    // The page content may leave the graphics state modified, so it is wrapped in q/Q
+    let save_id = document.add_object(Stream::new(Dictionary::new(), b"q\n".to_vec()));
+    // Names in the page resources, unique so several watermarks can be applied
+    let xobject_name = format!("Web2PdfWatermark{}", form_id.0);
+    let ext_g_state_name = format!("Web2PdfWatermarkGS{}", form_id.0);
+
+    let page_ids: Vec<ObjectId> = document.get_pages().into_values().collect();
+    for page_id in page_ids {
+        let page_box = page_attribute(&document, page_id, b"CropBox")
+            .or_else(|| page_attribute(&document, page_id, b"MediaBox"))
+            .and_then(|page_box| array_rectangle(&document, &page_box))
+            .ok_or_else(|| watermark_error("page without media box"))?;
+        let edge_distance = match watermark.content {
+            WatermarkContent::Text { .. } => EDGE_DISTANCE,
+            WatermarkContent::Pdf(_) => 0.0,
+        };
+        let matrix = placement(bbox, page_box, watermark, edge_distance);
+        let stamp = format!(
+            "Q\nq /{} gs {} cm /{} Do Q\n",
+            ext_g_state_name,
+            matrix
+                .iter()
+                .map(|value| format!("{:.4}", value))
+                .collect::<Vec<_>>()
+                .join(" "),
+            xobject_name
+        );
+        let mut stamp = Stream::new(Dictionary::new(), stamp.into_bytes());
+        let _ = stamp.compress();
+        let stamp_id = document.add_object(stamp);
+
+        let mut resources = page_attribute(&document, page_id, b"Resources")
+            .and_then(|resources| resolve_dictionary(&document, &resources).cloned())
+            .unwrap_or_default();
+        add_resource(
+            &document,
+            &mut resources,
+            b"XObject",
+            &xobject_name,
+            form_id,
+        );
+        add_resource(
+            &document,
+            &mut resources,
+            b"ExtGState",
+            &ext_g_state_name,
+            ext_g_state_id,
+        );
+
+        let page = document.get_dictionary_mut(page_id)?;
+        let mut contents = vec![Object::Reference(save_id)];
+        match page.get(b"Contents") {
+            Ok(Object::Array(streams)) => contents.extend(streams.iter().cloned()),
+            Ok(stream) => contents.push(stream.clone()),
+            Err(_) => {}
+        }
+        contents.push(Object::Reference(stamp_id));
+        page.set("Contents", contents);
+        page.set("Resources", resources);
+    }
+
+    if let WatermarkContent::Pdf(_) = watermark.content {
+        // Drops the objects of the watermark PDF not used by the form
+        document.prune_objects();
+    }
+    save_to_vec(&mut document)
+}
+
+fn watermark_error(message: &str) -> Box<dyn std::error::Error> {
+    Box::new(WatermarkError {
+        error_message: message.to_string(),
+    })
+}
+
+/// Creates a form XObject showing a single line of text, its origin is the bottom left corner
+fn text_form(text: &str, font_size: f64) -> Stream {
+    let encoded = encode_win_ansi(text);
+    let width: f64 = encoded
+        .iter()
+        .map(|&byte| f64::from(helvetica_width(byte)) * font_size / 1000.0)
+        .sum();
+    // Helvetica's descender and ascender, relative to the font size
+    let descent = 0.207 * font_size;
+    let height = (0.718 + 0.207) * font_size;
+
+    let mut content = format!("0.5 g BT /F1 {} Tf 0 {:.4} Td (", font_size, descent).into_bytes();
+    for byte in encoded {
+        if matches!(byte, b'(' | b')' | b'\\') {
+            content.push(b'\\');
+        }
+        content.push(byte);
+    }
+    content.extend(b") Tj ET");
+
+    let mut form = Stream::new(
+        dictionary! {
+            "Type" => "XObject",
+            "Subtype" => "Form",
+            "BBox" => vec![0.into(), 0.into(), (width as f32).into(), (height as f32).into()],
+            "Resources" => dictionary! {
+                "Font" => dictionary! {
+                    "F1" => dictionary! {
+                        "Type" => "Font",
+                        "Subtype" => "Type1",
+                        "BaseFont" => "Helvetica",
+                        "Encoding" => "WinAnsiEncoding",
+                    },
+                },
+            },
+        },
+        content,
+    );
+    let _ = form.compress();
+    form
+}
+
+/// Creates a form XObject of the first page of another PDF, whose objects are copied into `document`
+fn pdf_form(document: &mut Document, overlay: &[u8]) -> Result<Stream> {
+    let mut overlay = Document::load_mem(overlay)?;
+    overlay.renumber_objects_with(document.max_id + 1);
+    document.max_id = overlay.max_id;
+
+    let page_id = *overlay
+        .get_pages()
+        .values()
+        .next()
+        .ok_or_else(|| watermark_error("the watermark PDF has no pages"))?;
+    let bbox = page_attribute(&overlay, page_id, b"CropBox")
+        .or_else(|| page_attribute(&overlay, page_id, b"MediaBox"))
+        .and_then(|bbox| array_rectangle(&overlay, &bbox))
+        .ok_or_else(|| watermark_error("the watermark PDF has no media box"))?;
+    let resources = page_attribute(&overlay, page_id, b"Resources")
+        .unwrap_or(Object::Dictionary(Dictionary::new()));
+    // The form has its own resources, so the page content can be used unchanged
+    let content = overlay.get_page_content(page_id);
+
+    // Only the objects used by the form are kept when saving
+    for (id, object) in overlay.objects {
+        match object.type_name().unwrap_or(b"") {
+            b"Catalog" | b"Pages" | b"Page" | b"Outlines" | b"Outline" => {}
+            _ => {
+                document.objects.insert(id, object);
+            }
+        }
+    }
+
+    let mut form = Stream::new(
+        dictionary! {
+            "Type" => "XObject",
+            "Subtype" => "Form",
+            "BBox" => bbox.iter().map(|&value| Object::Real(value as f32)).collect::<Vec<_>>(),
+            "Resources" => resources,
+        },
+        content,
+    );
+    let _ = form.compress();
+    Ok(form)
+}
+
+/// The transformation matrix placing a form with the bounding box `bbox` on a page
+fn placement(
+    bbox: [f64; 4],
+    page_box: [f64; 4],
+    watermark: &Watermark,
+    edge_distance: f64,
+) -> [f64; 6] {
+    let (cos, sin) = {
+        let angle = watermark.rotation.to_radians();
+        (angle.cos(), angle.sin())
+    };
+    let (width, height) = (bbox[2] - bbox[0], bbox[3] - bbox[1]);
+    let center = ((bbox[0] + bbox[2]) / 2.0, (bbox[1] + bbox[3]) / 2.0);
+    // Half the size of the rotated bounding box
+    let extent = (
+        (width * cos).abs() / 2.0 + (height * sin).abs() / 2.0,
+        (width * sin).abs() / 2.0 + (height * cos).abs() / 2.0,
+    );
+
+    let (horizontal, vertical) = watermark.position.alignment();
+    let target = (
+        (page_box[0] + page_box[2]) / 2.0
+            + horizontal * ((page_box[2] - page_box[0]) / 2.0 - edge_distance - extent.0),
+        (page_box[1] + page_box[3]) / 2.0
+            + vertical * ((page_box[3] - page_box[1]) / 2.0 - edge_distance - extent.1),
+    );
+
+    // Moves the center of the form to the origin, rotates it and moves it to the target
+    [
+        cos,
+        sin,
+        -sin,
+        cos,
+        target.0 - (cos * center.0 - sin * center.1),
+        target.1 - (sin * center.0 + cos * center.1),
+    ]
+}
+
+/// Adds a named resource to a resource dictionary, resolving shared category dictionaries
+fn add_resource(
+    document: &Document,
+    resources: &mut Dictionary,
+    category: &[u8],
+    name: &str,
+    id: ObjectId,
+) {
+    let mut entries = resources
+        .get(category)
+        .ok()
+        .and_then(|entries| resolve_dictionary(document, entries))
+        .cloned()
+        .unwrap_or_default();
+    entries.set(name, id);
+    resources.set(category, entries);
+}
+
+/// An attribute of a page, which may be inherited from the page tree
+fn page_attribute(document: &Document, page_id: ObjectId, key: &[u8]) -> Option<Object> {
+    document
+        .get_dictionary(page_id)
+        .ok()?
+        .get(key)
+        .ok()
+        .cloned()
+        .or_else(|| inherited_attribute(document, page_id, key))
+}
+
+/// Reads a rectangle (`[llx lly urx ury]`) from a dictionary
+fn rectangle(dictionary: &Dictionary, key: &[u8]) -> Option<[f64; 4]> {
+    match dictionary.get(key).ok()? {
+        Object::Array(values) => numbers(values),
+        _ => None,
+    }
+}
+
+/// Reads a rectangle, which may be a reference, as normalized `[llx lly urx ury]`
+fn array_rectangle(document: &Document, rectangle: &Object) -> Option<[f64; 4]> {
+    let (_, rectangle) = document.dereference(rectangle).ok()?;
+    let [x0, y0, x1, y1] = numbers(rectangle.as_array().ok()?)?;
+    Some([x0.min(x1), y0.min(y1), x0.max(x1), y0.max(y1)])
+}
+
+fn numbers(values: &[Object]) -> Option<[f64; 4]> {
+    let values: Vec<f64> = values
+        .iter()
+        .map(|value| value.as_float().map(f64::from).ok())
+        .collect::<Option<_>>()?;
+    values.try_into().ok()
+}